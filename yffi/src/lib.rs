@@ -9,17 +9,22 @@ use std::ptr::{null, null_mut};
 use std::rc::Rc;
 use yrs::block::{ClientID, ItemContent, Prelim};
 use yrs::types::array::ArrayEvent;
+use yrs::types::counter::CounterEvent;
 use yrs::types::map::MapEvent;
 use yrs::types::text::TextEvent;
-use yrs::types::xml::{XmlEvent, XmlTextEvent};
+use yrs::types::tree::TreeEvent;
+use yrs::types::weak::WeakEvent;
+use yrs::types::xml::{XmlEvent, XmlFragmentEvent, XmlHookEvent, XmlTextEvent};
 use yrs::types::{
     Attrs, BranchPtr, Change, Delta, EntryChange, Event, PathSegment, Value, TYPE_REFS_ARRAY,
-    TYPE_REFS_MAP, TYPE_REFS_TEXT, TYPE_REFS_XML_ELEMENT, TYPE_REFS_XML_TEXT,
+    TYPE_REFS_COUNTER, TYPE_REFS_MAP, TYPE_REFS_TEXT, TYPE_REFS_TREE, TYPE_REFS_WEAK,
+    TYPE_REFS_XML_ELEMENT, TYPE_REFS_XML_FRAGMENT, TYPE_REFS_XML_HOOK, TYPE_REFS_XML_TEXT,
 };
 use yrs::updates::decoder::{Decode, DecoderV1, DecoderV2};
 use yrs::updates::encoder::{Encode, Encoder, EncoderV1, EncoderV2};
 use yrs::{
-    AfterTransactionEvent, Array, DeleteSet, Map, OffsetKind, Text, Update, XmlElement, XmlText,
+    AfterTransactionEvent, Array, Counter, DeleteSet, Map, OffsetKind, Text, Tree, Update,
+    WeakLink, XmlElement, XmlFragment, XmlHook, XmlText,
 };
 use yrs::{Options, StateVector};
 use yrs::{SubscriptionId, Xml};
@@ -68,6 +73,21 @@ pub const Y_XML_ELEM: i8 = 4;
 /// Flag used by `YInput` and `YOutput` to tag content, which is an `YXmlText` shared type.
 pub const Y_XML_TEXT: i8 = 5;
 
+/// Flag used by `YOutput` to tag content, which is an `YWeakLink` shared type.
+pub const Y_WEAK_LINK: i8 = 6;
+
+/// Flag used by `YOutput` to tag content, which is an `YCounter` shared type.
+pub const Y_COUNTER: i8 = 7;
+
+/// Flag used by `YOutput` to tag content, which is an `YTree` shared type.
+pub const Y_TREE: i8 = 8;
+
+/// Flag used by `YOutput` to tag content, which is an `YXmlFragment` shared type.
+pub const Y_XML_FRAG: i8 = 9;
+
+/// Flag used by `YOutput` to tag content, which is an `YXmlHook` shared type.
+pub const Y_XML_HOOK: i8 = 10;
+
 /// Flag used to mark a truthy boolean numbers.
 pub const Y_TRUE: c_char = 1;
 
@@ -86,6 +106,10 @@ pub const Y_OFFSET_UTF16: c_int = 1;
 /// by UTF-32 chars of encoded string.
 pub const Y_OFFSET_UTF32: c_int = 2;
 
+/// Flag used by `YOptions` to determine, that text operations offsets and length will be counted by
+/// grapheme clusters (user-perceived characters) of encoded string.
+pub const Y_OFFSET_GRAPHEME: c_int = 3;
+
 /* pub types below are used by cbindgen for c header generation */
 
 /// A Yrs document type. Documents are most important units of collaborative resources management.
@@ -192,6 +216,7 @@ pub struct YOptions {
     /// - `Y_ENCODING_BYTES`
     /// - `Y_ENCODING_UTF16`
     /// - `Y_ENCODING_UTF32`
+    /// - `Y_OFFSET_GRAPHEME`
     pub encoding: c_int,
 
     /// Boolean flag used to determine if deleted blocks should be garbage collected or not
@@ -205,6 +230,7 @@ impl Into<Options> for YOptions {
             Y_OFFSET_BYTES => OffsetKind::Bytes,
             Y_OFFSET_UTF16 => OffsetKind::Utf16,
             Y_OFFSET_UTF32 => OffsetKind::Utf32,
+            Y_OFFSET_GRAPHEME => OffsetKind::Grapheme,
             _ => panic!("Unrecognized YOptions.encoding type"),
         };
         Options {
@@ -451,6 +477,34 @@ pub unsafe extern "C" fn yxmltext(txn: *mut Transaction, name: *const c_char) ->
     txn.as_mut().unwrap().get_xml_text(name).into_raw_branch()
 }
 
+/// Gets or creates a new shared `YCounter` data type instance as a root-level type of a given
+/// document. This structure can later be accessed using its `name`, which must be a null-terminated
+/// UTF-8 compatible string.
+#[no_mangle]
+pub unsafe extern "C" fn ycounter(txn: *mut Transaction, name: *const c_char) -> *mut Branch {
+    assert!(!txn.is_null());
+    assert!(!name.is_null());
+
+    let name = CStr::from_ptr(name).to_str().unwrap();
+    txn.as_mut().unwrap().get_counter(name).into_raw_branch()
+}
+
+/// Gets or creates a new shared `YXmlFragment` data type instance as a root-level type of a given
+/// document. This structure can later be accessed using its `name`, which must be a null-terminated
+/// UTF-8 compatible string. XML fragments are used as root containers by rich text editor
+/// bindings, such as y-prosemirror.
+#[no_mangle]
+pub unsafe extern "C" fn yxmlfragment(txn: *mut Transaction, name: *const c_char) -> *mut Branch {
+    assert!(!txn.is_null());
+    assert!(!name.is_null());
+
+    let name = CStr::from_ptr(name).to_str().unwrap();
+    txn.as_mut()
+        .unwrap()
+        .get_xml_fragment(name)
+        .into_raw_branch()
+}
+
 /// Returns a state vector of a current transaction's document, serialized using lib0 version 1
 /// encoding. Payload created by this function can then be send over the network to a remote peer,
 /// where it can be used as a parameter of [ytransaction_state_diff_v1] in order to produce a delta
@@ -1076,6 +1130,31 @@ pub unsafe extern "C" fn ymap_insert(
     map.insert(txn, key, value.read());
 }
 
+/// Returns a current value of a `counter`.
+#[no_mangle]
+pub unsafe extern "C" fn ycounter_get(counter: *const Branch) -> c_longlong {
+    assert!(!counter.is_null());
+
+    let counter = Counter::from_raw_branch(counter);
+    counter.get() as c_longlong
+}
+
+/// Increments a value of a `counter` by a given `delta`. Negative `delta` can be used to
+/// decrement it. Concurrent increments made by different peers are always added up together.
+#[no_mangle]
+pub unsafe extern "C" fn ycounter_increment(
+    counter: *const Branch,
+    txn: *mut Transaction,
+    delta: c_longlong,
+) {
+    assert!(!counter.is_null());
+    assert!(!txn.is_null());
+
+    let counter = Counter::from_raw_branch(counter);
+    let txn = txn.as_mut().unwrap();
+    counter.increment(txn, delta as i64);
+}
+
 /// Removes a `map` entry, given its `key`. Returns `1` if the corresponding entry was successfully
 /// removed or `0` if no entry with a provided `key` has been found inside of a `map`.
 ///
@@ -1303,6 +1382,7 @@ pub unsafe extern "C" fn yxmlelem_next_sibling(xml: *const Branch) -> *mut YOutp
         match next {
             Xml::Element(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlElement(v)))),
             Xml::Text(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlText(v)))),
+            Xml::Hook(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlHook(v)))),
         }
     } else {
         std::ptr::null_mut()
@@ -1324,6 +1404,7 @@ pub unsafe extern "C" fn yxmlelem_prev_sibling(xml: *const Branch) -> *mut YOutp
         match next {
             Xml::Element(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlElement(v)))),
             Xml::Text(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlText(v)))),
+            Xml::Hook(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlHook(v)))),
         }
     } else {
         std::ptr::null_mut()
@@ -1347,6 +1428,7 @@ pub unsafe extern "C" fn yxmltext_next_sibling(xml: *const Branch) -> *mut YOutp
         match next {
             Xml::Element(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlElement(v)))),
             Xml::Text(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlText(v)))),
+            Xml::Hook(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlHook(v)))),
         }
     } else {
         std::ptr::null_mut()
@@ -1368,6 +1450,7 @@ pub unsafe extern "C" fn yxmltext_prev_sibling(xml: *const Branch) -> *mut YOutp
         match next {
             Xml::Element(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlElement(v)))),
             Xml::Text(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlText(v)))),
+            Xml::Hook(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlHook(v)))),
         }
     } else {
         std::ptr::null_mut()
@@ -1416,6 +1499,7 @@ pub unsafe extern "C" fn yxmlelem_first_child(xml: *const Branch) -> *mut YOutpu
         match value {
             Xml::Element(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlElement(v)))),
             Xml::Text(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlText(v)))),
+            Xml::Hook(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlHook(v)))),
         }
     } else {
         std::ptr::null_mut()
@@ -1457,6 +1541,7 @@ pub unsafe extern "C" fn yxmlelem_tree_walker_next(iterator: *mut TreeWalker) ->
         match next {
             Xml::Element(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlElement(v)))),
             Xml::Text(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlText(v)))),
+            Xml::Hook(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlHook(v)))),
         }
     } else {
         std::ptr::null_mut()
@@ -1489,6 +1574,43 @@ pub unsafe extern "C" fn yxmlelem_insert_elem(
     xml.insert_elem(txn, index as u32, name).into_raw_branch()
 }
 
+/// Inserts an `YXmlHook` as a child of a current node at the given `index` and returns its
+/// pointer. Entries of a hook can be modified using `ymap_*` functions.
+///
+/// An `index` value must be between 0 and (inclusive) length of a current XML element (use
+/// [yxmlelem_child_len] function to determine its length).
+///
+/// A `name` must be a null-terminated UTF-8 encoded string, which will be copied into current
+/// document. Therefore `name` should be freed by the function caller.
+#[no_mangle]
+pub unsafe extern "C" fn yxmlelem_insert_hook(
+    xml: *const Branch,
+    txn: *mut Transaction,
+    index: c_int,
+    name: *const c_char,
+) -> *mut Branch {
+    assert!(!xml.is_null());
+    assert!(!txn.is_null());
+    assert!(!name.is_null());
+
+    let xml = XmlElement::from_raw_branch(xml);
+    let txn = txn.as_mut().unwrap();
+
+    let name = CStr::from_ptr(name).to_str().unwrap();
+    xml.insert_hook(txn, index as u32, name).into_raw_branch()
+}
+
+/// Returns a name of a current `YXmlHook`.
+///
+/// Returned value is a null-terminated UTF-8 string, which must be released using [ystring_destroy]
+/// function.
+#[no_mangle]
+pub unsafe extern "C" fn yxmlhook_name(hook: *const Branch) -> *mut c_char {
+    assert!(!hook.is_null());
+    let hook = XmlHook::from_raw_branch(hook);
+    CString::new(hook.hook_name()).unwrap().into_raw()
+}
+
 /// Inserts an `YXmlText` as a child of a current node at the given `index` and returns its
 /// pointer.
 ///
@@ -1542,6 +1664,7 @@ pub unsafe extern "C" fn yxmlelem_get(xml: *const Branch, index: c_int) -> *cons
         match child {
             Xml::Element(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlElement(v)))),
             Xml::Text(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlText(v)))),
+            Xml::Hook(v) => Box::into_raw(Box::new(YOutput::from(Value::YXmlHook(v)))),
         }
     } else {
         std::ptr::null()
@@ -1968,6 +2091,11 @@ pub struct YOutput {
     /// - [Y_MAP] for pointers to `YMap` data types.
     /// - [Y_XML_ELEM] for pointers to `YXmlElement` data types.
     /// - [Y_XML_TEXT] for pointers to `YXmlText` data types.
+    /// - [Y_WEAK_LINK] for pointers to `YWeakLink` data types.
+    /// - [Y_COUNTER] for pointers to `YCounter` data types.
+    /// - [Y_TREE] for pointers to `YTree` data types.
+    /// - [Y_XML_FRAG] for pointers to `YXmlFragment` data types.
+    /// - [Y_XML_HOOK] for pointers to `YXmlHook` data types.
     pub tag: i8,
 
     /// Length of the contents stored by a current `YOutput` cell.
@@ -2036,6 +2164,16 @@ impl std::fmt::Display for YOutput {
                 write!(f, "YXmlText")
             } else if tag == Y_XML_ELEM {
                 write!(f, "YXmlElement",)
+            } else if tag == Y_WEAK_LINK {
+                write!(f, "YWeakLink")
+            } else if tag == Y_COUNTER {
+                write!(f, "YCounter")
+            } else if tag == Y_TREE {
+                write!(f, "YTree")
+            } else if tag == Y_XML_FRAG {
+                write!(f, "YXmlFragment")
+            } else if tag == Y_XML_HOOK {
+                write!(f, "YXmlHook")
             } else if tag == Y_JSON_BUF {
                 write!(f, "YBinary(len: {})", self.len)
             } else {
@@ -2082,7 +2220,12 @@ impl From<Value> for YOutput {
             Value::YArray(v) => Self::from(v),
             Value::YMap(v) => Self::from(v),
             Value::YXmlElement(v) => Self::from(v),
+            Value::YXmlFragment(v) => Self::from(v),
             Value::YXmlText(v) => Self::from(v),
+            Value::YXmlHook(v) => Self::from(v),
+            Value::YWeakLink(v) => Self::from(v),
+            Value::YCounter(v) => Self::from(v),
+            Value::YTree(v) => Self::from(v),
         }
     }
 }
@@ -2216,6 +2359,30 @@ impl From<XmlElement> for YOutput {
     }
 }
 
+impl From<XmlFragment> for YOutput {
+    fn from(v: XmlFragment) -> Self {
+        YOutput {
+            tag: Y_XML_FRAG,
+            len: 1,
+            value: YOutputContent {
+                y_type: v.into_raw_branch(),
+            },
+        }
+    }
+}
+
+impl From<XmlHook> for YOutput {
+    fn from(v: XmlHook) -> Self {
+        YOutput {
+            tag: Y_XML_HOOK,
+            len: 1,
+            value: YOutputContent {
+                y_type: v.into_raw_branch(),
+            },
+        }
+    }
+}
+
 impl From<XmlText> for YOutput {
     fn from(v: XmlText) -> Self {
         YOutput {
@@ -2228,6 +2395,42 @@ impl From<XmlText> for YOutput {
     }
 }
 
+impl From<WeakLink> for YOutput {
+    fn from(v: WeakLink) -> Self {
+        YOutput {
+            tag: Y_WEAK_LINK,
+            len: 1,
+            value: YOutputContent {
+                y_type: v.into_raw_branch(),
+            },
+        }
+    }
+}
+
+impl From<Counter> for YOutput {
+    fn from(v: Counter) -> Self {
+        YOutput {
+            tag: Y_COUNTER,
+            len: 1,
+            value: YOutputContent {
+                y_type: v.into_raw_branch(),
+            },
+        }
+    }
+}
+
+impl From<Tree> for YOutput {
+    fn from(v: Tree) -> Self {
+        YOutput {
+            tag: Y_TREE,
+            len: 1,
+            value: YOutputContent {
+                y_type: v.into_raw_branch(),
+            },
+        }
+    }
+}
+
 #[repr(C)]
 union YOutputContent {
     flag: c_char,
@@ -2621,6 +2824,82 @@ pub unsafe extern "C" fn youtput_read_yxmltext(val: *const YOutput) -> *mut Bran
     }
 }
 
+/// Attempts to read the value for a given `YOutput` pointer as an `YWeakLink`.
+///
+/// Returns a null pointer in case when a value stored under current `YOutput` cell
+/// is not an `YWeakLink`. Underlying heap resources are released automatically as part of
+/// [youtput_destroy] destructor.
+#[no_mangle]
+pub unsafe extern "C" fn youtput_read_yweak(val: *const YOutput) -> *mut Branch {
+    let v = val.as_ref().unwrap();
+    if v.tag == Y_WEAK_LINK {
+        v.value.y_type
+    } else {
+        std::ptr::null_mut()
+    }
+}
+
+/// Attempts to read the value for a given `YOutput` pointer as an `YCounter`.
+///
+/// Returns a null pointer in case when a value stored under current `YOutput` cell
+/// is not an `YCounter`. Underlying heap resources are released automatically as part of
+/// [youtput_destroy] destructor.
+#[no_mangle]
+pub unsafe extern "C" fn youtput_read_ycounter(val: *const YOutput) -> *mut Branch {
+    let v = val.as_ref().unwrap();
+    if v.tag == Y_COUNTER {
+        v.value.y_type
+    } else {
+        std::ptr::null_mut()
+    }
+}
+
+/// Attempts to read the value for a given `YOutput` pointer as an `YTree`.
+///
+/// Returns a null pointer in case when a value stored under current `YOutput` cell
+/// is not an `YTree`. Underlying heap resources are released automatically as part of
+/// [youtput_destroy] destructor.
+#[no_mangle]
+pub unsafe extern "C" fn youtput_read_ytree(val: *const YOutput) -> *mut Branch {
+    let v = val.as_ref().unwrap();
+    if v.tag == Y_TREE {
+        v.value.y_type
+    } else {
+        std::ptr::null_mut()
+    }
+}
+
+/// Attempts to read the value for a given `YOutput` pointer as an `YXmlFragment`.
+///
+/// Returns a null pointer in case when a value stored under current `YOutput` cell
+/// is not an `YXmlFragment`. Underlying heap resources are released automatically as part of
+/// [youtput_destroy] destructor.
+#[no_mangle]
+pub unsafe extern "C" fn youtput_read_yxmlfragment(val: *const YOutput) -> *mut Branch {
+    let v = val.as_ref().unwrap();
+    if v.tag == Y_XML_FRAG {
+        v.value.y_type
+    } else {
+        std::ptr::null_mut()
+    }
+}
+
+/// Attempts to read the value for a given `YOutput` pointer as an `YXmlHook`. Entries of a hook
+/// can be accessed using `ymap_*` functions.
+///
+/// Returns a null pointer in case when a value stored under current `YOutput` cell
+/// is not an `YXmlHook`. Underlying heap resources are released automatically as part of
+/// [youtput_destroy] destructor.
+#[no_mangle]
+pub unsafe extern "C" fn youtput_read_yxmlhook(val: *const YOutput) -> *mut Branch {
+    let v = val.as_ref().unwrap();
+    if v.tag == Y_XML_HOOK {
+        v.value.y_type
+    } else {
+        std::ptr::null_mut()
+    }
+}
+
 /// Subscribes a given callback function `cb` to changes made by this `YText` instance. Callbacks
 /// are triggered whenever a `ytransaction_commit` is called.
 /// Returns a subscription ID which can be then used to unsubscribe this callback by using
@@ -2726,6 +3005,48 @@ pub unsafe extern "C" fn yxmltext_observe(
     subscription_id as c_uint
 }
 
+/// Subscribes a given callback function `cb` to changes of the content referenced by this
+/// `YWeakLink` instance. Callbacks are triggered whenever a `ytransaction_commit` is called.
+/// Returns a subscription ID which can be then used to unsubscribe this callback by using
+/// `yweak_unobserve` function.
+#[no_mangle]
+pub unsafe extern "C" fn yweak_observe(
+    weak: *const Branch,
+    state: *mut c_void,
+    cb: extern "C" fn(*mut c_void, *const YWeakLinkEvent),
+) -> c_uint {
+    assert!(!weak.is_null());
+
+    let mut weak = WeakLink::from_raw_branch(weak);
+    let observer = weak.observe(move |txn, e| {
+        let e = YWeakLinkEvent::new(e, txn);
+        cb(state, &e as *const YWeakLinkEvent);
+    });
+    let subscription_id: u32 = observer.into();
+    subscription_id as c_uint
+}
+
+/// Subscribes a given callback function `cb` to changes made by this `YCounter` instance.
+/// Callbacks are triggered whenever a `ytransaction_commit` is called.
+/// Returns a subscription ID which can be then used to unsubscribe this callback by using
+/// `ycounter_unobserve` function.
+#[no_mangle]
+pub unsafe extern "C" fn ycounter_observe(
+    counter: *const Branch,
+    state: *mut c_void,
+    cb: extern "C" fn(*mut c_void, *const YCounterEvent),
+) -> c_uint {
+    assert!(!counter.is_null());
+
+    let mut counter = Counter::from_raw_branch(counter);
+    let observer = counter.observe(move |txn, e| {
+        let e = YCounterEvent::new(e, txn);
+        cb(state, &e as *const YCounterEvent);
+    });
+    let subscription_id: u32 = observer.into();
+    subscription_id as c_uint
+}
+
 /// Subscribes a given callback function `cb` to changes made by this shared type instance as well
 /// as all nested shared types living within it. Callbacks are triggered whenever a
 /// `ytransaction_commit` is called.
@@ -2902,6 +3223,11 @@ pub struct YEvent {
     /// - [Y_MAP] for pointers to `YMap` data types.
     /// - [Y_XML_ELEM] for pointers to `YXmlElement` data types.
     /// - [Y_XML_TEXT] for pointers to `YXmlText` data types.
+    /// - [Y_WEAK_LINK] for pointers to `YWeakLink` data types.
+    /// - [Y_COUNTER] for pointers to `YCounter` data types.
+    /// - [Y_TREE] for pointers to `YTree` data types.
+    /// - [Y_XML_FRAG] for pointers to `YXmlFragment` data types.
+    /// - [Y_XML_HOOK] for pointers to `YXmlHook` data types.
     pub tag: i8,
 
     /// A nested event type, specific for a shared data type that triggered it. Type of an
@@ -2942,6 +3268,36 @@ impl YEvent {
                     xml_text: YXmlTextEvent::new(e, txn),
                 },
             },
+            Event::Weak(e) => YEvent {
+                tag: Y_WEAK_LINK,
+                content: YEventContent {
+                    weak: YWeakLinkEvent::new(e, txn),
+                },
+            },
+            Event::Counter(e) => YEvent {
+                tag: Y_COUNTER,
+                content: YEventContent {
+                    counter: YCounterEvent::new(e, txn),
+                },
+            },
+            Event::Tree(e) => YEvent {
+                tag: Y_TREE,
+                content: YEventContent {
+                    tree: YTreeEvent::new(e, txn),
+                },
+            },
+            Event::XmlFragment(e) => YEvent {
+                tag: Y_XML_FRAG,
+                content: YEventContent {
+                    xml_fragment: YXmlFragmentEvent::new(e, txn),
+                },
+            },
+            Event::XmlHook(e) => YEvent {
+                tag: Y_XML_HOOK,
+                content: YEventContent {
+                    xml_hook: YXmlHookEvent::new(e, txn),
+                },
+            },
         }
     }
 }
@@ -2953,6 +3309,11 @@ pub union YEventContent {
     pub array: YArrayEvent,
     pub xml_elem: YXmlEvent,
     pub xml_text: YXmlTextEvent,
+    pub weak: YWeakLinkEvent,
+    pub counter: YCounterEvent,
+    pub tree: YTreeEvent,
+    pub xml_fragment: YXmlFragmentEvent,
+    pub xml_hook: YXmlHookEvent,
 }
 
 /// Event pushed into callbacks registered with `ytext_observe` function. It contains delta of all
@@ -3107,6 +3468,146 @@ impl Deref for YXmlTextEvent {
     }
 }
 
+/// Event pushed into callbacks registered with `yweak_observe` function, when content referenced
+/// by a `YWeakLink` instance has been changed within a scope of corresponding transaction.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct YWeakLinkEvent {
+    inner: *const c_void,
+    pub txn: *const Transaction,
+}
+
+impl YWeakLinkEvent {
+    fn new(inner: &WeakEvent, txn: &Transaction) -> Self {
+        let inner = inner as *const WeakEvent as *const _;
+        let txn = txn as *const Transaction;
+        YWeakLinkEvent { inner, txn }
+    }
+}
+
+impl Deref for YWeakLinkEvent {
+    type Target = WeakEvent;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { (self.inner as *const WeakEvent).as_ref().unwrap() }
+    }
+}
+
+/// Event pushed into callbacks registered with `ycounter_observe` function, when a value of
+/// a `YCounter` instance has been changed within a scope of corresponding transaction
+/// (see: `ycounter_event_delta`).
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct YCounterEvent {
+    inner: *const c_void,
+    pub txn: *const Transaction,
+}
+
+impl YCounterEvent {
+    fn new(inner: &CounterEvent, txn: &Transaction) -> Self {
+        let inner = inner as *const CounterEvent as *const _;
+        let txn = txn as *const Transaction;
+        YCounterEvent { inner, txn }
+    }
+
+    fn txn(&self) -> &Transaction {
+        unsafe { self.txn.as_ref().unwrap() }
+    }
+}
+
+impl Deref for YCounterEvent {
+    type Target = CounterEvent;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { (self.inner as *const CounterEvent).as_ref().unwrap() }
+    }
+}
+
+/// Event pushed into callbacks registered with `yobserve_deep` function, when nodes of a `YTree`
+/// instance have been created, moved or deleted within a scope of corresponding transaction.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct YTreeEvent {
+    inner: *const c_void,
+    pub txn: *const Transaction,
+}
+
+impl YTreeEvent {
+    fn new(inner: &TreeEvent, txn: &Transaction) -> Self {
+        let inner = inner as *const TreeEvent as *const _;
+        let txn = txn as *const Transaction;
+        YTreeEvent { inner, txn }
+    }
+}
+
+impl Deref for YTreeEvent {
+    type Target = TreeEvent;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { (self.inner as *const TreeEvent).as_ref().unwrap() }
+    }
+}
+
+/// Event pushed into callbacks registered with `yobserve_deep` function, when child nodes of
+/// a `YXmlFragment` instance have been changed within a scope of corresponding transaction
+/// (see: `yxmlfragment_event_delta`).
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct YXmlFragmentEvent {
+    inner: *const c_void,
+    pub txn: *const Transaction,
+}
+
+impl YXmlFragmentEvent {
+    fn new(inner: &XmlFragmentEvent, txn: &Transaction) -> Self {
+        let inner = inner as *const XmlFragmentEvent as *const _;
+        let txn = txn as *const Transaction;
+        YXmlFragmentEvent { inner, txn }
+    }
+
+    fn txn(&self) -> &Transaction {
+        unsafe { self.txn.as_ref().unwrap() }
+    }
+}
+
+impl Deref for YXmlFragmentEvent {
+    type Target = XmlFragmentEvent;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { (self.inner as *const XmlFragmentEvent).as_ref().unwrap() }
+    }
+}
+
+/// Event pushed into callbacks registered with `yobserve_deep` function, when entries of
+/// a `YXmlHook` instance have been changed within a scope of corresponding transaction
+/// (see: `yxmlhook_event_keys`).
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct YXmlHookEvent {
+    inner: *const c_void,
+    pub txn: *const Transaction,
+}
+
+impl YXmlHookEvent {
+    fn new(inner: &XmlHookEvent, txn: &Transaction) -> Self {
+        let inner = inner as *const XmlHookEvent as *const _;
+        let txn = txn as *const Transaction;
+        YXmlHookEvent { inner, txn }
+    }
+
+    fn txn(&self) -> &Transaction {
+        unsafe { self.txn.as_ref().unwrap() }
+    }
+}
+
+impl Deref for YXmlHookEvent {
+    type Target = XmlHookEvent;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { (self.inner as *const XmlHookEvent).as_ref().unwrap() }
+    }
+}
+
 /// Releases a callback subscribed via `ytext_observe` function represented by passed
 /// observer parameter.
 #[no_mangle]
@@ -3147,6 +3648,22 @@ pub unsafe extern "C" fn yxmltext_unobserve(xml: *const Branch, subscription_id:
     xml.unobserve(subscription_id as SubscriptionId);
 }
 
+/// Releases a callback subscribed via `yweak_observe` function represented by passed
+/// observer parameter.
+#[no_mangle]
+pub unsafe extern "C" fn yweak_unobserve(weak: *const Branch, subscription_id: c_uint) {
+    let mut weak = WeakLink::from_raw_branch(weak);
+    weak.unobserve(subscription_id as SubscriptionId);
+}
+
+/// Releases a callback subscribed via `ycounter_observe` function represented by passed
+/// observer parameter.
+#[no_mangle]
+pub unsafe extern "C" fn ycounter_unobserve(counter: *const Branch, subscription_id: c_uint) {
+    let mut counter = Counter::from_raw_branch(counter);
+    counter.unobserve(subscription_id as SubscriptionId);
+}
+
 /// Releases a callback subscribed via `yobserve_deep` function represented by passed
 /// observer parameter.
 #[no_mangle]
@@ -3196,6 +3713,55 @@ pub unsafe extern "C" fn yxmltext_event_target(e: *const YXmlTextEvent) -> *mut
     Box::into_raw(Box::new(out)) as *mut _
 }
 
+/// Returns a pointer to a weak link, which triggered passed event `e`.
+#[no_mangle]
+pub unsafe extern "C" fn yweak_event_target(e: *const YWeakLinkEvent) -> *mut Branch {
+    assert!(!e.is_null());
+    let out = (&*e).target().clone();
+    out.into_raw_branch()
+}
+
+/// Returns a pointer to a counter, which triggered passed event `e`.
+#[no_mangle]
+pub unsafe extern "C" fn ycounter_event_target(e: *const YCounterEvent) -> *mut Branch {
+    assert!(!e.is_null());
+    let out = (&*e).target().clone();
+    out.into_raw_branch()
+}
+
+/// Returns a pointer to a XML fragment, which triggered passed event `e`.
+#[no_mangle]
+pub unsafe extern "C" fn yxmlfragment_event_target(e: *const YXmlFragmentEvent) -> *mut Branch {
+    assert!(!e.is_null());
+    let out = (&*e).target().clone();
+    out.into_raw_branch()
+}
+
+/// Returns a pointer to a XML hook, which triggered passed event `e`.
+#[no_mangle]
+pub unsafe extern "C" fn yxmlhook_event_target(e: *const YXmlHookEvent) -> *mut Branch {
+    assert!(!e.is_null());
+    let out = (&*e).target().clone();
+    out.into_raw_branch()
+}
+
+/// Returns a pointer to a tree, which triggered passed event `e`.
+#[no_mangle]
+pub unsafe extern "C" fn ytree_event_target(e: *const YTreeEvent) -> *mut Branch {
+    assert!(!e.is_null());
+    let out = (&*e).target().clone();
+    out.into_raw_branch()
+}
+
+/// Returns a difference between a value of a `YCounter` after and before a transaction, which
+/// triggered passed event `e`.
+#[no_mangle]
+pub unsafe extern "C" fn ycounter_event_delta(e: *const YCounterEvent) -> c_longlong {
+    assert!(!e.is_null());
+    let e = &*e;
+    e.delta(e.txn()) as c_longlong
+}
+
 /// Returns a path from a root type down to a current shared collection (which can be obtained using
 /// `ytext_event_target` function). It can consist of either integer indexes (used by sequence
 /// components) of *char keys (used by map components). `len` output parameter is used to provide
@@ -3385,6 +3951,30 @@ pub unsafe extern "C" fn yxmlelem_event_delta(
     Box::into_raw(out) as *mut _
 }
 
+/// Returns a sequence of changes produced over child nodes of a `YXmlFragment` instance, which
+/// triggered passed event `e`. `len` output parameter is used to provide information about
+/// number of changes produced.
+///
+/// Delta returned from this function should eventually be released using `yevent_delta_destroy`
+/// function.
+#[no_mangle]
+pub unsafe extern "C" fn yxmlfragment_event_delta(
+    e: *const YXmlFragmentEvent,
+    len: *mut c_int,
+) -> *mut YEventChange {
+    assert!(!e.is_null());
+    let e = &*e;
+    let delta: Vec<_> = e
+        .delta(e.txn())
+        .into_iter()
+        .map(YEventChange::from)
+        .collect();
+
+    let out = delta.into_boxed_slice();
+    *len = out.len() as c_int;
+    Box::into_raw(out) as *mut _
+}
+
 /// Releases memory allocated by the object returned from `yevent_delta` function.
 #[no_mangle]
 pub unsafe extern "C" fn ytext_delta_destroy(delta: *mut YDelta, len: c_int) {
@@ -3450,6 +4040,29 @@ pub unsafe extern "C" fn yxmlelem_event_keys(
     Box::into_raw(out) as *mut _
 }
 
+/// Returns a sequence of changes produced over entries of a `YXmlHook` instance.
+/// `len` output parameter is used to provide information about number of changes produced.
+///
+/// Delta returned from this function should eventually be released using `yevent_keys_destroy`
+/// function.
+#[no_mangle]
+pub unsafe extern "C" fn yxmlhook_event_keys(
+    e: *const YXmlHookEvent,
+    len: *mut c_int,
+) -> *mut YEventKeyChange {
+    assert!(!e.is_null());
+    let e = &*e;
+    let delta: Vec<_> = e
+        .keys(e.txn())
+        .into_iter()
+        .map(|(k, v)| YEventKeyChange::new(k.as_ref(), v))
+        .collect();
+
+    let out = delta.into_boxed_slice();
+    *len = out.len() as c_int;
+    Box::into_raw(out) as *mut _
+}
+
 /// Returns a sequence of changes produced by map component of shared collections.
 /// `len` output parameter is used to provide information about number of changes produced.
 ///
@@ -3484,7 +4097,7 @@ pub unsafe extern "C" fn yevent_keys_destroy(keys: *mut YEventKeyChange, len: c_
 
 /// Returns a value informing what kind of Yrs shared collection given `branch` represents.
 /// Returns either 0 when `branch` is null or one of values: `Y_ARRAY`, `Y_TEXT`, `Y_MAP`,
-/// `Y_XML_ELEM`, `Y_XML_TEXT`.
+/// `Y_XML_ELEM`, `Y_XML_TEXT`, `Y_WEAK_LINK`, `Y_COUNTER`, `Y_TREE`, `Y_XML_FRAG`.
 #[no_mangle]
 pub unsafe extern "C" fn ytype_kind(branch: *const Branch) -> c_char {
     if let Some(branch) = branch.as_ref() {
@@ -3494,6 +4107,11 @@ pub unsafe extern "C" fn ytype_kind(branch: *const Branch) -> c_char {
             TYPE_REFS_TEXT => Y_TEXT,
             TYPE_REFS_XML_ELEMENT => Y_XML_ELEM,
             TYPE_REFS_XML_TEXT => Y_XML_TEXT,
+            TYPE_REFS_WEAK => Y_WEAK_LINK,
+            TYPE_REFS_COUNTER => Y_COUNTER,
+            TYPE_REFS_TREE => Y_TREE,
+            TYPE_REFS_XML_FRAGMENT => Y_XML_FRAG,
+            TYPE_REFS_XML_HOOK => Y_XML_HOOK,
             other => panic!("Unknown kind: {}", other),
         }
     } else {
@@ -0,0 +1,364 @@
+//! Derive macros generating strongly typed schemas over Yrs shared types.
+//!
+//! `#[derive(YMap)]` applied on a struct with named fields, generates a set of types, which
+//! allow to work with a [yrs::Map] as if it was a struct of a given shape:
+//!
+//! ```ignore
+//! use yrs::schema::ArrayOf;
+//! use yrs::{Doc, Text};
+//! use yrs_derive::YMap;
+//!
+//! #[derive(YMap)]
+//! struct Task {
+//!     title: Text,
+//!     done: bool,
+//!     #[ymap(rename = "labels")]
+//!     tags: ArrayOf<String>,
+//! }
+//! ```
+//!
+//! For a struct above, following items are generated:
+//!
+//! - `TaskRef` - a wrapper around [yrs::Map], which exposes a typed getter (eg. `title()`) and
+//!   setter (eg. `set_title(txn, value)`) for every field, and `observe` method which notifies
+//!   about changes of schema fields.
+//! - `TaskField` - an enum with variant for every field of a struct, used by typed map events.
+//! - `Task::from_map` method and `Field`/`TryFrom<Value>` implementations for both `Task` and
+//!   `TaskRef`, which allow to read them from [yrs::types::Value] and to nest them as fields of
+//!   other schemas. Whenever a value doesn't match a schema, a `yrs::schema::SchemaError` is
+//!   returned.
+//!
+//! Every field type must implement `yrs::schema::Field` trait. Setters of `Option<T>` fields
+//! accept an optional value: setting it to `None` removes a corresponding map entry.
+//!
+//! Shared collections can be used as fields directly, eg. `tags: Array`. However [yrs::Array]
+//! itself is not generic over its elements, so `Array<String>` cannot be used as a field type.
+//! `yrs::schema::ArrayOf<String>` should be used instead, when array elements are expected to be
+//! of a given type: it wraps an [yrs::Array] and checks types of elements, as they're read.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
+
+/// Generates a strongly typed view over a [yrs::Map] for a struct with named fields.
+/// See crate-level documentation for details.
+#[proc_macro_derive(YMap, attributes(ymap))]
+pub fn derive_ymap(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_ymap(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+struct FieldDef {
+    ident: Ident,
+    ty: syn::Type,
+    key: String,
+    variant: Ident,
+    /// True for fields of `Option<T>` type, which can be removed from a map.
+    optional: bool,
+}
+
+fn expand_ymap(input: DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "YMap cannot be derived for generic structs",
+        ));
+    }
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "YMap can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "YMap can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut fields = Vec::with_capacity(named.len());
+    for field in named.iter() {
+        let ident = field.ident.clone().unwrap();
+        let name = unraw(&ident);
+        let key = field_key(field)?.unwrap_or_else(|| name.clone());
+        fields.push(FieldDef {
+            variant: Ident::new(&to_camel_case(&name), Span::call_site()),
+            optional: is_option(&field.ty),
+            ty: field.ty.clone(),
+            ident,
+            key,
+        });
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let ref_name = format_ident!("{}Ref", name);
+    let field_name = format_ident!("{}Field", name);
+
+    let variants: Vec<_> = fields.iter().map(|f| &f.variant).collect();
+    let keys: Vec<_> = fields.iter().map(|f| &f.key).collect();
+    let idents: Vec<_> = fields.iter().map(|f| &f.ident).collect();
+    let accessors: Vec<_> = fields.iter().map(field_accessors).collect();
+    let ref_doc = format!(
+        "Strongly typed view over a [yrs::Map] of a [{}] shape.",
+        name
+    );
+    let field_doc = format!("Fields of a [{}] schema.", name);
+
+    Ok(quote! {
+        #[doc = #field_doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #vis enum #field_name {
+            #(#variants,)*
+        }
+
+        impl ::yrs::schema::FieldKey for #field_name {
+            fn key(&self) -> &'static str {
+                match self {
+                    #(#field_name::#variants => #keys,)*
+                }
+            }
+
+            fn from_key(key: &str) -> ::std::option::Option<Self> {
+                match key {
+                    #(#keys => ::std::option::Option::Some(#field_name::#variants),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+
+        #[doc = #ref_doc]
+        #[derive(Debug, Clone, PartialEq)]
+        #vis struct #ref_name(::yrs::Map);
+
+        impl #ref_name {
+            /// Reads all fields of an underlying map at once.
+            pub fn load(&self) -> ::std::result::Result<#name, ::yrs::schema::SchemaError> {
+                #name::from_map(&self.0)
+            }
+
+            #(#accessors)*
+
+            /// Subscribes a given callback to be triggered whenever an underlying map is changed.
+            /// Changes made to keys, which are not part of a schema, are not reported by
+            /// [yrs::schema::TypedMapEvent::fields].
+            pub fn observe<F>(&mut self, f: F) -> ::yrs::Subscription<::yrs::types::map::MapEvent>
+            where
+                F: Fn(&::yrs::Transaction, &::yrs::schema::TypedMapEvent<#field_name>) + 'static,
+            {
+                ::yrs::schema::observe_typed(&mut self.0, f)
+            }
+
+            /// Unsubscribes a previously subscribed event callback identified by given
+            /// `subscription_id`.
+            pub fn unobserve(&mut self, subscription_id: ::yrs::SubscriptionId) {
+                self.0.unobserve(subscription_id)
+            }
+        }
+
+        impl ::std::convert::From<::yrs::Map> for #ref_name {
+            fn from(map: ::yrs::Map) -> Self {
+                #ref_name(map)
+            }
+        }
+
+        impl ::std::convert::From<#ref_name> for ::yrs::Map {
+            fn from(map: #ref_name) -> Self {
+                map.0
+            }
+        }
+
+        impl ::std::convert::AsRef<::yrs::Map> for #ref_name {
+            fn as_ref(&self) -> &::yrs::Map {
+                &self.0
+            }
+        }
+
+        impl ::yrs::schema::Field for #ref_name {
+            type Prelim = <::yrs::Map as ::yrs::schema::Field>::Prelim;
+
+            fn from_value(
+                value: ::yrs::types::Value,
+            ) -> ::std::result::Result<Self, ::yrs::schema::SchemaError> {
+                <::yrs::Map as ::yrs::schema::Field>::from_value(value).map(#ref_name)
+            }
+        }
+
+        impl ::std::convert::TryFrom<::yrs::types::Value> for #ref_name {
+            type Error = ::yrs::schema::SchemaError;
+
+            fn try_from(value: ::yrs::types::Value) -> ::std::result::Result<Self, Self::Error> {
+                <Self as ::yrs::schema::Field>::from_value(value)
+            }
+        }
+
+        impl #name {
+            /// Reads all fields of a given `map`, returning an error if any of them doesn't
+            /// match the schema.
+            pub fn from_map(map: &::yrs::Map) -> ::std::result::Result<Self, ::yrs::schema::SchemaError> {
+                ::std::result::Result::Ok(#name {
+                    #(#idents: ::yrs::schema::get_field(map, #keys)?,)*
+                })
+            }
+        }
+
+        impl ::yrs::schema::Field for #name {
+            type Prelim = <::yrs::Map as ::yrs::schema::Field>::Prelim;
+
+            fn from_value(
+                value: ::yrs::types::Value,
+            ) -> ::std::result::Result<Self, ::yrs::schema::SchemaError> {
+                let map = <::yrs::Map as ::yrs::schema::Field>::from_value(value)?;
+                #name::from_map(&map)
+            }
+        }
+
+        impl ::std::convert::TryFrom<::yrs::types::Value> for #name {
+            type Error = ::yrs::schema::SchemaError;
+
+            fn try_from(value: ::yrs::types::Value) -> ::std::result::Result<Self, Self::Error> {
+                <Self as ::yrs::schema::Field>::from_value(value)
+            }
+        }
+    })
+}
+
+/// Generates a typed getter and setter of a given field.
+fn field_accessors(field: &FieldDef) -> TokenStream2 {
+    let FieldDef { ident, ty, key, .. } = field;
+    let setter = format_ident!("set_{}", unraw(ident));
+    let getter_doc = format!("Returns a value of a `{}` field.", key);
+    let setter = if field.optional {
+        let doc = format!(
+            "Sets a new value of a `{}` field. Setting it to `None` removes the field.",
+            key
+        );
+        quote! {
+            #[doc = #doc]
+            pub fn #setter<V>(&self, txn: &mut ::yrs::Transaction, value: V)
+            where
+                V: ::std::convert::Into<
+                    ::std::option::Option<<#ty as ::yrs::schema::Field>::Prelim>,
+                >,
+            {
+                match value.into() {
+                    ::std::option::Option::Some(value) => {
+                        self.0.insert(txn, #key, value);
+                    }
+                    ::std::option::Option::None => {
+                        self.0.remove(txn, #key);
+                    }
+                }
+            }
+        }
+    } else {
+        let doc = format!("Sets a new value of a `{}` field.", key);
+        quote! {
+            #[doc = #doc]
+            pub fn #setter<V>(&self, txn: &mut ::yrs::Transaction, value: V)
+            where
+                V: ::std::convert::Into<<#ty as ::yrs::schema::Field>::Prelim>,
+            {
+                let value: <#ty as ::yrs::schema::Field>::Prelim = value.into();
+                self.0.insert(txn, #key, value);
+            }
+        }
+    };
+    quote! {
+        #[doc = #getter_doc]
+        pub fn #ident(&self) -> ::std::result::Result<#ty, ::yrs::schema::SchemaError> {
+            ::yrs::schema::get_field(&self.0, #key)
+        }
+
+        #setter
+    }
+}
+
+/// Checks if a given type is an `Option<T>`. Since macros operate on syntax only, a type is
+/// recognized by the name of its last path segment.
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() => match path.path.segments.last() {
+            Some(segment) => {
+                segment.ident == "Option"
+                    && matches!(segment.arguments, syn::PathArguments::AngleBracketed(_))
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Reads a map key overridden with `#[ymap(rename = "key")]` field attribute.
+fn field_key(field: &syn::Field) -> syn::Result<Option<String>> {
+    let mut key = None;
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("ymap")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested.iter() {
+                    match nested {
+                        NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                            if let Lit::Str(s) = &nv.lit {
+                                key = Some(s.value());
+                            } else {
+                                return Err(syn::Error::new_spanned(
+                                    &nv.lit,
+                                    "expected string literal",
+                                ));
+                            }
+                        }
+                        other => {
+                            return Err(syn::Error::new_spanned(
+                                other,
+                                "unknown ymap attribute, expected `rename = \"...\"`",
+                            ))
+                        }
+                    }
+                }
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "expected `#[ymap(rename = \"...\")]`",
+                ))
+            }
+        }
+    }
+    Ok(key)
+}
+
+fn unraw(ident: &Ident) -> String {
+    let name = ident.to_string();
+    match name.strip_prefix("r#") {
+        Some(name) => name.to_string(),
+        None => name,
+    }
+}
+
+fn to_camel_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut upper = true;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            result.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
@@ -0,0 +1,146 @@
+use lib0::any::Any;
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::rc::Rc;
+use yrs::schema::{ArrayOf, FieldKey, SchemaError};
+use yrs::types::Value;
+use yrs::{Array, Doc, Map, PrelimMap, Text};
+use yrs_derive::YMap;
+
+#[derive(YMap)]
+struct Task {
+    title: Text,
+    done: bool,
+    tags: ArrayOf<String>,
+    #[ymap(rename = "due-date")]
+    due_date: Option<String>,
+}
+
+#[derive(YMap)]
+struct Project {
+    name: String,
+    lead: TaskRef,
+    members: Array,
+}
+
+#[test]
+fn typed_getters_and_setters() {
+    let doc = Doc::with_client_id(1);
+    let mut txn = doc.transact();
+    let task = TaskRef::from(txn.get_map("task"));
+
+    task.set_title(&mut txn, "buy milk");
+    task.set_done(&mut txn, false);
+    task.set_tags(&mut txn, vec!["shopping".to_string()]);
+
+    assert_eq!(task.title().unwrap().to_string(), "buy milk");
+    assert_eq!(task.done(), Ok(false));
+    assert_eq!(task.due_date(), Ok(None));
+
+    let tags = task.tags().unwrap();
+    tags.push_back(&mut txn, "home");
+    assert_eq!(
+        tags.to_vec(),
+        Ok(vec!["shopping".to_string(), "home".to_string()])
+    );
+
+    task.set_due_date(&mut txn, "2022-06-01".to_string());
+    let map: &Map = task.as_ref();
+    assert_eq!(
+        map.get("due-date"),
+        Some(Value::Any(Any::String("2022-06-01".into())))
+    );
+
+    let loaded = task.load().unwrap();
+    assert_eq!(loaded.title.to_string(), "buy milk");
+    assert!(!loaded.done);
+    assert_eq!(loaded.tags.len(), 2);
+    assert_eq!(loaded.due_date, Some("2022-06-01".to_string()));
+
+    task.set_due_date(&mut txn, None);
+    assert_eq!(map.get("due-date"), None);
+    assert_eq!(task.due_date(), Ok(None));
+}
+
+#[test]
+fn shape_mismatch() {
+    let doc = Doc::with_client_id(1);
+    let mut txn = doc.transact();
+    let map = txn.get_map("task");
+    map.insert(&mut txn, "title", "not a text");
+    map.insert(&mut txn, "done", true);
+
+    let task = TaskRef::from(map.clone());
+    assert_eq!(
+        task.title().unwrap_err(),
+        SchemaError::InvalidField(
+            "title",
+            Box::new(SchemaError::TypeMismatch {
+                expected: "Text",
+                found: "string"
+            })
+        )
+    );
+    assert_eq!(task.tags().unwrap_err(), SchemaError::MissingField("tags"));
+    assert!(Task::try_from(Value::YMap(map)).is_err());
+    assert_eq!(
+        TaskRef::try_from(Value::Any(Any::Null)).unwrap_err(),
+        SchemaError::TypeMismatch {
+            expected: "Map",
+            found: "null"
+        }
+    );
+}
+
+#[test]
+fn nested_schemas() {
+    let doc = Doc::with_client_id(1);
+    let mut txn = doc.transact();
+    let project = ProjectRef::from(txn.get_map("project"));
+    project.set_name(&mut txn, "groceries");
+    project.set_lead(&mut txn, PrelimMap::new());
+
+    let lead = project.lead().unwrap();
+    lead.set_title(&mut txn, "buy milk");
+    lead.set_done(&mut txn, true);
+    lead.set_tags(&mut txn, Vec::<String>::new());
+    project.set_members(&mut txn, vec![Any::String("alice".into())]);
+
+    let loaded = project.load().unwrap();
+    assert_eq!(loaded.name, "groceries");
+    assert_eq!(loaded.lead.done(), Ok(true));
+    assert_eq!(
+        loaded.members.get(0),
+        Some(Value::Any(Any::String("alice".into())))
+    );
+}
+
+#[test]
+fn typed_observer() {
+    let doc = Doc::with_client_id(1);
+    let mut task = {
+        let mut txn = doc.transact();
+        TaskRef::from(txn.get_map("task"))
+    };
+
+    let changed = Rc::new(RefCell::new(Vec::new()));
+    let changed_c = changed.clone();
+    let _sub = task.observe(move |txn, e| {
+        let fields: Vec<_> = e.fields(txn).into_iter().map(|(f, _)| f).collect();
+        *changed_c.borrow_mut() = fields;
+    });
+
+    {
+        let mut txn = doc.transact();
+        task.set_due_date(&mut txn, Some("2022-06-01".to_string()));
+        task.set_done(&mut txn, true);
+        task.as_ref().insert(&mut txn, "unknown", 1.0);
+    }
+
+    assert_eq!(
+        changed.borrow().as_slice(),
+        &[TaskField::Done, TaskField::DueDate]
+    );
+    assert_eq!(TaskField::DueDate.key(), "due-date");
+    assert_eq!(TaskField::from_key("due_date"), None);
+}
@@ -454,6 +454,68 @@ fn b4_2(c: &mut Criterion, name: &str) {
     );
 }
 
+fn b5_1(len: u32, size: usize) -> Vec<TextOp> {
+    (0..size as u32)
+        .map(|i| TextOp::Insert(len + i, "a".to_string()))
+        .collect()
+}
+
+fn b5_2(len: u32, size: usize) -> Vec<TextOp> {
+    (0..size as u32)
+        .map(|i| TextOp::Insert(len / 2 + i, "a".to_string()))
+        .collect()
+}
+
+fn b5_3(len: u32, size: usize) -> Vec<TextOp> {
+    // type and remove characters in the middle of a document
+    (0..size as u32)
+        .map(|i| {
+            if i % 2 == 0 {
+                TextOp::Insert(len / 2, "a".to_string())
+            } else {
+                TextOp::Delete(len / 2, 1)
+            }
+        })
+        .collect()
+}
+
+/// Edits a large text document, which consists of many blocks, so that the cost of finding
+/// an index is dominated by a document size rather than by the edits themselves.
+fn large_text_benchmark<F>(c: &mut Criterion, name: &str, gen: F)
+where
+    F: FnOnce(u32, usize) -> Vec<TextOp>,
+{
+    let input = {
+        let doc = Doc::new();
+        let txt = doc.transact().get_text("text");
+        let mut rng = StdRng::seed_from_u64(SEED);
+        // characters inserted at random positions are not squashed into a single block
+        for op in b1_4(&mut rng, 10 * N) {
+            if let TextOp::Insert(idx, chunk) = op {
+                txt.insert(&mut doc.transact(), idx, &chunk);
+            }
+        }
+        let ops = gen(txt.len(), N);
+        (doc, txt, ops)
+    };
+
+    c.bench_with_input(
+        BenchmarkId::new(name, input.2.len()),
+        &input,
+        |b, (doc, text, ops)| {
+            b.iter(|| {
+                for op in ops.iter() {
+                    let mut txn = doc.transact();
+                    match op {
+                        TextOp::Insert(idx, txt) => text.insert(&mut txn, *idx, txt),
+                        TextOp::Delete(idx, len) => text.remove_range(&mut txn, *idx, *len),
+                    }
+                }
+            });
+        },
+    );
+}
+
 fn read_input(fpath: &str) -> Vec<TextOp> {
     use std::fs::File;
     use yrs::updates::decoder::DecoderV1;
@@ -533,6 +595,17 @@ fn bench(c: &mut Criterion) {
     b3_4(c, "[B3.4] 20√N clients concurrently insert text in Array");
     b4_2(c, "[B4.2] Apply real-world document snapshot of size");
     b4_1(c, "[B4.1] Apply real-world editing dataset");
+    large_text_benchmark(c, "[B5.1] Append N characters to a large document", b5_1);
+    large_text_benchmark(
+        c,
+        "[B5.2] Insert N characters in the middle of a large document",
+        b5_2,
+    );
+    large_text_benchmark(
+        c,
+        "[B5.3] Insert/Delete characters in the middle of a large document",
+        b5_3,
+    );
 }
 
 criterion_group! {
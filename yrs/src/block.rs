@@ -1,10 +1,12 @@
 use crate::doc::OffsetKind;
-use crate::moving::Move;
+use crate::moving::{transfer_len, visible_parent, Move};
+use crate::search_marker::repair_markers;
 use crate::store::Store;
+use crate::types::weak::LinkSource;
 use crate::types::{
-    Attrs, Branch, BranchPtr, TypePtr, Value, TYPE_REFS_ARRAY, TYPE_REFS_MAP, TYPE_REFS_TEXT,
-    TYPE_REFS_UNDEFINED, TYPE_REFS_XML_ELEMENT, TYPE_REFS_XML_FRAGMENT, TYPE_REFS_XML_HOOK,
-    TYPE_REFS_XML_TEXT,
+    Attrs, Branch, BranchPtr, TypePtr, Value, TYPE_REFS_ARRAY, TYPE_REFS_COUNTER, TYPE_REFS_MAP,
+    TYPE_REFS_TEXT, TYPE_REFS_TREE, TYPE_REFS_UNDEFINED, TYPE_REFS_WEAK, TYPE_REFS_XML_ELEMENT,
+    TYPE_REFS_XML_FRAGMENT, TYPE_REFS_XML_HOOK, TYPE_REFS_XML_TEXT,
 };
 use crate::updates::decoder::{Decode, Decoder};
 use crate::updates::encoder::{Encode, Encoder};
@@ -336,9 +338,14 @@ impl BlockPtr {
                     }
 
                     // adjust length of parent
-                    if this.parent_sub.is_none() && this.is_countable() && !this.is_deleted() {
-                        parent_ref.block_len += this.len;
-                        parent_ref.content_len += this.content_len(encoding);
+                    if this.parent_sub.is_none() {
+                        if this.is_countable() && !this.is_deleted() {
+                            parent_ref.block_len += this.len;
+                            parent_ref.content_len += this.content_len(encoding);
+                            // line and grapheme indexes are kept up to date only by local text updates
+                            parent_ref.line_index.clear();
+                            parent_ref.grapheme_index.clear();
+                        }
                     }
 
                     // check if this item is in a moved range
@@ -355,6 +362,12 @@ impl BlockPtr {
                     if left_moved.is_some() || right_moved.is_some() {
                         if left_moved == right_moved {
                             this.moved = left_moved;
+                            if this.parent_sub.is_none() {
+                                // moved range may be visible in a different sequence
+                                let visible = visible_parent(self_ptr);
+                                let from = Some(parent_ref);
+                                transfer_len(txn, self_ptr, from, visible, &mut HashSet::new());
+                            }
                         } else {
                             #[inline]
                             fn try_integrate(mut ptr: BlockPtr, txn: &mut Transaction) {
@@ -370,10 +383,12 @@ impl BlockPtr {
 
                             if let Some(ptr) = left_moved {
                                 try_integrate(ptr, txn);
+                                repair_markers(txn, parent_ref, ptr, false);
                             }
 
                             if let Some(ptr) = right_moved {
                                 try_integrate(ptr, txn);
+                                repair_markers(txn, parent_ref, ptr, false);
                             }
                         }
                     }
@@ -393,15 +408,23 @@ impl BlockPtr {
                             //}
                             todo!()
                         }
-                        ItemContent::Format(_, _) => {
-                            // @todo searchmarker are currently unsupported for rich text documents
-                            // /** @type {AbstractType<any>} */ (item.parent)._searchMarker = null
+                        ItemContent::Type(branch) => {
+                            branch.store = Some(txn.store.clone());
+                            if branch.type_ref() == TYPE_REFS_WEAK {
+                                let link = BranchPtr::from(&*branch);
+                                if let Some(quoted) = WeakLink::from(link).quoted_branch() {
+                                    let links = &mut txn.store_mut().weak_links;
+                                    links.entry(quoted).or_default().insert(link);
+                                }
+                            }
                         }
-                        ItemContent::Type(branch) => branch.store = Some(txn.store.clone()),
                         _ => {
                             // other types don't define integration-specific actions
                         }
                     }
+                    if this.parent_sub.is_none() {
+                        repair_markers(txn, parent_ref, self_ptr, false);
+                    }
                     txn.add_changed_type(parent_ref, this.parent_sub.clone());
                     let parent_deleted = if let TypePtr::Branch(ptr) = &this.parent {
                         if let Some(block) = ptr.item {
@@ -426,8 +449,18 @@ impl BlockPtr {
     }
 
     pub(crate) fn gc(&mut self, parent_gced: bool) {
+        let self_ptr = self.clone();
         if let Block::Item(item) = self.deref_mut() {
             if item.is_deleted() {
+                if let ItemContent::Move(m) = &mut item.content {
+                    if !parent_gced {
+                        if m.is_overridden(&mut HashSet::new()) {
+                            // deleted move may be reintegrated once its overriding move is deleted
+                            return;
+                        }
+                        m.release_overrides(self_ptr);
+                    }
+                }
                 item.content.gc();
                 let len = item.len();
                 if parent_gced {
@@ -1121,7 +1154,7 @@ impl SplittableString {
         } else {
             match kind {
                 OffsetKind::Bytes => len,
-                OffsetKind::Utf16 => self.utf16_len(),
+                OffsetKind::Utf16 | OffsetKind::Grapheme => self.utf16_len(),
                 OffsetKind::Utf32 => self.unicode_len(),
             }
         }
@@ -1144,7 +1177,9 @@ impl SplittableString {
     pub fn split_at(&self, offset: usize, kind: OffsetKind) -> (&str, &str) {
         let off = match kind {
             OffsetKind::Bytes => offset,
-            OffsetKind::Utf16 => self.map_utf16_offset(offset as u32) as usize,
+            OffsetKind::Utf16 | OffsetKind::Grapheme => {
+                self.map_utf16_offset(offset as u32) as usize
+            }
             OffsetKind::Utf32 => self.map_unicode_offset(offset as u32) as usize,
         };
         self.content.split_at(off)
@@ -1153,9 +1188,11 @@ impl SplittableString {
     /// Maps given offset onto block offset. This means, that given an `offset` provided
     /// in given `encoding` we want the output as a UTF-16 compatible offset (required
     /// by Yjs for compatibility reasons).
+    ///
+    /// [OffsetKind::Grapheme] offsets are already mapped onto UTF-16 ones by [Text].
     pub(crate) fn block_offset(&self, offset: u32, kind: OffsetKind) -> u32 {
         match kind {
-            OffsetKind::Utf16 => offset,
+            OffsetKind::Utf16 | OffsetKind::Grapheme => offset,
             OffsetKind::Bytes => {
                 let mut remaining = offset;
                 let mut i = 0;
@@ -1431,6 +1468,8 @@ impl ItemContent {
                 if type_ref == types::TYPE_REFS_XML_ELEMENT || type_ref == types::TYPE_REFS_XML_HOOK
                 {
                     encoder.write_key(inner.name.as_ref().unwrap().as_ref())
+                } else if type_ref == types::TYPE_REFS_WEAK {
+                    inner.link_source.as_ref().unwrap().encode(encoder)
                 }
             }
             ItemContent::Any(any) => {
@@ -1469,6 +1508,8 @@ impl ItemContent {
                 if type_ref == types::TYPE_REFS_XML_ELEMENT || type_ref == types::TYPE_REFS_XML_HOOK
                 {
                     encoder.write_key(inner.name.as_ref().unwrap().as_ref())
+                } else if type_ref == types::TYPE_REFS_WEAK {
+                    inner.link_source.as_ref().unwrap().encode(encoder)
                 }
             }
             ItemContent::Any(any) => {
@@ -1511,7 +1552,10 @@ impl ItemContent {
                 } else {
                     None
                 };
-                let inner = Branch::new(type_ref, name);
+                let mut inner = Branch::new(type_ref, name);
+                if type_ref == TYPE_REFS_WEAK {
+                    inner.link_source = Some(Box::new(LinkSource::decode(decoder)?));
+                }
                 Ok(ItemContent::Type(inner))
             }
             BLOCK_ITEM_ANY_REF_NUMBER => {
@@ -1771,6 +1815,12 @@ impl std::fmt::Display for ItemContent {
                 TYPE_REFS_XML_FRAGMENT => write!(f, "<xml fragment>"),
                 TYPE_REFS_XML_HOOK => write!(f, "<xml hook>"),
                 TYPE_REFS_XML_TEXT => write!(f, "<xml text>"),
+                TYPE_REFS_WEAK => match inner.link_source.as_ref() {
+                    Some(source) => write!(f, "<weak link: {}>", source),
+                    None => write!(f, "<weak link>"),
+                },
+                TYPE_REFS_COUNTER => write!(f, "<counter>"),
+                TYPE_REFS_TREE => write!(f, "<tree>"),
                 _ => write!(f, "<undefined type ref>"),
             },
             ItemContent::Move(m) => std::fmt::Display::fmt(m.as_ref(), f),
@@ -1,9 +1,10 @@
 use crate::block::{Block, BlockPtr, Item, ItemContent, Prelim};
 use crate::moving::{Move, RelativePosition};
+use crate::search_marker::{is_markable, repair_markers, update_markers};
 use crate::types::array::ArraySliceConcat;
-use crate::types::{BranchPtr, TypePtr, Value};
+use crate::types::{BranchPtr, TypePtr, Value, TYPE_REFS_TEXT, TYPE_REFS_XML_TEXT};
 use crate::{Transaction, ID};
-use std::ops::DerefMut;
+use std::ops::{Deref, DerefMut};
 
 #[derive(Debug, Clone)]
 pub(crate) struct BlockIter {
@@ -35,14 +36,27 @@ impl BlockIter {
         }
     }
 
+    #[inline]
+    pub fn branch(&self) -> BranchPtr {
+        self.branch
+    }
+
+    #[inline]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
     #[inline]
     pub fn rel(&self) -> u32 {
         self.rel
     }
 
+    /// Checks if iterator has reached the end of an iterated sequence. Reaching the end of a block
+    /// list while being inside of a moved range doesn't finish the iteration, since moved blocks
+    /// are visible at the position of their move.
     #[inline]
     pub fn finished(&self) -> bool {
-        self.reached_end || self.index == self.branch.content_len
+        (self.reached_end && self.curr_move.is_none()) || self.index == self.branch.content_len
     }
 
     #[inline]
@@ -109,6 +123,17 @@ impl BlockIter {
             return false;
         }
 
+        // when iterator starts from the beginning of a sequence, we can skip over to the closest
+        // search marker instead
+        let seek = self.is_at_start();
+        if seek {
+            if let Some(marker) = self.branch.search_markers.find(len, true) {
+                self.next_item = Some(marker.ptr);
+                self.index = marker.index;
+                len -= marker.index;
+            }
+        }
+
         let mut item = self.next_item;
         self.index += len;
         if self.rel != 0 {
@@ -167,15 +192,78 @@ impl BlockIter {
 
         self.index -= len;
         self.next_item = item;
+        if seek {
+            self.mark_position(txn);
+        }
         true
     }
 
+    fn is_at_start(&self) -> bool {
+        self.index == 0
+            && self.rel == 0
+            && !self.reached_end
+            && self.curr_move.is_none()
+            && self.next_item == self.branch.start
+    }
+
+    /// Stores a current iterator position as a search marker of an iterated branch.
+    fn mark_position(&mut self, txn: &Transaction) {
+        let type_ref = self.branch.type_ref();
+        if self.curr_move.is_some() || type_ref == TYPE_REFS_TEXT || type_ref == TYPE_REFS_XML_TEXT
+        {
+            // text markers must also carry formatting attributes, see: Text::find_position
+            return;
+        }
+        let encoding = txn.store().options.offset_kind;
+        let mut ptr = self.next_item;
+        let mut index = self.index;
+        if !self.reached_end {
+            index -= self.rel;
+        } else {
+            // iterator is placed after the last block, find the last visible one
+            while let Some(Block::Item(item)) = ptr.as_deref() {
+                if is_markable(ptr.unwrap()) {
+                    index -= item.content_len(encoding);
+                    break;
+                } else if item.moved.is_some() || matches!(item.content, ItemContent::Move(_)) {
+                    return;
+                }
+                ptr = item.left;
+            }
+        }
+        if let Some(ptr) = ptr {
+            let len = self.branch.content_len;
+            self.branch.search_markers.mark(ptr, index, None, len);
+        }
+    }
+
     fn reduce_moves(&mut self, txn: &mut Transaction) {
         let mut item = self.next_item;
-        if item.is_some() {
-            while item == self.curr_move_start {
-                item = self.curr_move;
-                self.pop(txn);
+        // iterator placed in the middle of the first block of a moved range is still inside of it
+        if item.is_some() && self.rel == 0 {
+            loop {
+                if item == self.curr_move_start {
+                    // start of a moved range is placed right before its move block
+                    item = self.curr_move;
+                    self.pop(txn);
+                } else if self.curr_move.is_some()
+                    && (item == self.curr_move_end
+                        || (self.reached_end && self.curr_move_end.is_none()))
+                {
+                    // end of a moved range is placed right after its move block, eg. after
+                    // the last element of a moved range has been deleted
+                    let curr_move = self.curr_move;
+                    self.pop(txn);
+                    match curr_move.as_deref() {
+                        Some(Block::Item(i)) if i.right.is_some() => item = i.right,
+                        _ => {
+                            item = curr_move;
+                            self.reached_end = true;
+                        }
+                    }
+                } else {
+                    break;
+                }
             }
             self.next_item = item;
         }
@@ -195,6 +283,7 @@ impl BlockIter {
                     0
                 };
             }
+            self.reached_end = false;
         }
         if self.rel >= len {
             self.rel -= len;
@@ -293,11 +382,15 @@ impl BlockIter {
         self.reached_end = false;
     }
 
-    pub fn delete(&mut self, txn: &mut Transaction, mut len: u32) {
-        let mut item = self.next_item;
+    pub fn delete(&mut self, txn: &mut Transaction, len: u32) {
         if self.index + len > self.branch.content_len() {
             panic!("Length exceeded");
         }
+        update_markers(self.branch, self.index, || self.delete_inner(txn, len))
+    }
+
+    fn delete_inner(&mut self, txn: &mut Transaction, mut len: u32) {
+        let mut item = self.next_item;
 
         let encoding = txn.store().options.offset_kind;
         let mut i: &Item;
@@ -365,7 +458,8 @@ impl BlockIter {
         self.index += len;
         let mut next_item = self.next_item;
         let encoding = txn.store().options.offset_kind;
-        while len > 0 && !self.reached_end {
+        // reaching the end of a moved range doesn't finish the iteration
+        while len > 0 && (!self.reached_end || self.curr_move.is_some()) {
             while let Some(mut ptr) = next_item {
                 if Some(ptr) != self.curr_move_end
                     && ptr.is_countable()
@@ -435,15 +529,40 @@ impl BlockIter {
     pub fn insert_contents<V: Prelim>(&mut self, txn: &mut Transaction, value: V) {
         self.reduce_moves(txn);
         self.split_rel(txn);
+        let moves = update_markers(self.branch, self.index, || {
+            self.insert_contents_inner(txn, value)
+        });
+        for ptr in moves {
+            // moved ranges change visibility of other blocks
+            repair_markers(txn, self.branch, ptr, false);
+        }
+    }
+
+    /// Inserts a new block at current iterator position. Returns moves affected by an insertion:
+    /// either an inserted block itself or the moves reintegrated, because it was inserted in between
+    /// two different moved ranges.
+    fn insert_contents_inner<V: Prelim>(
+        &mut self,
+        txn: &mut Transaction,
+        value: V,
+    ) -> Vec<BlockPtr> {
         let id = {
             let store = txn.store();
             let client_id = store.options.client_id;
             let clock = store.blocks.get_state(&client_id);
             ID::new(client_id, clock)
         };
-        let parent = TypePtr::Branch(self.branch);
         let right = self.right();
         let left = self.left();
+        // inside of a moved range neighbors may belong to a different sequence
+        let parent = [left, right]
+            .iter()
+            .flatten()
+            .find_map(|ptr| match ptr.deref() {
+                Block::Item(item) => Some(item.parent.clone()),
+                _ => None,
+            })
+            .unwrap_or(TypePtr::Branch(self.branch));
         let (content, remainder) = value.into_content(txn);
         let inner_ref = if let ItemContent::Type(inner_ref) = &content {
             Some(BranchPtr::from(inner_ref))
@@ -463,6 +582,21 @@ impl BlockIter {
         let mut block_ptr = BlockPtr::from(&mut block);
 
         block_ptr.integrate(txn, 0);
+        let mut moves = Vec::new();
+        if let Block::Item(item) = block_ptr.deref() {
+            let moved = |ptr: Option<BlockPtr>| match ptr.as_deref() {
+                Some(Block::Item(i)) => i.moved,
+                _ => None,
+            };
+            if let ItemContent::Move(_) = &item.content {
+                moves.push(block_ptr);
+            }
+            let (left_moved, right_moved) = (moved(item.left), moved(item.right));
+            if left_moved != right_moved {
+                moves.extend(left_moved);
+                moves.extend(right_moved);
+            }
+        }
 
         let local_block_list = txn.store_mut().blocks.get_client_blocks_mut(id.client);
         local_block_list.push(block);
@@ -471,12 +605,12 @@ impl BlockIter {
             remainder.integrate(txn, inner_ref.unwrap().into())
         }
 
-        if let Some(Block::Item(item)) = right.as_deref() {
-            self.next_item = item.right;
-        } else {
-            self.next_item = left;
+        // iterator is placed right after inserted block, so that consecutive insertions keep order
+        if right.is_none() {
+            self.next_item = Some(block_ptr);
             self.reached_end = true;
         }
+        moves
     }
 
     pub fn insert_move(
@@ -508,7 +642,7 @@ impl<'a, 'txn> Iterator for Values<'a, 'txn> {
     type Item = Value;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.iter.reached_end || self.iter.index == self.iter.branch.content_len() {
+        if self.iter.finished() {
             None
         } else {
             let mut content = self
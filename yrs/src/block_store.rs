@@ -1,4 +1,5 @@
 use crate::block::{Block, BlockPtr, ClientID, ID};
+use crate::types::TypePtr;
 use crate::updates::decoder::{Decode, Decoder};
 use crate::updates::encoder::{Encode, Encoder};
 use crate::utils::client_hasher::ClientHasher;
@@ -323,6 +324,8 @@ impl ClientBlockList {
                                 *r = left;
                             }
                         }
+                    } else if let TypePtr::Branch(mut parent) = item.parent {
+                        parent.search_markers.remove(right_ptr);
                     }
                 }
             }
@@ -1,8 +1,9 @@
 use crate::block::ClientID;
 
-use crate::event::{AfterTransactionEvent, EventHandler, Subscription, UpdateEvent};
+use crate::event::{AfterTransactionEvent, EventHandler, RootsEvent, Subscription, UpdateEvent};
 use crate::store::{Store, StoreRef};
 use crate::transaction::Transaction;
+use crate::types::DocEvents;
 use crate::updates::encoder::{Encode, Encoder, EncoderV1, EncoderV2};
 use crate::{DeleteSet, StateVector, SubscriptionId};
 use rand::Rng;
@@ -143,6 +144,53 @@ impl Doc {
         }
     }
 
+    /// Subscribe callback function, which is called whenever new root-level types are defined
+    /// within the `Doc`. This happens when a root type is accessed for the first time (eg. via
+    /// [Transaction::get_map]) or when a remote update containing it has been applied. The
+    /// callback is triggered on transaction commit.
+    ///
+    /// Returns a subscription, which will unsubscribe function when dropped.
+    pub fn observe_roots<F>(&mut self, f: F) -> Subscription<RootsEvent>
+    where
+        F: Fn(&Transaction, &RootsEvent) + 'static,
+    {
+        self.store
+            .roots_events
+            .get_or_insert_with(EventHandler::new)
+            .subscribe(f)
+    }
+
+    /// Manually unsubscribes from a callback used in [Doc::observe_roots] method.
+    pub fn unobserve_roots(&mut self, subscription_id: SubscriptionId) {
+        if let Some(handler) = self.store.roots_events.as_mut() {
+            handler.unsubscribe(subscription_id);
+        }
+    }
+
+    /// Subscribe callback function for all events emitted by any of the root-level types defined
+    /// within the `Doc` and their nested types. Unlike
+    /// [DeepObservable::observe_deep](crate::types::DeepObservable::observe_deep), paths of these
+    /// events ([DocEvent::path](crate::types::DocEvent::path)) start with a name of the root-level
+    /// type. The callback is triggered on transaction commit.
+    ///
+    /// Returns a subscription, which will unsubscribe function when dropped.
+    pub fn observe_all_deep<F>(&mut self, f: F) -> Subscription<DocEvents>
+    where
+        F: Fn(&Transaction, &DocEvents) + 'static,
+    {
+        self.store
+            .all_deep_events
+            .get_or_insert_with(EventHandler::new)
+            .subscribe(f)
+    }
+
+    /// Manually unsubscribes from a callback used in [Doc::observe_all_deep] method.
+    pub fn unobserve_all_deep(&mut self, subscription_id: SubscriptionId) {
+        if let Some(handler) = self.store.all_deep_events.as_mut() {
+            handler.unsubscribe(subscription_id);
+        }
+    }
+
     pub fn encode_state_as_update<E: Encoder>(&self, sv: &StateVector, encoder: &mut E) {
         let store = self.store.deref();
         store.write_blocks(sv, encoder);
@@ -207,15 +255,26 @@ pub enum OffsetKind {
     Utf16,
     /// Compute editable strings length and offset using Unicode code points number.
     Utf32,
+    /// Compute editable strings length and offset using extended grapheme clusters, ie.
+    /// user-perceived characters. Indexes passed to [Text] methods always point to grapheme
+    /// boundaries, so that multi-codepoint characters (like emoji ZWJ sequences or letters with
+    /// combining marks) are never split.
+    ///
+    /// Grapheme clusters may span over multiple blocks, therefore they are cached by each text
+    /// and updated alongside its content. Underlying blocks, event deltas and other offsets that
+    /// are not explicitly converted are measured using UTF-16 chars count.
+    Grapheme,
 }
 
 #[cfg(test)]
 mod test {
     use crate::block::{Block, ItemContent};
+    use crate::types::xml::Xml;
+    use crate::types::{Path, PathSegment};
     use crate::update::Update;
     use crate::updates::decoder::Decode;
     use crate::updates::encoder::{Encode, Encoder, EncoderV1};
-    use crate::{DeleteSet, Doc, StateVector, SubscriptionId};
+    use crate::{DeleteSet, Doc, PrelimMap, StateVector, SubscriptionId};
     use lib0::any::Any;
     use std::cell::{Cell, RefCell};
     use std::collections::HashMap;
@@ -635,4 +694,100 @@ mod test {
         .unwrap();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn observe_roots() {
+        let mut d1 = Doc::with_client_id(1);
+        let roots = Rc::new(RefCell::new(Vec::new()));
+        let roots_c = roots.clone();
+        let _sub = d1.observe_roots(move |_, e| {
+            roots_c.borrow_mut().push(e.added.clone());
+        });
+
+        {
+            let mut txn = d1.transact();
+            txn.get_map("a");
+            txn.get_text("b");
+            txn.get_map("a");
+        }
+        let mut added = roots.borrow_mut().pop().unwrap();
+        added.sort();
+        assert_eq!(added, vec![Rc::from("a"), Rc::from("b")]);
+
+        // roots accessed for the second time are not reported
+        d1.transact().get_map("a");
+        assert!(roots.borrow().is_empty());
+
+        // roots defined by remote peers are reported on update
+        let d2 = Doc::with_client_id(2);
+        {
+            let mut txn = d2.transact();
+            txn.get_array("c").push_back(&mut txn, 1);
+        }
+        let update = d2.encode_state_as_update_v1(&StateVector::default());
+        d1.transact()
+            .apply_update(Update::decode_v1(update.as_slice()).unwrap());
+        assert_eq!(roots.borrow_mut().pop(), Some(vec![Rc::from("c")]));
+
+        // transactions created by read operations don't report roots of an outer transaction
+        {
+            let mut txn = d1.transact();
+            let frag = txn.get_xml_fragment("d");
+            frag.push_elem_back(&mut txn, "p");
+            assert!(frag.get::<Xml>(0).is_some());
+            assert!(roots.borrow().is_empty());
+        }
+        assert_eq!(roots.borrow_mut().pop(), Some(vec![Rc::from("d")]));
+    }
+
+    #[test]
+    fn observe_all_deep() {
+        let mut doc = Doc::with_client_id(1);
+        let paths = Rc::new(RefCell::new(Vec::new()));
+        let paths_c = paths.clone();
+        let _sub = doc.observe_all_deep(move |_, events| {
+            for e in events.iter() {
+                paths_c.borrow_mut().push((e.root().clone(), e.path()));
+            }
+        });
+
+        {
+            let mut txn = doc.transact();
+            let map = txn.get_map("map");
+            map.insert(&mut txn, "nested", PrelimMap::<bool>::new());
+            txn.get_text("text").push(&mut txn, "hello");
+        }
+        let mut actual = paths.borrow_mut().split_off(0);
+        actual.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    Rc::from("map"),
+                    Path::from(vec![PathSegment::Key("map".into())])
+                ),
+                (
+                    Rc::from("text"),
+                    Path::from(vec![PathSegment::Key("text".into())])
+                ),
+            ]
+        );
+
+        {
+            let mut txn = doc.transact();
+            let map = txn.get_map("map");
+            let nested = map.get("nested").unwrap().to_ymap().unwrap();
+            nested.insert(&mut txn, "key", true);
+        }
+        assert_eq!(
+            paths.borrow().as_slice(),
+            &[(
+                Rc::from("map"),
+                Path::from(vec![
+                    PathSegment::Key("map".into()),
+                    PathSegment::Key("nested".into())
+                ])
+            )]
+        );
+    }
 }
@@ -2,6 +2,7 @@ use crate::{DeleteSet, StateVector, Transaction};
 use rand::RngCore;
 use std::collections::HashMap;
 use std::ptr::NonNull;
+use std::rc::Rc;
 
 #[repr(transparent)]
 pub(crate) struct EventHandler<T>(Box<Subscriptions<T>>);
@@ -94,6 +95,13 @@ pub struct AfterTransactionEvent {
     pub delete_set: DeleteSet,
 }
 
+/// Event passed to a callback registered with [Doc::observe_roots]. Contains names of root-level
+/// types, which have been defined for the first time within the scope of a committed transaction,
+/// either by accessing them locally or by applying remote updates that reference them.
+pub struct RootsEvent {
+    pub added: Vec<Rc<str>>,
+}
+
 #[cfg(test)]
 mod test {
     use crate::event::EventHandler;
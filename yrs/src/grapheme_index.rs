@@ -0,0 +1,399 @@
+use crate::block::{Block, BlockPtr, ItemContent};
+use crate::types::BranchPtr;
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
+
+/// Number of UTF-16 code units read around a locally changed range of text in order to check if
+/// grapheme clusters at its boundaries are left intact.
+const CONTEXT_LEN: u32 = 16;
+
+/// Grapheme index of a text branch using [OffsetKind::Grapheme](crate::OffsetKind::Grapheme):
+/// a cached list of grapheme clusters longer than a single UTF-16 code unit. Since blocks of such
+/// branches are measured in UTF-16 code units, this is enough to map offsets between both units
+/// without segmenting an entire text.
+///
+/// Just like [LineIndex](crate::line_index::LineIndex), grapheme index is updated by local
+/// insertions and removals of text, while any other change to the branch sequence drops it. Local
+/// changes, which could merge or split grapheme clusters around a changed range, drop it as well.
+/// Dropped index is rebuilt from branch blocks on the next lookup.
+#[derive(Debug, Default)]
+pub(crate) struct GraphemeIndex {
+    /// Clusters spanning over more than a single UTF-16 code unit, ordered by their offsets.
+    /// `None` if the index has to be rebuilt.
+    clusters: Option<Vec<Cluster>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Cluster {
+    /// UTF-16 offset at which a cluster starts.
+    start: u32,
+    /// Number of UTF-16 code units within a cluster.
+    len: u32,
+    /// Number of UTF-16 code units exceeding a single grapheme within all preceding clusters.
+    excess: u32,
+}
+
+impl Cluster {
+    fn end(&self) -> u32 {
+        self.start + self.len
+    }
+
+    /// Grapheme offset at which a cluster starts.
+    fn index(&self) -> u32 {
+        self.start - self.excess
+    }
+
+    /// Number of UTF-16 code units exceeding a single grapheme up to the end of this cluster.
+    fn total_excess(&self) -> u32 {
+        self.excess + self.len - 1
+    }
+}
+
+impl GraphemeIndex {
+    pub fn clear(&mut self) {
+        self.clusters = None;
+    }
+
+    /// Returns cached clusters. If index has been dropped, it's rebuilt from a block list
+    /// beginning at a given `start`.
+    fn clusters(&mut self, start: Option<BlockPtr>) -> &[Cluster] {
+        self.clusters.get_or_insert_with(|| scan(start))
+    }
+
+    /// Returns a number of grapheme clusters within a text of a given UTF-16 `len`, which block
+    /// list begins at a given `start`.
+    pub fn len(&mut self, start: Option<BlockPtr>, len: u32) -> u32 {
+        let excess = self.clusters(start).last().map_or(0, Cluster::total_excess);
+        len - excess
+    }
+
+    /// Converts a UTF-16 `offset` into a grapheme offset. Offsets pointing inside of a cluster
+    /// are moved to its start.
+    pub fn to_graphemes(&mut self, start: Option<BlockPtr>, offset: u32) -> u32 {
+        let clusters = self.clusters(start);
+        let i = clusters.partition_point(|c| c.end() <= offset);
+        match clusters.get(i) {
+            Some(c) if c.start < offset => c.index(),
+            _ if i == 0 => offset,
+            _ => offset - clusters[i - 1].total_excess(),
+        }
+    }
+
+    /// Converts a grapheme `offset` into a UTF-16 offset.
+    pub fn to_units(&mut self, start: Option<BlockPtr>, offset: u32) -> u32 {
+        let clusters = self.clusters(start);
+        match clusters.partition_point(|c| c.index() < offset) {
+            0 => offset,
+            i => offset + clusters[i - 1].total_excess(),
+        }
+    }
+
+    /// Updates grapheme index after `len` UTF-16 code units have been inserted into a `branch`
+    /// at a given `index`.
+    fn inserted(&mut self, branch: BranchPtr, index: u32, len: u32) {
+        if let Some(clusters) = self.clusters.as_mut() {
+            let i = clusters.partition_point(|c| c.end() <= index);
+            let splits_cluster = matches!(clusters.get(i), Some(c) if c.start < index);
+            match segment(branch, index, index + len) {
+                Some(inserted) if !splits_cluster => {
+                    for c in clusters[i..].iter_mut() {
+                        c.start += len;
+                    }
+                    clusters.splice(i..i, inserted);
+                    recount(clusters, i);
+                }
+                _ => self.clear(),
+            }
+        }
+    }
+
+    /// Updates grapheme index after `len` UTF-16 code units have been removed from a `branch`
+    /// starting at a given `index`.
+    fn removed(&mut self, branch: BranchPtr, index: u32, len: u32) {
+        if let Some(clusters) = self.clusters.as_mut() {
+            let end = index + len;
+            let i = clusters.partition_point(|c| c.end() <= index);
+            let j = clusters.partition_point(|c| c.end() <= end);
+            let splits_cluster = matches!(clusters.get(i), Some(c) if c.start < index)
+                || matches!(clusters.get(j), Some(c) if c.start < end);
+            match segment(branch, index, index) {
+                Some(_) if !splits_cluster => {
+                    clusters.drain(i..j);
+                    for c in clusters[i..].iter_mut() {
+                        c.start -= len;
+                    }
+                    recount(clusters, i);
+                }
+                _ => self.clear(),
+            }
+        }
+    }
+}
+
+/// Recomputes excess UTF-16 code units of clusters starting from a given position.
+fn recount(clusters: &mut [Cluster], from: usize) {
+    let mut excess = match from {
+        0 => 0,
+        i => clusters[i - 1].total_excess(),
+    };
+    for c in clusters[from..].iter_mut() {
+        c.excess = excess;
+        excess = c.total_excess();
+    }
+}
+
+/// Returns clusters longer than a single UTF-16 code unit found within a given `text`, which
+/// starts at a given UTF-16 `offset`.
+fn clusters_of(text: &str, mut offset: u32) -> Vec<Cluster> {
+    let mut clusters = Vec::new();
+    for cluster in text.graphemes(true) {
+        let len = cluster.chars().map(|c| c.len_utf16() as u32).sum();
+        if len > 1 {
+            clusters.push(Cluster {
+                start: offset,
+                len,
+                excess: 0,
+            });
+        }
+        offset += len;
+    }
+    clusters
+}
+
+fn scan(mut ptr: Option<BlockPtr>) -> Vec<Cluster> {
+    // grapheme clusters may span over multiple blocks, embeds are represented by a NUL character
+    let mut text = String::new();
+    while let Some(Block::Item(item)) = ptr.as_deref() {
+        if !item.is_deleted() && item.is_countable() {
+            match &item.content {
+                ItemContent::String(s) => text.push_str(s.as_str()),
+                _ => text.push('\0'),
+            }
+        }
+        ptr = item.right;
+    }
+    let mut clusters = clusters_of(&text, 0);
+    recount(&mut clusters, 0);
+    clusters
+}
+
+/// Returns clusters longer than a single UTF-16 code unit found between `start` and `end` UTF-16
+/// offsets of a given `branch`. Returns `None` if any of these offsets is not a grapheme cluster
+/// boundary or that cannot be determined from a limited context read around them.
+fn segment(branch: BranchPtr, start: u32, end: u32) -> Option<Vec<Cluster>> {
+    let from = start.saturating_sub(CONTEXT_LEN);
+    let to = end + CONTEXT_LEN;
+    let (mut ptr, mut offset) = match branch.search_markers.find(from, true) {
+        Some(marker) => (Some(marker.ptr), marker.index),
+        None => (branch.start, 0),
+    };
+    let mut text = String::new();
+    let mut text_offset = None;
+    let mut bounds = (None, None);
+    let mut push = |text: &mut String, c: char, offset: u32| {
+        if offset >= from && offset < to {
+            text_offset.get_or_insert(offset);
+            if offset == start {
+                bounds.0 = Some(text.len());
+            }
+            if offset == end {
+                bounds.1 = Some(text.len());
+            }
+            text.push(c);
+        }
+    };
+    while let Some(Block::Item(item)) = ptr.as_deref() {
+        if offset >= to {
+            break;
+        }
+        if !item.is_deleted() && item.is_countable() {
+            match &item.content {
+                ItemContent::String(s) => {
+                    for c in s.as_str().chars() {
+                        push(&mut text, c, offset);
+                        offset += c.len_utf16() as u32;
+                    }
+                }
+                _ => {
+                    push(&mut text, '\0', offset);
+                    offset += 1;
+                }
+            }
+        }
+        ptr = item.right;
+    }
+    let at_end = ptr.is_none();
+    if at_end && offset == start {
+        bounds.0 = Some(text.len());
+    }
+    if at_end && offset == end {
+        bounds.1 = Some(text.len());
+    }
+    let (lo, hi) = (bounds.0?, bounds.1?);
+    if text.chars().any(is_regional_indicator) {
+        // pairing of regional indicators depends on a number of all preceding ones
+        return None;
+    }
+
+    // a text which doesn't cover an entire branch is embedded between placeholder positions, so
+    // that grapheme cursor reports a lack of context instead of treating it as a text boundary
+    let chunk_start = if text_offset.unwrap_or(offset) == 0 {
+        0
+    } else {
+        1
+    };
+    let len = chunk_start + text.len() + if at_end { 0 } else { 1 };
+    for at in [lo, hi] {
+        let mut cursor = GraphemeCursor::new(chunk_start + at, len, true);
+        if cursor.is_boundary(&text, chunk_start) != Ok(true) {
+            return None;
+        }
+    }
+    Some(clusters_of(&text[lo..hi], start))
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// Executes a local update `f` of a text `branch`, which either inserts or removes content at
+/// a given UTF-16 `index`.
+///
+/// Just like with [update_lines](crate::line_index::update_lines), grapheme index is detached
+/// from a branch for the time of an update, then updated using the difference in branch length
+/// and reattached back.
+pub(crate) fn update_graphemes<F, T>(mut branch: BranchPtr, index: u32, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let mut graphemes = std::mem::take(&mut branch.grapheme_index);
+    let before = branch.content_len;
+    let result = f();
+    let after = branch.content_len;
+    if after >= before {
+        graphemes.inserted(branch, index, after - before);
+    } else {
+        graphemes.removed(branch, index, before - after);
+    }
+    branch.grapheme_index = graphemes;
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::doc::{OffsetKind, Options};
+    use crate::grapheme_index::scan;
+    use crate::test_utils::exchange_updates;
+    use crate::types::BranchPtr;
+    use crate::{Doc, Text};
+    use lib0::any::Any;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    fn grapheme_doc(client_id: u64) -> Doc {
+        Doc::with_options(Options {
+            client_id,
+            offset_kind: OffsetKind::Grapheme,
+            ..Options::default()
+        })
+    }
+
+    /// Checks if cached grapheme index of a given text is equal to the one built from scratch.
+    fn assert_clusters(txt: &Text) {
+        let branch = BranchPtr::from(txt.as_ref());
+        let start = branch.start;
+        if let Some(clusters) = branch.grapheme_index.clusters.as_ref() {
+            assert_eq!(clusters, &scan(start));
+        }
+    }
+
+    #[test]
+    fn grapheme_index_local_updates() {
+        let doc = grapheme_doc(1);
+        let txt = doc.transact().get_text("text");
+        let chunks = [
+            "ab",
+            "😀",
+            "e\u{301}",
+            "\u{301}",
+            "👩\u{200D}",
+            "🔬",
+            "🇵🇱",
+            "\r",
+            "\n",
+        ];
+        let mut expected: Vec<String> = Vec::new();
+        for i in 0..300u32 {
+            let mut txn = doc.transact();
+            let index = (i * 7) % (expected.len() as u32 + 1);
+            let chunk = chunks[i as usize % chunks.len()];
+            txt.insert(&mut txn, index, chunk);
+            let mut s: String = expected.concat();
+            let at: usize = expected[..index as usize].iter().map(|g| g.len()).sum();
+            s.insert_str(at, chunk);
+            expected = s.graphemes(true).map(String::from).collect();
+            if i % 3 == 0 {
+                let index = (i * 13) % expected.len() as u32;
+                let len = 2.min(expected.len() as u32 - index);
+                txt.remove_range(&mut txn, index, len);
+                expected.drain(index as usize..(index + len) as usize);
+                let s: String = expected.concat();
+                expected = s.graphemes(true).map(String::from).collect();
+            }
+            if i % 50 == 0 {
+                txt.insert_embed(&mut txn, 0, Any::Bool(true));
+                expected.insert(0, "\0".to_string());
+            }
+            // grapheme index is built on the first lookup and then updated incrementally
+            assert_eq!(txt.len(), expected.len() as u32);
+            assert_clusters(&txt);
+        }
+
+        // changes which don't touch clusters around them keep the index cached
+        let mut txn = doc.transact();
+        txt.insert(&mut txn, 0, "x");
+        let len = txt.len();
+        txt.insert(&mut txn, len, "😀y");
+        txt.remove_range(&mut txn, 1, 1);
+        let branch = BranchPtr::from(txt.as_ref());
+        assert!(branch.grapheme_index.clusters.is_some());
+        assert_clusters(&txt);
+    }
+
+    #[test]
+    fn grapheme_index_remote_updates() {
+        let d1 = grapheme_doc(1);
+        let t1 = d1.transact().get_text("text");
+        let d2 = grapheme_doc(2);
+        let t2 = d2.transact().get_text("text");
+
+        {
+            let mut txn = d1.transact();
+            t1.insert(&mut txn, 0, "😀 e");
+        }
+        assert_eq!(t1.len(), 3);
+        exchange_updates(&[&d1, &d2]);
+        {
+            // combining mark inserted by a remote peer joins a cluster it's appended to
+            let mut txn = d2.transact();
+            t2.insert(&mut txn, 3, "\u{301}😀");
+        }
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(t1.len(), 4);
+        assert_eq!(
+            t1.convert_offset(3, OffsetKind::Grapheme, OffsetKind::Utf16),
+            5
+        );
+        assert_eq!(
+            t1.convert_offset(6, OffsetKind::Utf16, OffsetKind::Grapheme),
+            3
+        );
+        assert_clusters(&t1);
+
+        {
+            let mut txn = d1.transact();
+            t1.remove_range(&mut txn, 0, 2);
+        }
+        assert_eq!(t1.to_string(), "e\u{301}😀");
+        assert_eq!(t1.len(), 2);
+        assert_clusters(&t1);
+    }
+}
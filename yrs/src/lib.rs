@@ -27,6 +27,7 @@ mod block_store;
 mod doc;
 mod event;
 mod id_set;
+pub mod schema;
 mod store;
 mod transaction;
 pub mod types;
@@ -38,7 +39,10 @@ mod utils;
 mod compatibility_tests;
 
 mod block_iter;
+mod grapheme_index;
+mod line_index;
 mod moving;
+mod search_marker;
 #[cfg(test)]
 mod test_utils;
 
@@ -51,15 +55,26 @@ pub use crate::block_store::StateVector;
 pub use crate::doc::Doc;
 pub use crate::doc::OffsetKind;
 pub use crate::doc::Options;
-pub use crate::event::{AfterTransactionEvent, Subscription, SubscriptionId, UpdateEvent};
+pub use crate::event::{
+    AfterTransactionEvent, RootsEvent, Subscription, SubscriptionId, UpdateEvent,
+};
 pub use crate::id_set::DeleteSet;
 pub use crate::transaction::Transaction;
 pub use crate::types::array::Array;
 pub use crate::types::array::PrelimArray;
+pub use crate::types::counter::Counter;
+pub use crate::types::counter::PrelimCounter;
 pub use crate::types::map::Map;
 pub use crate::types::map::PrelimMap;
 pub use crate::types::text::Text;
+pub use crate::types::tree::PrelimTree;
+pub use crate::types::tree::Tree;
+pub use crate::types::weak::WeakLink;
+pub use crate::types::xml::PrelimXml;
 pub use crate::types::xml::Xml;
 pub use crate::types::xml::XmlElement;
+pub use crate::types::xml::XmlFragment;
+pub use crate::types::xml::XmlHook;
 pub use crate::types::xml::XmlText;
+pub use crate::types::PrelimJson;
 pub use crate::update::Update;
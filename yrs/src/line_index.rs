@@ -0,0 +1,252 @@
+use crate::block::{Block, BlockPtr, ItemContent};
+use crate::types::BranchPtr;
+use crate::OffsetKind;
+
+/// Line index of a text branch: a cached list of offsets at which consecutive lines of text start.
+/// Offsets are expressed using the same units as [Branch::content_len](crate::types::Branch),
+/// which means UTF-16 code units for documents using [OffsetKind::Grapheme].
+///
+/// Line index is updated by local insertions and removals of text, while any other change to
+/// the branch sequence (remote updates, deletions of nested types or moves) simply drops it.
+/// Dropped index is rebuilt from branch blocks on the next lookup.
+#[derive(Debug, Default)]
+pub(crate) struct LineIndex {
+    /// Offsets of starts of all lines but the first one, which always starts at `0`. `None` if
+    /// the index has to be rebuilt.
+    starts: Option<Vec<u32>>,
+}
+
+impl LineIndex {
+    pub fn clear(&mut self) {
+        self.starts = None;
+    }
+
+    /// Returns offsets of starts of all lines but the first one. If index has been dropped, it's
+    /// rebuilt from a block list beginning at a given `start`.
+    pub fn starts(&mut self, start: Option<BlockPtr>, kind: OffsetKind) -> &[u32] {
+        self.starts.get_or_insert_with(|| scan(start, kind))
+    }
+
+    /// Updates line index after a `chunk` of `len` elements has been inserted at a given `index`.
+    fn inserted(&mut self, index: u32, len: u32, chunk: &str, kind: OffsetKind) {
+        if let Some(starts) = self.starts.as_mut() {
+            // line starting right at the index is prepended with inserted chunk
+            let i = starts.partition_point(|&start| start <= index);
+            for start in starts[i..].iter_mut() {
+                *start += len;
+            }
+            let breaks = line_breaks(chunk, kind).map(|offset| index + offset);
+            starts.splice(i..i, breaks);
+        }
+    }
+
+    /// Updates line index after `len` elements have been removed starting from a given `index`.
+    fn removed(&mut self, index: u32, len: u32) {
+        if let Some(starts) = self.starts.as_mut() {
+            let end = index + len;
+            starts.retain(|&start| start <= index || start > end);
+            for start in starts.iter_mut() {
+                if *start > end {
+                    *start -= len;
+                }
+            }
+        }
+    }
+}
+
+/// Returns a length of a given character expressed in units used by [LineIndex].
+pub(crate) fn unit_len(c: char, kind: OffsetKind) -> u32 {
+    match kind {
+        OffsetKind::Bytes => c.len_utf8() as u32,
+        OffsetKind::Utf16 | OffsetKind::Grapheme => c.len_utf16() as u32,
+        OffsetKind::Utf32 => 1,
+    }
+}
+
+/// Returns offsets right after every new line character within a given string.
+fn line_breaks(s: &str, kind: OffsetKind) -> impl Iterator<Item = u32> + '_ {
+    let mut offset = 0;
+    s.chars().filter_map(move |c| {
+        offset += unit_len(c, kind);
+        if c == '\n' {
+            Some(offset)
+        } else {
+            None
+        }
+    })
+}
+
+fn scan(mut ptr: Option<BlockPtr>, kind: OffsetKind) -> Vec<u32> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    while let Some(Block::Item(item)) = ptr.as_deref() {
+        if !item.is_deleted() && item.is_countable() {
+            if let ItemContent::String(s) = &item.content {
+                starts.extend(line_breaks(s.as_str(), kind).map(|o| offset + o));
+            }
+            offset += item.content_len(kind);
+        }
+        ptr = item.right;
+    }
+    starts
+}
+
+/// Executes a local update `f` of a text `branch`, which either inserts a given `chunk` or removes
+/// content starting at a given `index`.
+///
+/// Line index is detached from a branch for the time of an update - so that it won't be dropped
+/// by block integration or deletion - then updated using the difference in branch length and
+/// reattached back.
+pub(crate) fn update_lines<F, T>(mut branch: BranchPtr, index: u32, chunk: &str, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let mut lines = std::mem::take(&mut branch.line_index);
+    let before = branch.content_len;
+    let result = f();
+    let after = branch.content_len;
+    if after >= before {
+        let kind = match branch.store.as_ref() {
+            Some(store) => store.options.offset_kind,
+            None => OffsetKind::Bytes,
+        };
+        lines.inserted(index, after - before, chunk, kind);
+    } else {
+        lines.removed(index, before - after);
+    }
+    branch.line_index = lines;
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::doc::{OffsetKind, Options};
+    use crate::line_index::scan;
+    use crate::test_utils::exchange_updates;
+    use crate::types::BranchPtr;
+    use crate::{Doc, Text};
+    use lib0::any::Any;
+
+    /// Checks if cached line index of a given text is equal to the one built from scratch.
+    fn assert_lines(txt: &Text, doc: &Doc) {
+        let kind = doc.transact().store().options.offset_kind;
+        let branch = BranchPtr::from(txt.as_ref());
+        let start = branch.start;
+        if let Some(starts) = branch.line_index.starts.as_ref() {
+            assert_eq!(starts, &scan(start, kind));
+        }
+    }
+
+    #[test]
+    fn line_index_local_updates() {
+        let doc = Doc::with_options(Options {
+            offset_kind: OffsetKind::Utf16,
+            ..Options::default()
+        });
+        let txt = doc.transact().get_text("text");
+        let mut expected: Vec<char> = Vec::new();
+        for i in 0..300u32 {
+            let mut txn = doc.transact();
+            let index = (i * 7) % (expected.len() as u32 + 1);
+            let chunk = if i % 4 == 0 { "a\nb" } else { "ą\n" };
+            txt.insert_with_offset_kind(&mut txn, index, chunk, OffsetKind::Utf32);
+            expected.splice(index as usize..index as usize, chunk.chars());
+            if i % 3 == 0 {
+                let index = (i * 13) % expected.len() as u32;
+                let len = 3.min(expected.len() as u32 - index);
+                txt.remove_range_with_offset_kind(&mut txn, index, len, OffsetKind::Utf32);
+                expected.drain(index as usize..(index + len) as usize);
+            }
+            if i % 50 == 0 {
+                txt.insert_embed(&mut txn, 0, Any::Bool(true));
+                expected.insert(0, '\0');
+            }
+            // line index is built on the first lookup and then updated incrementally
+            let expected: String = expected.iter().collect();
+            assert_eq!(txt.line_count(), expected.split('\n').count() as u32);
+            assert_lines(&txt, &doc);
+        }
+    }
+
+    #[test]
+    fn line_column_conversions() {
+        let d1 = Doc::with_options(Options {
+            client_id: 1,
+            offset_kind: OffsetKind::Utf16,
+            ..Options::default()
+        });
+        let t1 = d1.transact().get_text("text");
+        let d2 = Doc::with_client_id(2);
+        let t2 = d2.transact().get_text("text");
+
+        {
+            let mut txn = d1.transact();
+            t1.insert(&mut txn, 0, "fn main() {\n    \"😀\"\n}");
+        }
+        assert_eq!(t1.line_count(), 3);
+        assert_eq!(t1.line(1), Some("    \"😀\"".to_string()));
+        assert_eq!(t1.line(3), None);
+        assert_eq!(t1.offset_to_line_column(0), Some((0, 0)));
+        assert_eq!(t1.offset_to_line_column(11), Some((0, 11)));
+        assert_eq!(t1.offset_to_line_column(12), Some((1, 0)));
+        assert_eq!(t1.offset_to_line_column(21), Some((2, 0)));
+        assert_eq!(t1.offset_to_line_column(22), Some((2, 1)));
+        assert_eq!(t1.offset_to_line_column(23), None);
+        assert_eq!(t1.line_column_to_offset(1, 8), Some(20));
+        assert_eq!(t1.line_column_to_offset(1, 9), None);
+        assert_eq!(t1.line_column_to_offset(2, 1), Some(22));
+        assert_eq!(t1.line_column_to_offset(3, 0), None);
+
+        // remote changes drop line index, which is rebuilt on the next lookup
+        exchange_updates(&[&d1, &d2]);
+        {
+            let mut txn = d2.transact();
+            t2.insert(&mut txn, 0, "// main\n");
+        }
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(t1.line_count(), 4);
+        assert_eq!(t1.line(2), Some("    \"😀\"".to_string()));
+        assert_eq!(t1.offset_to_line_column(21), Some((2, 1)));
+        assert_lines(&t1, &d1);
+
+        {
+            let mut txn = d1.transact();
+            t1.remove_range(&mut txn, 2, 6);
+        }
+        assert_eq!(t1.to_string(), "//fn main() {\n    \"😀\"\n}");
+        assert_eq!(t1.line_count(), 3);
+        assert_eq!(t1.line(0), Some("//fn main() {".to_string()));
+        assert_lines(&t1, &d1);
+    }
+
+    #[test]
+    fn line_column_conversions_graphemes() {
+        let doc = Doc::with_options(Options {
+            offset_kind: OffsetKind::Grapheme,
+            ..Options::default()
+        });
+        let txt = doc.transact().get_text("text");
+        {
+            let mut txn = doc.transact();
+            txt.insert(&mut txn, 0, "👩‍🔬 e\u{301}\n🇵🇱x\ny");
+        }
+        assert_eq!(txt.len(), 8);
+        assert_eq!(txt.offset_to_line_column(3), Some((0, 3)));
+        assert_eq!(txt.offset_to_line_column(4), Some((1, 0)));
+        assert_eq!(txt.offset_to_line_column(6), Some((1, 2)));
+        assert_eq!(txt.offset_to_line_column(8), Some((2, 1)));
+        assert_eq!(txt.offset_to_line_column(9), None);
+        assert_eq!(txt.line_column_to_offset(1, 1), Some(5));
+        assert_eq!(txt.line_column_to_offset(1, 3), None);
+        assert_eq!(txt.line_column_to_offset(2, 1), Some(8));
+
+        {
+            let mut txn = doc.transact();
+            txt.insert(&mut txn, 5, "😀\n");
+        }
+        assert_eq!(txt.line(1), Some("🇵🇱😀".to_string()));
+        assert_eq!(txt.offset_to_line_column(7), Some((2, 0)));
+        assert_eq!(txt.line_column_to_offset(1, 2), Some(6));
+        assert_lines(&txt, &doc);
+    }
+}
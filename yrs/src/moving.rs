@@ -6,7 +6,7 @@ use crate::updates::encoder::{Encode, Encoder};
 use crate::{Transaction, ID};
 use lib0::error::Error;
 use std::collections::HashSet;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, RangeInclusive};
 
 /// Association type. If true, associate with right block. Otherwise with the left one.
 pub type Assoc = bool;
@@ -24,6 +24,10 @@ pub struct Move {
     /// Ideally, we should probably just re-iterate the document and re-integrate all moved items.
     /// This is fast enough and reduces memory footprint significantly.
     pub(crate) overrides: Option<HashSet<BlockPtr>>,
+
+    /// Moves which override this one. As long as any of them is alive, a deleted move cannot be
+    /// garbage collected, as it may need to be reintegrated once overriding move gets deleted.
+    pub(crate) overridden_by: Option<HashSet<BlockPtr>>,
 }
 
 impl Move {
@@ -33,6 +37,7 @@ impl Move {
             end,
             priority,
             overrides: None,
+            overridden_by: None,
         }
     }
 
@@ -97,24 +102,79 @@ impl Move {
         e.insert(ptr);
     }
 
+    /// Marks a move stored under `ptr` as overridden by another move stored under `by`.
+    fn mark_overridden(mut ptr: BlockPtr, by: BlockPtr) {
+        if let Block::Item(item) = ptr.deref_mut() {
+            if let ItemContent::Move(m) = &mut item.content {
+                let e = m.overridden_by.get_or_insert_with(HashSet::default);
+                e.insert(by);
+            }
+        }
+    }
+
+    /// Checks if this move is overridden by any other move, which is either alive or (if it has
+    /// been deleted) is overridden itself. Such moves cannot be garbage collected.
+    pub(crate) fn is_overridden(&self, visited: &mut HashSet<BlockPtr>) -> bool {
+        if let Some(overridden_by) = &self.overridden_by {
+            for &ptr in overridden_by.iter() {
+                if !visited.insert(ptr) {
+                    continue;
+                }
+                if let Block::Item(item) = ptr.deref() {
+                    if let ItemContent::Move(m) = &item.content {
+                        if !item.is_deleted() || m.is_overridden(visited) {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Releases all moves overridden by a garbage collected move stored under `ptr`. Deleted
+    /// moves, which are no longer overridden by anything, are garbage collected as well.
+    pub(crate) fn release_overrides(&mut self, ptr: BlockPtr) {
+        if let Some(overrides) = self.overrides.take() {
+            for mut overridden in overrides {
+                if let Block::Item(item) = overridden.deref_mut() {
+                    if let ItemContent::Move(m) = &mut item.content {
+                        if let Some(overridden_by) = &mut m.overridden_by {
+                            overridden_by.remove(&ptr);
+                        }
+                        if item.is_deleted() {
+                            overridden.gc(false);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     pub(crate) fn integrate_block(&mut self, txn: &mut Transaction, item: BlockPtr) {
         let (init, end) = self.get_moved_coords(txn);
         let mut max_priority = 0i32;
         let adapt_priority = self.priority < 0;
+        if adapt_priority {
+            // priority must be known upfront: overridden moves may be reintegrated and compete
+            // for the remaining elements of this range before it has been fully integrated
+            let mut start = init;
+            while start != end {
+                if let Some(Block::Item(i)) = start.as_deref() {
+                    max_priority = max_priority.max(Self::priority_of(i.moved));
+                    start = i.right;
+                } else {
+                    break;
+                }
+            }
+            self.priority = max_priority + 1;
+        }
         let mut start = init;
         while start != end && start.is_some() {
             let start_ptr = start.unwrap().clone();
             if let Some(Block::Item(start_item)) = start.as_deref_mut() {
                 let mut prev_move = start_item.moved;
-                let next_prio = if let Some(Block::Item(m)) = prev_move.as_deref() {
-                    if let ItemContent::Move(next) = &m.content {
-                        next.priority
-                    } else {
-                        -1
-                    }
-                } else {
-                    -1
-                };
+                let next_prio = Self::priority_of(prev_move);
 
                 #[inline]
                 fn is_lower(a: &ID, b: &ID) -> bool {
@@ -136,6 +196,7 @@ impl Move {
                             }
                         }
                         self.push_override(moved_ptr);
+                        Self::mark_overridden(moved_ptr, item);
                         if Some(start_ptr) != init {
                             // only add this to mergeStructs if this is not the first item
                             txn.merge_blocks.push(start_item.id);
@@ -152,7 +213,7 @@ impl Move {
                             txn.prev_moved.insert(start_ptr, prev_move);
                         }
                     }
-                    start_item.moved = Some(item);
+                    set_moved(txn, start_ptr, Some(item));
                     if !start_item.is_deleted() {
                         if let ItemContent::Move(m) = &start_item.content {
                             if m.find_move_loop(txn, start_ptr, &mut HashSet::from([item])) {
@@ -173,8 +234,18 @@ impl Move {
         }
 
         if adapt_priority {
-            self.priority = max_priority + 1;
+            self.priority = self.priority.max(max_priority + 1);
+        }
+    }
+
+    /// Returns a priority of a move referenced by a given `ptr` or -1 if there's none.
+    fn priority_of(ptr: Option<BlockPtr>) -> i32 {
+        if let Some(Block::Item(item)) = ptr.as_deref() {
+            if let ItemContent::Move(m) = &item.content {
+                return m.priority;
+            }
         }
+        -1
     }
 
     pub(crate) fn delete(&self, txn: &mut Transaction, item: BlockPtr) {
@@ -194,7 +265,7 @@ impl Move {
                             // Normal case: item has been moved by this move and it has not been created & deleted in the same transaction
                             txn.prev_moved.insert(start_ptr, item);
                         }
-                        i.moved = None;
+                        set_moved(txn, start_ptr, None);
                     }
                     start = i.right;
                     continue;
@@ -203,7 +274,11 @@ impl Move {
             break;
         }
 
-        fn reintegrate(mut ptr: BlockPtr, txn: &mut Transaction) {
+        fn reintegrate(mut ptr: BlockPtr, txn: &mut Transaction, visited: &mut HashSet<BlockPtr>) {
+            if !visited.insert(ptr) {
+                // concurrent moves may override each other
+                return;
+            }
             let ptr_copy = ptr.clone();
             if let Block::Item(item) = ptr.deref_mut() {
                 let deleted = item.is_deleted();
@@ -212,7 +287,7 @@ impl Move {
                         // potentially we can integrate the items that reIntegrateItem overrides
                         if let Some(overrides) = &content.overrides {
                             for &inner in overrides.iter() {
-                                reintegrate(inner, txn);
+                                reintegrate(inner, txn, visited);
                             }
                         }
                     } else {
@@ -223,8 +298,199 @@ impl Move {
         }
 
         if let Some(overrides) = &self.overrides {
+            let mut visited = HashSet::new();
             for &ptr in overrides {
-                reintegrate(ptr, txn);
+                reintegrate(ptr, txn, &mut visited);
+            }
+        }
+    }
+}
+
+/// Moves elements of a `source` sequence found within a given `range` of indexes into a `target`
+/// sequence at given `index`. Source and target can be the same sequence, in which case `index`
+/// should not be placed within moved range. Moved elements keep their identity, so that concurrent
+/// updates made to them are not lost.
+///
+/// `assoc` flags are used to mark if moved range should include elements that might have been
+/// inserted concurrently at its start and end edges.
+pub(crate) fn move_range(
+    txn: &mut Transaction,
+    source: BranchPtr,
+    range: RangeInclusive<u32>,
+    assoc: (Assoc, Assoc),
+    target: BranchPtr,
+    index: u32,
+) {
+    let ranges = visible_ranges(txn, source, range, assoc);
+    let mut walker = BlockIter::new(target);
+    if !walker.try_forward(txn, index) {
+        panic!("Index {} is outside of the range of a sequence", index);
+    }
+    // each move is inserted right after the previous one, as integrating a move may change
+    // indexes of the target sequence, eg. by reintegrating the moves it overrides
+    for (start, end) in ranges {
+        walker.insert_move(txn, start, end);
+    }
+}
+
+/// Splits a `range` of elements visible in a `branch` sequence into ranges of adjacent blocks, each
+/// one defined by a pair of relative positions.
+///
+/// Elements visible next to each other don't have to be neighbors in a block list, eg. when one of
+/// them has been moved. A single [Move] defined over such elements would capture blocks placed
+/// in between them as well, which is why each adjacent range must be moved on its own.
+fn visible_ranges(
+    txn: &mut Transaction,
+    branch: BranchPtr,
+    range: RangeInclusive<u32>,
+    assoc: (Assoc, Assoc),
+) -> Vec<(RelativePosition, RelativePosition)> {
+    let (start, end) = range.into_inner();
+    let mut walker = BlockIter::new(branch);
+    if !walker.try_forward(txn, start) {
+        panic!("Index {} is outside of the range of a sequence", start);
+    }
+    let mut ranges: Vec<(ID, ID)> = Vec::new();
+    for index in start..=end {
+        if index != start {
+            walker.forward(txn, 1);
+        }
+        let id = match walker.next_item() {
+            Some(ptr) if !walker.finished() => {
+                ID::new(ptr.id().client, ptr.id().clock + walker.rel())
+            }
+            _ => panic!("Index {} is outside of the range of a sequence", end),
+        };
+        match ranges.last_mut() {
+            Some((_, last)) if is_adjacent(txn, last, &id) => *last = id,
+            _ => ranges.push((id, id)),
+        }
+    }
+
+    let (assoc_start, assoc_end) = assoc;
+    let last_range = ranges.len() - 1;
+    let mut result = Vec::with_capacity(ranges.len());
+    for (i, (first, last)) in ranges.into_iter().enumerate() {
+        let mut left = RelativePosition::create(first, true);
+        if i == 0 && !assoc_start {
+            // range starts right after the element preceding it
+            if let Some(pos) = RelativePosition::from_type_index(txn, branch, start, false) {
+                if is_adjacent(txn, &pos.id, &first) {
+                    left = pos;
+                }
+            }
+        }
+        let mut right = RelativePosition::create(last, false);
+        if i == last_range && assoc_end && end + 1 < branch.content_len() {
+            // range ends right before the element following it
+            if let Some(pos) = RelativePosition::from_type_index(txn, branch, end + 1, true) {
+                if is_adjacent(txn, &last, &pos.id) {
+                    right = pos;
+                }
+            }
+        }
+        result.push((left, right));
+    }
+    result
+}
+
+/// Checks if an element identified by `next` directly follows the `prev` one in the block list,
+/// skipping over deleted blocks.
+fn is_adjacent(txn: &Transaction, prev: &ID, next: &ID) -> bool {
+    if let Some(ptr) = txn.store().blocks.get_block(prev) {
+        if let Block::Item(item) = ptr.deref() {
+            if prev.clock + 1 < item.id.clock + item.len {
+                return next.client == prev.client && next.clock == prev.clock + 1;
+            }
+            let mut right = item.right;
+            while let Some(Block::Item(i)) = right.as_deref() {
+                if &i.id == next {
+                    return true;
+                } else if !i.is_deleted() {
+                    return false;
+                }
+                right = i.right;
+            }
+        }
+    }
+    false
+}
+
+/// Returns a branch, which indexed sequence contains a visible block under a given `ptr`. Usually
+/// it's a block's parent, however moved blocks are visible at the position of their move, which
+/// may belong to a different sequence. Returns `None` if moves form a cycle.
+pub(crate) fn visible_parent(ptr: BlockPtr) -> Option<BranchPtr> {
+    let mut current = ptr;
+    let mut visited = Vec::new();
+    while let Block::Item(item) = current.deref() {
+        match item.moved {
+            Some(moved) if visited.contains(&moved) => return None,
+            Some(moved) => {
+                visited.push(moved);
+                current = moved;
+            }
+            None => return item.parent.as_branch().cloned(),
+        }
+    }
+    None
+}
+
+/// Sets a move, which given block is part of. If this changes a sequence, which block is visible
+/// in, lengths of both affected sequences are updated.
+fn set_moved(txn: &mut Transaction, mut ptr: BlockPtr, moved: Option<BlockPtr>) {
+    let before = visible_parent(ptr);
+    if let Block::Item(item) = ptr.deref_mut() {
+        item.moved = moved;
+    }
+    let after = visible_parent(ptr);
+    if before != after {
+        transfer_len(txn, ptr, before, after, &mut HashSet::new());
+    }
+}
+
+/// Moves length of a block under given `ptr` from one sequence to another. If block is a move
+/// itself, lengths of all elements moved by it are transferred as well.
+pub(crate) fn transfer_len(
+    txn: &mut Transaction,
+    ptr: BlockPtr,
+    from: Option<BranchPtr>,
+    to: Option<BranchPtr>,
+    visited: &mut HashSet<BlockPtr>,
+) {
+    if from == to || !visited.insert(ptr) {
+        return;
+    }
+    if let Block::Item(item) = ptr.deref() {
+        if item.is_countable() && !item.is_deleted() {
+            let encoding = txn.store().options.offset_kind;
+            let content_len = item.content_len(encoding);
+            if let Some(mut branch) = from {
+                branch.block_len -= item.len();
+                branch.content_len -= content_len;
+                branch.search_markers.clear();
+                branch.line_index.clear();
+                branch.grapheme_index.clear();
+                txn.add_changed_type(branch, None);
+            }
+            if let Some(mut branch) = to {
+                branch.block_len += item.len();
+                branch.content_len += content_len;
+                branch.search_markers.clear();
+                branch.line_index.clear();
+                branch.grapheme_index.clear();
+                txn.add_changed_type(branch, None);
+            }
+        } else if let ItemContent::Move(m) = &item.content {
+            let (mut start, end) = m.get_moved_coords(txn);
+            while start != end {
+                if let Some(Block::Item(i)) = start.as_deref() {
+                    if i.moved == Some(ptr) {
+                        transfer_len(txn, start.unwrap(), from, to, visited);
+                    }
+                    start = i.right;
+                } else {
+                    break;
+                }
             }
         }
     }
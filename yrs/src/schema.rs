@@ -0,0 +1,505 @@
+//! Building blocks for strongly typed views over Yrs shared collections.
+//!
+//! Shared types like [Map] or [Array] are schema-less: they can store any [Value] under any key
+//! or index, which means that every read needs to check if a returned value has an expected
+//! shape. This module defines a [Field] trait describing how a single value can be read from and
+//! written into a shared collection, together with a set of implementations for commonly used
+//! primitives and shared types.
+//!
+//! These are primarily meant to be used by code generated with `#[derive(YMap)]` macro from the
+//! `yrs-derive` crate, which produces typed getters, setters and observers for a [Map] based on
+//! a shape of a Rust struct:
+//!
+//! ```ignore
+//! use yrs::schema::ArrayOf;
+//! use yrs::Text;
+//! use yrs_derive::YMap;
+//!
+//! #[derive(YMap)]
+//! struct Task {
+//!     title: Text,
+//!     done: bool,
+//!     tags: ArrayOf<String>,
+//! }
+//!
+//! let doc = Doc::new();
+//! let mut txn = doc.transact();
+//! let task = TaskRef::from(txn.get_map("task"));
+//! task.set_title(&mut txn, "buy milk");
+//! task.set_done(&mut txn, false);
+//! assert_eq!(task.done(), Ok(false));
+//! ```
+
+use crate::block::Prelim;
+use crate::event::Subscription;
+use crate::types::map::MapEvent;
+use crate::types::text::PrelimTextBuf;
+use crate::types::{EntryChange, Path, Value};
+use crate::{Array, Map, PrelimArray, PrelimMap, Text, Transaction};
+use lib0::any::Any;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+
+/// Error returned when a [Value] stored inside of a shared collection doesn't match the shape
+/// expected by a typed schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// Map didn't contain an entry for a field that is not optional.
+    MissingField(&'static str),
+    /// Value was of a different type than the one expected by a schema.
+    TypeMismatch {
+        /// Name of a type expected by a schema.
+        expected: &'static str,
+        /// Name of a type that was actually found.
+        found: &'static str,
+    },
+    /// Value stored under a given field of a map could not be converted.
+    InvalidField(&'static str, Box<SchemaError>),
+}
+
+impl SchemaError {
+    /// Creates a [SchemaError::TypeMismatch] for a given `value`.
+    pub fn mismatch(expected: &'static str, value: &Value) -> Self {
+        SchemaError::TypeMismatch {
+            expected,
+            found: type_name(value),
+        }
+    }
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::MissingField(key) => write!(f, "missing field `{}`", key),
+            SchemaError::TypeMismatch { expected, found } => {
+                write!(f, "expected {} but found {}", expected, found)
+            }
+            SchemaError::InvalidField(key, cause) => {
+                write!(f, "invalid field `{}`: {}", key, cause)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Returns a human readable name of a type of a given `value`.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Any(Any::Null) => "null",
+        Value::Any(Any::Undefined) => "undefined",
+        Value::Any(Any::Bool(_)) => "bool",
+        Value::Any(Any::Number(_)) => "number",
+        Value::Any(Any::BigInt(_)) => "bigint",
+        Value::Any(Any::String(_)) => "string",
+        Value::Any(Any::Buffer(_)) => "buffer",
+        Value::Any(Any::Array(_)) => "array",
+        Value::Any(Any::Map(_)) => "object",
+        Value::YText(_) => "Text",
+        Value::YArray(_) => "Array",
+        Value::YMap(_) => "Map",
+        Value::YXmlElement(_) => "XmlElement",
+        Value::YXmlFragment(_) => "XmlFragment",
+        Value::YXmlText(_) => "XmlText",
+        Value::YXmlHook(_) => "XmlHook",
+        Value::YWeakLink(_) => "WeakLink",
+        Value::YCounter(_) => "Counter",
+        Value::YTree(_) => "Tree",
+    }
+}
+
+/// Trait implemented by types, which can be used as strongly typed fields of a schema generated
+/// with `#[derive(YMap)]`.
+pub trait Field: Sized {
+    /// Preliminary value used to write a field into a shared collection.
+    type Prelim: Prelim;
+
+    /// Tries to convert a [Value] read from a shared collection into current type.
+    fn from_value(value: Value) -> Result<Self, SchemaError>;
+
+    /// Called when a map entry for a current field is not present. By default it returns
+    /// [SchemaError::MissingField], but optional fields may override it.
+    fn from_missing(key: &'static str) -> Result<Self, SchemaError> {
+        Err(SchemaError::MissingField(key))
+    }
+}
+
+/// Reads a value stored under a given `key` of a `map` and converts it into a typed [Field].
+/// Conversion errors are annotated with a field `key`.
+pub fn get_field<T: Field>(map: &Map, key: &'static str) -> Result<T, SchemaError> {
+    match map.get(key) {
+        None => T::from_missing(key),
+        Some(value) => {
+            T::from_value(value).map_err(|e| SchemaError::InvalidField(key, Box::new(e)))
+        }
+    }
+}
+
+impl Field for Value {
+    type Prelim = Any;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        Ok(value)
+    }
+}
+
+impl Field for Any {
+    type Prelim = Any;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::Any(any) => Ok(any),
+            other => Err(SchemaError::mismatch("any", &other)),
+        }
+    }
+}
+
+impl Field for bool {
+    type Prelim = bool;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::Any(Any::Bool(v)) => Ok(v),
+            other => Err(SchemaError::mismatch("bool", &other)),
+        }
+    }
+}
+
+impl Field for f64 {
+    type Prelim = f64;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::Any(Any::Number(v)) => Ok(v),
+            Value::Any(Any::BigInt(v)) => Ok(v as f64),
+            other => Err(SchemaError::mismatch("number", &other)),
+        }
+    }
+}
+
+impl Field for i64 {
+    type Prelim = i64;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::Any(Any::BigInt(v)) => Ok(v),
+            // numbers written by Yjs are always floats, even when they represent integers
+            Value::Any(Any::Number(v)) if v.fract() == 0.0 => Ok(v as i64),
+            other => Err(SchemaError::mismatch("integer", &other)),
+        }
+    }
+}
+
+impl Field for String {
+    type Prelim = String;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::Any(Any::String(v)) => Ok(v.into()),
+            other => Err(SchemaError::mismatch("string", &other)),
+        }
+    }
+}
+
+impl<T: Field> Field for Option<T> {
+    type Prelim = T::Prelim;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::Any(Any::Null) | Value::Any(Any::Undefined) => Ok(None),
+            other => Ok(Some(T::from_value(other)?)),
+        }
+    }
+
+    fn from_missing(_key: &'static str) -> Result<Self, SchemaError> {
+        Ok(None)
+    }
+}
+
+impl Field for Text {
+    type Prelim = PrelimTextBuf;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::YText(v) => Ok(v),
+            other => Err(SchemaError::mismatch("Text", &other)),
+        }
+    }
+}
+
+impl Field for Map {
+    type Prelim = PrelimMap<Any>;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::YMap(v) => Ok(v),
+            other => Err(SchemaError::mismatch("Map", &other)),
+        }
+    }
+}
+
+impl Field for Array {
+    type Prelim = PrelimArray<Vec<Any>, Any>;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        match value {
+            Value::YArray(v) => Ok(v),
+            other => Err(SchemaError::mismatch("Array", &other)),
+        }
+    }
+}
+
+/// A typed view over an [Array], which elements are all expected to be of type `T`. [Array] is
+/// not generic over its elements, so this wrapper is used as a schema field type in places where
+/// `Array<T>` would be expected. Untyped [Array] can be used as a field type as well.
+///
+/// Since [Array] itself is schema-less, it's still possible for other peers to insert elements
+/// of a different type. For this reason all read operations return a [Result].
+#[derive(Debug)]
+pub struct ArrayOf<T> {
+    inner: Array,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Field> ArrayOf<T> {
+    /// Returns an untyped [Array] which is wrapped by current view.
+    pub fn inner(&self) -> &Array {
+        &self.inner
+    }
+
+    /// Returns a number of elements stored within current array.
+    pub fn len(&self) -> u32 {
+        self.inner.len()
+    }
+
+    /// Checks if current array has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an element stored under a given `index` or `None` if `index` was outside of
+    /// array bounds.
+    pub fn get(&self, index: u32) -> Option<Result<T, SchemaError>> {
+        self.inner.get(index).map(T::from_value)
+    }
+
+    /// Inserts a `value` at a given `index`. Inserting at index equal to array's length will
+    /// append it at the end of it.
+    pub fn insert<V: Into<T::Prelim>>(&self, txn: &mut Transaction, index: u32, value: V) {
+        self.inner.insert(txn, index, value.into())
+    }
+
+    /// Inserts a `value` at the end of current array.
+    pub fn push_back<V: Into<T::Prelim>>(&self, txn: &mut Transaction, value: V) {
+        self.inner.push_back(txn, value.into())
+    }
+
+    /// Inserts a `value` at the beginning of current array.
+    pub fn push_front<V: Into<T::Prelim>>(&self, txn: &mut Transaction, value: V) {
+        self.inner.push_front(txn, value.into())
+    }
+
+    /// Removes a single element at a given `index`.
+    pub fn remove(&self, txn: &mut Transaction, index: u32) {
+        self.inner.remove(txn, index)
+    }
+
+    /// Returns an iterator over typed elements of current array.
+    pub fn iter(&self) -> impl Iterator<Item = Result<T, SchemaError>> + '_ {
+        self.inner.iter().map(T::from_value)
+    }
+
+    /// Converts all elements of current array into a vector, failing on the first element which
+    /// doesn't match the expected type.
+    pub fn to_vec(&self) -> Result<Vec<T>, SchemaError> {
+        self.iter().collect()
+    }
+}
+
+impl<T> Clone for ArrayOf<T> {
+    fn clone(&self) -> Self {
+        ArrayOf::from(self.inner.clone())
+    }
+}
+
+impl<T> PartialEq for ArrayOf<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T> From<Array> for ArrayOf<T> {
+    fn from(inner: Array) -> Self {
+        ArrayOf {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> From<ArrayOf<T>> for Array {
+    fn from(array: ArrayOf<T>) -> Self {
+        array.inner
+    }
+}
+
+impl<T: Field> Field for ArrayOf<T> {
+    type Prelim = PrelimArray<Vec<T::Prelim>, T::Prelim>;
+
+    fn from_value(value: Value) -> Result<Self, SchemaError> {
+        Array::from_value(value).map(ArrayOf::from)
+    }
+}
+
+/// Trait implemented by field enums generated by `#[derive(YMap)]`. It maps between names of
+/// map entries and their corresponding strongly typed fields.
+pub trait FieldKey: Sized + Copy {
+    /// Returns a map key used by current field.
+    fn key(&self) -> &'static str;
+
+    /// Returns a field corresponding to a given map `key`, if it's known to a schema.
+    fn from_key(key: &str) -> Option<Self>;
+}
+
+/// A typed counterpart of a [MapEvent], which recognizes keys defined by a schema `K`.
+pub struct TypedMapEvent<'a, K> {
+    inner: &'a MapEvent,
+    _marker: PhantomData<K>,
+}
+
+impl<'a, K: FieldKey + Ord> TypedMapEvent<'a, K> {
+    pub(crate) fn new(inner: &'a MapEvent) -> Self {
+        TypedMapEvent {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns an untyped map event.
+    pub fn inner(&self) -> &'a MapEvent {
+        self.inner
+    }
+
+    /// Returns a [Map] instance which emitted this event.
+    pub fn target(&self) -> &Map {
+        self.inner.target()
+    }
+
+    /// Returns a path from root type down to [Map] instance which emitted this event.
+    pub fn path(&self) -> Path {
+        self.inner.path()
+    }
+
+    /// Returns a list of schema fields changed within a scope of a current transaction, sorted
+    /// by their declaration order. Changes made to keys unknown to a schema are skipped.
+    pub fn fields(&self, txn: &Transaction) -> Vec<(K, &'a EntryChange)> {
+        let mut fields: Vec<_> = self
+            .inner
+            .keys(txn)
+            .iter()
+            .filter_map(|(key, change)| Some((K::from_key(key)?, change)))
+            .collect();
+        fields.sort_by_key(|(field, _)| *field);
+        fields
+    }
+}
+
+/// Subscribes a callback to a `map` which receives events typed with a schema fields `K`.
+/// Used by observers generated with `#[derive(YMap)]`.
+pub fn observe_typed<K, F>(map: &mut Map, f: F) -> Subscription<MapEvent>
+where
+    K: FieldKey + Ord,
+    F: Fn(&Transaction, &TypedMapEvent<K>) + 'static,
+{
+    map.observe(move |txn, e| f(txn, &TypedMapEvent::new(e)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::schema::{get_field, ArrayOf, Field, SchemaError};
+    use crate::types::Value;
+    use crate::{Doc, Text};
+    use lib0::any::Any;
+
+    #[test]
+    fn primitive_fields() {
+        assert_eq!(bool::from_value(Value::Any(Any::Bool(true))), Ok(true));
+        assert_eq!(i64::from_value(Value::Any(Any::Number(3.0))), Ok(3));
+        assert_eq!(
+            i64::from_value(Value::Any(Any::Number(3.5))),
+            Err(SchemaError::TypeMismatch {
+                expected: "integer",
+                found: "number"
+            })
+        );
+        assert_eq!(
+            Option::<String>::from_value(Value::Any(Any::Null)),
+            Ok(None)
+        );
+        assert_eq!(
+            String::from_value(Value::Any(Any::Bool(false))),
+            Err(SchemaError::TypeMismatch {
+                expected: "string",
+                found: "bool"
+            })
+        );
+    }
+
+    #[test]
+    fn map_fields() {
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let map = txn.get_map("map");
+        map.insert(&mut txn, "title", <Text as Field>::Prelim::from("hello"));
+        map.insert(&mut txn, "done", "no");
+        map.insert(&mut txn, "tags", vec!["a".to_string(), "b".to_string()]);
+
+        let title: Text = get_field(&map, "title").unwrap();
+        assert_eq!(title.to_string(), "hello");
+
+        let done: Result<bool, _> = get_field(&map, "done");
+        assert_eq!(
+            done,
+            Err(SchemaError::InvalidField(
+                "done",
+                Box::new(SchemaError::TypeMismatch {
+                    expected: "bool",
+                    found: "string"
+                })
+            ))
+        );
+
+        let missing: Result<bool, _> = get_field(&map, "missing");
+        assert_eq!(missing, Err(SchemaError::MissingField("missing")));
+        let missing: Option<bool> = get_field(&map, "missing").unwrap();
+        assert_eq!(missing, None);
+
+        // `tags` were inserted as a JSON-like array, not as shared Array
+        let tags: Result<ArrayOf<String>, _> = get_field(&map, "tags");
+        assert!(tags.is_err());
+    }
+
+    #[test]
+    fn typed_array() {
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let map = txn.get_map("map");
+        map.insert(
+            &mut txn,
+            "tags",
+            <ArrayOf<String> as Field>::Prelim::from(vec![]),
+        );
+
+        let tags: ArrayOf<String> = get_field(&map, "tags").unwrap();
+        tags.push_back(&mut txn, "b");
+        tags.push_front(&mut txn, "a");
+        tags.inner().push_back(&mut txn, 1.0);
+
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags.get(1), Some(Ok("b".to_string())));
+        assert!(tags.to_vec().is_err());
+
+        tags.remove(&mut txn, 2);
+        assert_eq!(tags.to_vec(), Ok(vec!["a".to_string(), "b".to_string()]));
+    }
+}
@@ -0,0 +1,528 @@
+use crate::block::{Block, BlockPtr, ItemContent};
+use crate::doc::OffsetKind;
+use crate::types::{Attrs, BranchPtr};
+use crate::Transaction;
+use std::ops::Deref;
+
+/// Maximum number of search markers cached by a single branch.
+const MAX_SEARCH_MARKERS: usize = 80;
+
+/// Search marker is a cached position of a block within an indexed sequence of a
+/// [Branch](crate::types::Branch). It allows to start a lookup for a given index from the marker
+/// closest to it instead of walking over the entire block list from its start.
+///
+/// Markers only point to non-deleted, countable blocks, which are not part of any moved range.
+#[derive(Debug, Clone)]
+pub(crate) struct SearchMarker {
+    /// Pointer to a block, which content starts at a marked `index`.
+    pub ptr: BlockPtr,
+    /// Index of the first element of a marked block, expressed using document's offset kind.
+    pub index: u32,
+    /// Formatting attributes active right before a marked block. Used only by text types.
+    pub attrs: Option<Box<Attrs>>,
+    timestamp: u32,
+}
+
+/// A collection of [SearchMarker]s cached by a branch. Markers are shifted by local indexed
+/// insertions and deletions, while any other change to the branch sequence (remote updates,
+/// formatting or moves) repairs them using [repair_markers].
+#[derive(Debug, Default)]
+pub(crate) struct SearchMarkers {
+    markers: Vec<SearchMarker>,
+    clock: u32,
+}
+
+impl SearchMarkers {
+    pub fn clear(&mut self) {
+        self.markers.clear();
+    }
+
+    /// Returns a marker placed before a given `index` (or at it, if `inclusive` is set), which is
+    /// the closest one to that index.
+    pub fn find(&self, index: u32, inclusive: bool) -> Option<&SearchMarker> {
+        self.markers
+            .iter()
+            .filter(|m| m.index < index || (inclusive && m.index == index))
+            .filter(|m| is_markable(m.ptr))
+            .max_by_key(|m| m.index)
+    }
+
+    /// Marks a position of a block under given `ptr`, which starts at given `index`. `len` is
+    /// a current length of a branch sequence and is used to reuse markers placed nearby.
+    pub fn mark(&mut self, ptr: BlockPtr, index: u32, attrs: Option<Box<Attrs>>, len: u32) {
+        if !is_markable(ptr) {
+            return;
+        }
+        self.clock += 1;
+        let marker = SearchMarker {
+            ptr,
+            index,
+            attrs,
+            timestamp: self.clock,
+        };
+        let threshold = len / MAX_SEARCH_MARKERS as u32;
+        let slot = self
+            .markers
+            .iter()
+            .position(|m| m.ptr == ptr)
+            .or_else(|| {
+                self.markers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, m)| m.index.abs_diff(index) < threshold)
+                    .min_by_key(|(_, m)| m.index.abs_diff(index))
+                    .map(|(i, _)| i)
+            })
+            .or_else(|| {
+                if self.markers.len() < MAX_SEARCH_MARKERS {
+                    None
+                } else {
+                    self.markers
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, m)| m.timestamp)
+                        .map(|(i, _)| i)
+                }
+            });
+        match slot {
+            Some(i) => self.markers[i] = marker,
+            None => self.markers.push(marker),
+        }
+    }
+
+    /// Updates markers after `len` elements have been inserted at a given `index`.
+    pub fn inserted(&mut self, index: u32, len: u32) {
+        self.markers.retain(|m| is_markable(m.ptr));
+        for m in self.markers.iter_mut() {
+            // marked block is always visible, so an insertion at its index happens before it
+            if m.index >= index {
+                m.index += len;
+            }
+        }
+    }
+
+    /// Updates markers after `len` elements have been removed starting from a given `index`.
+    pub fn removed(&mut self, index: u32, len: u32) {
+        let end = index + len;
+        self.markers
+            .retain(|m| is_markable(m.ptr) && (m.index < index || m.index >= end));
+        for m in self.markers.iter_mut() {
+            if m.index >= end {
+                m.index -= len;
+            }
+        }
+    }
+
+    /// Drops markers pointing to a given block. Used when block is about to be squashed with its
+    /// left neighbor.
+    pub fn remove(&mut self, ptr: BlockPtr) {
+        self.markers.retain(|m| m.ptr != ptr);
+    }
+
+    /// Drops all markers placed at or after a given `index`.
+    pub fn truncate(&mut self, index: u32) {
+        self.markers.retain(|m| m.index < index);
+    }
+
+    /// Returns an index at which a block under given `ptr` is placed within a branch sequence.
+    /// Index is established by walking to the left until either a marked block or the beginning
+    /// of a sequence is reached. Returns `None` if blocks on the way are part of a moved range,
+    /// as these are visible at a different position.
+    fn index_of(&self, ptr: BlockPtr, kind: OffsetKind) -> Option<u32> {
+        let marked = |ptr: BlockPtr| self.markers.iter().find(|m| m.ptr == ptr);
+        if let Some(m) = marked(ptr) {
+            return Some(m.index);
+        }
+        let mut index = 0;
+        let mut current = ptr.as_item()?.left;
+        while let Some(Block::Item(item)) = current.as_deref() {
+            if !item.is_deleted() && item.is_countable() && item.moved.is_none() {
+                let len = item.content_len(kind);
+                if let Some(m) = marked(current.unwrap()) {
+                    return Some(m.index + len + index);
+                }
+                index += len;
+            } else if !item.is_deleted() && matches!(item.content, ItemContent::Move(_)) {
+                return None;
+            }
+            current = item.left;
+        }
+        Some(index)
+    }
+}
+
+/// Checks if block under given `ptr` can be a target of a [SearchMarker].
+pub(crate) fn is_markable(ptr: BlockPtr) -> bool {
+    if let Block::Item(item) = ptr.deref() {
+        !item.is_deleted() && item.is_countable() && item.moved.is_none()
+    } else {
+        false
+    }
+}
+
+/// Executes a local indexed update `f` of a `branch` sequence, which starts at a given `index`.
+///
+/// Search markers are detached from a branch for the time of an update - so that they won't be
+/// dropped by block integration or deletion - then shifted by the difference in branch length and
+/// reattached back.
+pub(crate) fn update_markers<F, T>(mut branch: BranchPtr, index: u32, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let mut markers = std::mem::take(&mut branch.search_markers);
+    let before = branch.content_len;
+    let result = f();
+    let after = branch.content_len;
+    if after >= before {
+        markers.inserted(index, after - before);
+    } else {
+        markers.removed(index, before - after);
+    }
+    branch.search_markers = markers;
+    result
+}
+
+/// Repairs search markers of a `branch` after a block under given `ptr` has been either integrated
+/// into it (`deleted` is false) or is about to be deleted from it, without going through a local
+/// indexed update (eg. when applying a remote update or formatting a text).
+///
+/// Similarly to Yjs `updateMarkerChanges`, markers placed after a changed block are shifted by its
+/// length. Changes which affect formatting attributes or visibility of blocks other than the
+/// changed one (formatting blocks and moves) drop only the markers placed after the first affected
+/// index. If that index cannot be established, all markers are dropped.
+pub(crate) fn repair_markers(
+    txn: &mut Transaction,
+    mut branch: BranchPtr,
+    ptr: BlockPtr,
+    deleted: bool,
+) {
+    if branch.search_markers.markers.is_empty() {
+        return;
+    }
+    let item = if let Block::Item(item) = ptr.deref() {
+        item
+    } else {
+        return;
+    };
+    let kind = txn.store().options.offset_kind;
+    let markers = &mut branch.search_markers;
+    let affected = match &item.content {
+        ItemContent::Move(_) => moved_index(txn, markers, ptr),
+        ItemContent::Format(_, _) => markers.index_of(ptr, kind),
+        _ if item.is_deleted() || !item.is_countable() => return,
+        _ => {
+            if let Some(moved) = item.moved {
+                // block is visible within a moved range
+                moved_index(txn, markers, moved)
+            } else if let Some(index) = markers.index_of(ptr, kind) {
+                let len = item.content_len(kind);
+                if deleted {
+                    markers.removed(index, len);
+                } else {
+                    markers.inserted(index, len);
+                }
+                return;
+            } else {
+                None
+            }
+        }
+    };
+    match affected {
+        Some(index) => markers.truncate(index),
+        None => markers.clear(),
+    }
+}
+
+/// Returns the first index of a sequence, which visibility may have been affected by integration
+/// or deletion of a move stored under a given `ptr`: it's either a position of a move itself,
+/// a position of its moved range or a position of any other move overridden by it.
+fn moved_index(txn: &mut Transaction, markers: &SearchMarkers, ptr: BlockPtr) -> Option<u32> {
+    let kind = txn.store().options.offset_kind;
+    if let Block::Item(item) = ptr.deref() {
+        if let ItemContent::Move(m) = &item.content {
+            let mut index = markers.index_of(ptr, kind)?;
+            if let (Some(start), _) = m.get_moved_coords(txn) {
+                index = index.min(markers.index_of(start, kind)?);
+            }
+            if let Some(overrides) = &m.overrides {
+                for &ptr in overrides.iter() {
+                    index = index.min(markers.index_of(ptr, kind)?);
+                }
+            }
+            return Some(index);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use crate::block::{Block, ItemContent};
+    use crate::search_marker::is_markable;
+    use crate::test_utils::exchange_updates;
+    use crate::types::array::Array;
+    use crate::types::text::Text;
+    use crate::types::xml::Xml;
+    use crate::types::{Attrs, Branch, BranchPtr, TYPE_REFS_TEXT};
+    use crate::updates::decoder::Decode;
+    use crate::{Doc, Update};
+    use lib0::any::Any;
+    use rand::prelude::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+
+    /// Checks if all search markers of a given branch point to correct indexes.
+    fn assert_markers<B: AsRef<Branch>>(branch: &B, doc: &Doc) {
+        let encoding = doc.transact().store().options.offset_kind;
+        let branch = BranchPtr::from(branch.as_ref());
+        let mut indexes = HashMap::new();
+        let mut index = 0;
+        let mut attrs = Attrs::new();
+        let mut ptr = branch.start;
+        while let Some(Block::Item(item)) = ptr.as_deref() {
+            indexes.insert(ptr.unwrap(), (index, attrs.clone()));
+            if !item.is_deleted() {
+                if let ItemContent::Format(key, value) = &item.content {
+                    Text::update_current_attributes(&mut attrs, key, value);
+                } else if item.is_countable() {
+                    index += item.content_len(encoding);
+                }
+            }
+            ptr = item.right;
+        }
+        for m in branch.search_markers.markers.iter() {
+            let (index, attrs) = indexes.get(&m.ptr).unwrap();
+            assert_eq!(*index, m.index, "invalid marker {:?}", m);
+            let marked_attrs = m.attrs.as_deref().cloned().unwrap_or_default();
+            if branch.type_ref() == TYPE_REFS_TEXT {
+                assert_eq!(attrs, &marked_attrs, "invalid marker attributes {:?}", m);
+            }
+        }
+    }
+
+    #[test]
+    fn text_markers() {
+        let doc = Doc::with_client_id(1);
+        let txt = doc.transact().get_text("text");
+        let mut expected = String::new();
+        for i in 0..1000u32 {
+            let mut txn = doc.transact();
+            let index = (i * 7) % (expected.len() as u32 + 1);
+            txt.insert(&mut txn, index, "ab");
+            expected.insert_str(index as usize, "ab");
+            if i % 3 == 0 {
+                let index = (i * 13) % expected.len() as u32;
+                txt.remove_range(&mut txn, index, 1);
+                expected.remove(index as usize);
+            }
+            assert_markers(&txt, &doc);
+        }
+        assert!(!txt.inner().search_markers.markers.is_empty());
+        assert_eq!(txt.to_string(), expected);
+    }
+
+    #[test]
+    fn text_markers_keep_formatting() {
+        let doc = Doc::with_client_id(1);
+        let txt = doc.transact().get_text("text");
+        let mut txn = doc.transact();
+        txt.insert(&mut txn, 0, "hello world");
+        let bold: Attrs = HashMap::from([("bold".into(), Any::Bool(true))]);
+        txt.format(&mut txn, 0, 5, bold.clone());
+        txn.commit();
+
+        // marker placed inside of a bold range
+        txt.insert(&mut txn, 3, "x");
+        assert!(!txt.inner().search_markers.markers.is_empty());
+        txt.insert_with_attributes(&mut txn, 5, "y", bold);
+        txn.commit();
+
+        let mut formats = 0;
+        let mut ptr = txt.inner().start;
+        while let Some(Block::Item(item)) = ptr.as_deref() {
+            if !item.is_deleted() {
+                if let ItemContent::Format(_, _) = &item.content {
+                    formats += 1;
+                }
+            }
+            ptr = item.right;
+        }
+        // bold range has been extended without introducing new formatting blocks
+        assert_eq!(formats, 2);
+        assert_eq!(txt.to_string(), "helxlyo world");
+    }
+
+    #[test]
+    fn array_markers() {
+        let doc = Doc::with_client_id(1);
+        let array = doc.transact().get_array("array");
+        let mut expected: Vec<u32> = Vec::new();
+        for i in 0..500u32 {
+            let mut txn = doc.transact();
+            let index = (i * 11) % (expected.len() as u32 + 1);
+            array.insert_range(&mut txn, index, [i, i + 1]);
+            expected.insert(index as usize, i + 1);
+            expected.insert(index as usize, i);
+            if i % 4 == 0 {
+                let index = (i * 5) % expected.len() as u32;
+                array.remove(&mut txn, index);
+                expected.remove(index as usize);
+            }
+            assert_markers(&array, &doc);
+        }
+        let mut txn = doc.transact();
+        for (i, value) in expected.iter().enumerate() {
+            let actual = array.get(i as u32).unwrap().to_json();
+            assert_eq!(actual, Any::Number(*value as f64));
+        }
+        assert_markers(&array, &doc);
+        array.move_to(&mut txn, 0, 10);
+        assert!(array.as_ref().search_markers.markers.is_empty());
+    }
+
+    #[test]
+    fn xml_markers() {
+        let doc = Doc::with_client_id(1);
+        let xml = doc.transact().get_xml_element("div");
+        let mut txn = doc.transact();
+        for i in 0..100 {
+            xml.insert_elem(&mut txn, i / 2, format!("p{}", i));
+        }
+        xml.remove_range(&mut txn, 10, 5);
+        assert_markers(&xml, &doc);
+        assert!(!xml.as_ref().search_markers.markers.is_empty());
+        let tags: Vec<_> = (0..95)
+            .map(|i| match xml.get(i) {
+                Some(Xml::Element(elem)) => elem.tag().to_string(),
+                _ => panic!("expected XML element"),
+            })
+            .collect();
+        let mut expected = Vec::new();
+        let mut ptr = xml.as_ref().start;
+        while let Some(Block::Item(item)) = ptr.as_deref() {
+            if !item.is_deleted() {
+                match &item.content {
+                    ItemContent::Type(inner) => {
+                        expected.push(inner.name.as_deref().unwrap().to_string())
+                    }
+                    _ => panic!("expected XML element"),
+                }
+            }
+            ptr = item.right;
+        }
+        assert_eq!(tags, expected);
+    }
+
+    #[test]
+    fn markers_with_remote_changes() {
+        let mut rng = StdRng::seed_from_u64(0xdeadbeef);
+        let d1 = Doc::with_client_id(1);
+        let d2 = Doc::with_client_id(2);
+        let t1 = d1.transact().get_text("text");
+        let t2 = d2.transact().get_text("text");
+        for _ in 0..200 {
+            for (doc, txt) in [(&d1, &t1), (&d2, &t2)] {
+                let mut txn = doc.transact();
+                let len = txt.len();
+                if len > 0 && rng.gen_bool(0.3) {
+                    let index = rng.gen_range(0, len);
+                    txt.remove_range(&mut txn, index, 1);
+                } else if len > 0 && rng.gen_bool(0.2) {
+                    let index = rng.gen_range(0, len);
+                    let len = rng.gen_range(1, len - index + 1);
+                    let value = if rng.gen_bool(0.5) {
+                        Any::Bool(true)
+                    } else {
+                        Any::Null
+                    };
+                    let attrs = HashMap::from([("bold".into(), value)]);
+                    txt.format(&mut txn, index, len, attrs);
+                } else {
+                    let index = rng.gen_range(0, len + 1);
+                    txt.insert(&mut txn, index, "abc");
+                }
+                txn.commit();
+                assert_markers(&txt, doc);
+            }
+            if rng.gen_bool(0.5) {
+                exchange_updates(&[&d1, &d2]);
+                assert_markers(&t1, &d1);
+                assert_markers(&t2, &d2);
+            }
+        }
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(t1.to_string(), t2.to_string());
+
+        // markers are repaired instead of being dropped when applying remote updates
+        let mut txn = d1.transact();
+        t1.insert(&mut txn, t1.len() / 2, "x");
+        let update = txn.encode_update_v1();
+        txn.commit();
+        let mut txn = d2.transact();
+        t2.insert(&mut txn, t2.len(), "y");
+        let before = t2.inner().search_markers.markers.len();
+        assert!(before > 0);
+        txn.apply_update(Update::decode_v1(&update).unwrap());
+        txn.commit();
+        assert_eq!(t2.inner().search_markers.markers.len(), before);
+        assert_markers(&t2, &d2);
+    }
+
+    #[test]
+    fn markers_with_moves() {
+        let mut rng = StdRng::seed_from_u64(0xdeadbeef);
+        let d1 = Doc::with_client_id(1);
+        let d2 = Doc::with_client_id(2);
+        let a1 = d1.transact().get_array("array");
+        let a2 = d2.transact().get_array("array");
+        let mut next = 0;
+        for _ in 0..200 {
+            for (doc, array) in [(&d1, &a1), (&d2, &a2)] {
+                let mut txn = doc.transact();
+                let len = array.len();
+                if len > 1 && rng.gen_bool(0.3) {
+                    let source = rng.gen_range(0, len);
+                    let target = rng.gen_range(0, len + 1);
+                    array.move_to(&mut txn, source, target);
+                } else if len > 0 && rng.gen_bool(0.2) {
+                    array.remove(&mut txn, rng.gen_range(0, len));
+                } else {
+                    next += 1;
+                    array.insert(&mut txn, rng.gen_range(0, len + 1), next);
+                }
+                txn.commit();
+                assert_visible_markers(array);
+            }
+            if rng.gen_bool(0.5) {
+                exchange_updates(&[&d1, &d2]);
+                assert_visible_markers(&a1);
+                assert_visible_markers(&a2);
+            }
+        }
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(a1.to_json(), a2.to_json());
+        assert!(!a1.as_ref().search_markers.markers.is_empty());
+        assert_visible_markers(&a1);
+    }
+
+    /// Checks if all search markers of a given array, which point to visible blocks, are placed at
+    /// correct indexes. Unlike [assert_markers], this takes moved ranges into account.
+    fn assert_visible_markers(array: &Array) {
+        let values: Vec<_> = array.iter().map(|v| v.to_json()).collect();
+        for m in array.as_ref().search_markers.markers.iter() {
+            if !is_markable(m.ptr) {
+                continue;
+            }
+            let first = match &m.ptr.as_item().unwrap().content {
+                ItemContent::Any(values) => values[0].clone(),
+                other => panic!("unexpected content {:?}", other),
+            };
+            assert_eq!(
+                values.get(m.index as usize),
+                Some(&first),
+                "invalid marker {:?}",
+                m
+            );
+        }
+    }
+}
@@ -1,15 +1,15 @@
 use crate::block::{ClientID, ItemContent};
 use crate::block_store::{BlockStore, StateVector};
 use crate::doc::Options;
-use crate::event::{AfterTransactionEvent, EventHandler};
+use crate::event::{AfterTransactionEvent, EventHandler, RootsEvent};
 use crate::id_set::DeleteSet;
-use crate::types::{Branch, BranchPtr, Path, PathSegment, TypeRefs};
+use crate::types::{Branch, BranchPtr, DocEvents, Path, PathSegment, TypeRefs};
 use crate::update::PendingUpdate;
 use crate::updates::encoder::{Encode, Encoder};
 use crate::UpdateEvent;
 use std::cell::UnsafeCell;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
@@ -49,6 +49,20 @@ pub(crate) struct Store {
     /// A subscription handler. It contains all callbacks with registered by user functions that
     /// are supposed to be called, once a new update arrives.
     pub(crate) update_v2_events: Option<EventHandler<UpdateEvent>>,
+
+    /// Names of root types created since the last transaction commit.
+    pub(crate) added_roots: Vec<Rc<str>>,
+
+    /// Handles subscriptions for the root-level types being defined for the first time.
+    pub(crate) roots_events: Option<EventHandler<RootsEvent>>,
+
+    /// Handles subscriptions for events emitted by any of the root-level types and their nested
+    /// types.
+    pub(crate) all_deep_events: Option<EventHandler<DocEvents>>,
+
+    /// Weak links integrated into this document, grouped by collections containing the content
+    /// they reference. They are notified whenever that content changes.
+    pub(crate) weak_links: HashMap<BranchPtr, HashSet<BranchPtr>>,
 }
 
 impl Store {
@@ -63,6 +77,10 @@ impl Store {
             update_v1_events: None,
             update_v2_events: None,
             after_transaction_events: None,
+            added_roots: Vec::new(),
+            roots_events: None,
+            all_deep_events: None,
+            weak_links: HashMap::new(),
         }
     }
 
@@ -100,6 +118,7 @@ impl Store {
                 let mut branch = Branch::new(type_ref, node_name);
                 let branch_ref = BranchPtr::from(&mut branch);
                 e.insert(branch);
+                self.added_roots.push(key);
                 branch_ref
             }
         }
@@ -192,7 +211,20 @@ impl Store {
                         }
                     }
                     PathSegment::Index(index) => {
-                        if let Some((ItemContent::Type(child_branch), _)) = current.get_at(*index) {
+                        let mut index = *index;
+                        let mut ptr = current.start;
+                        let child = loop {
+                            let block = ptr?;
+                            let item = block.as_item()?;
+                            if !item.is_deleted() && item.is_countable() {
+                                if index < item.len() {
+                                    break block;
+                                }
+                                index -= item.len();
+                            }
+                            ptr = item.right;
+                        };
+                        if let ItemContent::Type(child_branch) = &child.as_item()?.content {
                             current = child_branch.into();
                         } else {
                             return None;
@@ -2,14 +2,21 @@ use crate::*;
 
 use crate::block::{Block, BlockPtr, Item, ItemContent, Prelim, ID};
 use crate::block_store::{Snapshot, StateVector};
-use crate::event::AfterTransactionEvent;
+use crate::event::{AfterTransactionEvent, RootsEvent};
 use crate::id_set::DeleteSet;
+use crate::moving::visible_parent;
+use crate::search_marker::repair_markers;
 use crate::store::{Store, StoreRef};
 use crate::types::array::Array;
-use crate::types::xml::{XmlElement, XmlText};
+use crate::types::counter::Counter;
+use crate::types::tree::Tree;
+use crate::types::weak::WeakLink;
+use crate::types::xml::{XmlElement, XmlFragment, XmlText};
 use crate::types::{
-    BranchPtr, Event, Events, Map, Text, TypePtr, TYPE_REFS_ARRAY, TYPE_REFS_MAP, TYPE_REFS_TEXT,
-    TYPE_REFS_XML_ELEMENT, TYPE_REFS_XML_TEXT,
+    BranchPtr, DocEvents, Event, Events, Map, Path, PathError, PathSegment, Text, TypePtr, Value,
+    TYPE_REFS_ARRAY, TYPE_REFS_COUNTER, TYPE_REFS_MAP, TYPE_REFS_TEXT, TYPE_REFS_TREE,
+    TYPE_REFS_UNDEFINED, TYPE_REFS_WEAK, TYPE_REFS_XML_ELEMENT, TYPE_REFS_XML_FRAGMENT,
+    TYPE_REFS_XML_TEXT,
 };
 use crate::update::Update;
 use std::collections::{HashMap, HashSet};
@@ -35,13 +42,17 @@ pub struct Transaction {
     pub(crate) prev_moved: HashMap<BlockPtr, BlockPtr>,
     /// All types that were directly modified (property added or child inserted/deleted).
     /// New types are not included in this Set.
-    changed: HashMap<TypePtr, HashSet<Option<Rc<str>>>>,
+    pub(crate) changed: HashMap<TypePtr, HashSet<Option<Rc<str>>>>,
+    /// Number of root types added by other transactions, which have not been committed yet at the
+    /// moment of creating this one. Only the roots added after that are reported by this transaction.
+    added_roots_offset: usize,
     committed: bool,
 }
 
 impl Transaction {
     pub(crate) fn new(store: StoreRef) -> Transaction {
         let begin_timestamp = store.blocks.get_state_vector();
+        let added_roots_offset = store.added_roots.len();
         Transaction {
             store,
             before_state: begin_timestamp,
@@ -50,6 +61,7 @@ impl Transaction {
             after_state: StateVector::default(),
             changed: HashMap::new(),
             prev_moved: HashMap::default(),
+            added_roots_offset,
             committed: false,
         }
     }
@@ -166,6 +178,24 @@ impl Transaction {
         XmlElement::from(c)
     }
 
+    /// Returns a [XmlFragment] data structure stored under a given `name`. XML fragments represent
+    /// a list of XML nodes without a tag name or attributes of their own. They are used as root
+    /// containers of rich text documents by editor bindings such as y-prosemirror.
+    ///
+    /// If not structure under defined `name` existed before, it will be created and returned
+    /// instead.
+    ///
+    /// If a structure under defined `name` already existed, but its type was different it will be
+    /// reinterpreted as a XML fragment (in such case a sequence component of complex data type
+    /// will be interpreted as a list of its child XML nodes).
+    pub fn get_xml_fragment(&mut self, name: &str) -> XmlFragment {
+        let mut c = self
+            .store_mut()
+            .get_or_create_type(name, None, TYPE_REFS_XML_FRAGMENT);
+        c.store = Some(self.store.clone());
+        XmlFragment::from(c)
+    }
+
     /// Returns a [XmlText] data structure stored under a given `name`. Text structures are used for
     /// collaborative text editing: they expose operations to append and remove chunks of text,
     /// which are free to execute concurrently by multiple peers over remote boundaries.
@@ -184,6 +214,169 @@ impl Transaction {
         XmlText::from(c)
     }
 
+    /// Returns a [Counter] stored under a given `name`. Counters represent a single integer
+    /// number, which can be concurrently incremented and decremented by multiple peers.
+    ///
+    /// If not structure under defined `name` existed before, it will be created and returned
+    /// instead.
+    ///
+    /// If a structure under defined `name` already existed, but its type was different it will be
+    /// reinterpreted as a counter (in such case a map component of complex data type will be
+    /// interpreted as per-client contributions to a counter value).
+    pub fn get_counter(&mut self, name: &str) -> Counter {
+        let mut c = self
+            .store_mut()
+            .get_or_create_type(name, None, TYPE_REFS_COUNTER);
+        c.store = Some(self.store.clone());
+        Counter::from(c)
+    }
+
+    /// Returns a [Tree] stored under a given `name`. Trees are used to store hierarchies of nodes,
+    /// which can be concurrently created, moved and deleted by multiple peers.
+    ///
+    /// If not structure under defined `name` existed before, it will be created and returned
+    /// instead.
+    ///
+    /// If a structure under defined `name` already existed, but its type was different it will be
+    /// reinterpreted as a tree (in such case a sequence component of complex data type will be
+    /// interpreted as a list of tree nodes).
+    pub fn get_tree(&mut self, name: &str) -> Tree {
+        let mut c = self
+            .store_mut()
+            .get_or_create_type(name, None, TYPE_REFS_TREE);
+        c.store = Some(self.store.clone());
+        Tree::from(c)
+    }
+
+    /// Returns a value found under a given `path`. First segment of a path must be a key with a
+    /// name of a root-level type, while the following ones are used to access nested [Map]
+    /// entries and [Array] or [XmlElement] children. See [parse_path](crate::types::parse_path)
+    /// for a string representation of paths.
+    ///
+    /// Returns `None` if there was no value under a given path.
+    pub fn get_by_path(&self, path: &Path) -> Option<Value> {
+        self.resolve_path(path.iter()).ok()
+    }
+
+    /// Sets a `value` under a given `path`. Last segment of a path is used to determine where
+    /// the value should be stored:
+    ///
+    /// - Keys and indexes of a [Map] are used as entry keys. If an entry already existed, it will
+    ///   be overridden.
+    /// - Indexes of an [Array] are used to replace existing array element. Using an index equal
+    ///   to the length of an array will append the value at its end.
+    /// - Keys of an [XmlElement] or [XmlHook](crate::XmlHook) are used as attribute names and
+    ///   entry keys.
+    /// - Indexes of an [XmlElement] or [XmlFragment] are used to replace or append child nodes
+    ///   the same way as for an [Array]. Use [PrelimXml](crate::PrelimXml) to create new nodes.
+    ///
+    /// All segments except the last one must point to an existing value.
+    pub fn set_by_path<V: Prelim>(&mut self, path: &Path, value: V) -> Result<(), PathError> {
+        let (parent, segment) = self.resolve_parent(path)?;
+        match (parent, segment) {
+            (Value::YMap(map), PathSegment::Key(key)) => {
+                map.insert(self, key.clone(), value);
+            }
+            (Value::YMap(map), PathSegment::Index(index)) => {
+                map.insert(self, index.to_string(), value);
+            }
+            (Value::YArray(array), PathSegment::Index(index)) => {
+                let len = array.len();
+                if *index < len {
+                    array.remove(self, *index);
+                    array.insert(self, *index, value);
+                } else if *index == len {
+                    array.push_back(self, value);
+                } else {
+                    return Err(PathError::NotFound(segment.clone()));
+                }
+            }
+            (Value::YXmlElement(elem), PathSegment::Key(key)) => {
+                elem.insert_attribute_value(self, key.clone(), value);
+            }
+            (Value::YXmlElement(elem), PathSegment::Index(index)) => {
+                if !elem.set_child(self, *index, value) {
+                    return Err(PathError::NotFound(segment.clone()));
+                }
+            }
+            (Value::YXmlFragment(xml), PathSegment::Index(index)) => {
+                if !xml.set_child(self, *index, value) {
+                    return Err(PathError::NotFound(segment.clone()));
+                }
+            }
+            (Value::YXmlHook(hook), PathSegment::Key(key)) => {
+                hook.insert(self, key.to_string(), value);
+            }
+            _ => return Err(PathError::InvalidSegment(segment.clone())),
+        }
+        Ok(())
+    }
+
+    /// Removes a value stored under a given `path`, returning it. Last segment of a path can be
+    /// a [Map] key, an [Array] or [XmlElement] child index or a name of XML attribute.
+    ///
+    /// Returns `None` if there was no value under a given path.
+    pub fn remove_by_path(&mut self, path: &Path) -> Option<Value> {
+        let (parent, segment) = self.resolve_parent(path).ok()?;
+        let removed = parent.get_child(segment)?;
+        match (parent, segment) {
+            (Value::YMap(map), PathSegment::Key(key)) => {
+                map.remove(self, key);
+            }
+            (Value::YMap(map), PathSegment::Index(index)) => {
+                map.remove(self, &index.to_string());
+            }
+            (Value::YArray(array), PathSegment::Index(index)) => array.remove(self, *index),
+            (Value::YXmlElement(elem), PathSegment::Index(index)) => {
+                elem.remove_range(self, *index, 1)
+            }
+            (Value::YXmlElement(elem), PathSegment::Key(key)) => elem.remove_attribute(self, key),
+            (Value::YXmlFragment(xml), PathSegment::Index(index)) => xml.remove(self, *index, 1),
+            (Value::YXmlText(text), PathSegment::Key(key)) => text.remove_attribute(self, key),
+            (Value::YXmlHook(hook), PathSegment::Key(key)) => {
+                hook.remove(self, key);
+            }
+            _ => return None,
+        }
+        Some(removed)
+    }
+
+    fn resolve_path<'a, I>(&self, mut segments: I) -> Result<Value, PathError>
+    where
+        I: Iterator<Item = &'a PathSegment>,
+    {
+        let mut current: Value = match segments.next() {
+            Some(PathSegment::Key(name)) => {
+                let mut root = self
+                    .store()
+                    .get_type(name.clone())
+                    .ok_or_else(|| PathError::NotFound(PathSegment::Key(name.clone())))?;
+                if root.type_ref() == TYPE_REFS_UNDEFINED {
+                    // root type was never accessed locally, so we cannot tell its type
+                    return Err(PathError::NotFound(PathSegment::Key(name.clone())));
+                }
+                root.store = Some(self.store.clone());
+                root.into()
+            }
+            _ => return Err(PathError::InvalidRoot),
+        };
+        for segment in segments {
+            current = current
+                .get_child(segment)
+                .ok_or_else(|| PathError::NotFound(segment.clone()))?;
+        }
+        Ok(current)
+    }
+
+    fn resolve_parent<'a>(&self, path: &'a Path) -> Result<(Value, &'a PathSegment), PathError> {
+        if path.len() < 2 {
+            return Err(PathError::InvalidRoot);
+        }
+        let last = path.len() - 1;
+        let parent = self.resolve_path(path.iter().take(last))?;
+        Ok((parent, &path[last]))
+    }
+
     /// Encodes changes made within the scope of the current transaction using lib0 v1 encoding.
     ///
     /// Document updates are idempotent and commutative. Caveats:
@@ -333,10 +526,22 @@ impl Transaction {
         let store = self.store.deref();
         if let Block::Item(item) = ptr.deref_mut() {
             if !item.is_deleted() {
-                if item.parent_sub.is_none() && item.is_countable() {
-                    if let TypePtr::Branch(mut parent) = item.parent {
-                        parent.block_len -= item.len();
-                        parent.content_len -= item.content_len(store.options.offset_kind);
+                if item.parent_sub.is_none() {
+                    if let TypePtr::Branch(parent) = item.parent {
+                        if item.is_countable() {
+                            // moved items are counted in a sequence they have been moved into
+                            if let Some(mut visible) = visible_parent(block) {
+                                visible.block_len -= item.len();
+                                visible.content_len -= item.content_len(store.options.offset_kind);
+                                visible.line_index.clear();
+                                visible.grapheme_index.clear();
+                                if visible != parent {
+                                    visible.search_markers.clear();
+                                    self.add_changed_type(visible, None);
+                                }
+                            }
+                        }
+                        repair_markers(self, parent, block, true);
                     }
                 }
 
@@ -355,6 +560,18 @@ impl Transaction {
                         todo!()
                     }
                     ItemContent::Type(inner) => {
+                        if inner.type_ref() == TYPE_REFS_WEAK {
+                            let link = BranchPtr::from(inner);
+                            if let Some(quoted) = WeakLink::from(link).quoted_branch() {
+                                let links = &mut self.store_mut().weak_links;
+                                if let Some(quoting) = links.get_mut(&quoted) {
+                                    quoting.remove(&link);
+                                    if quoting.is_empty() {
+                                        links.remove(&quoted);
+                                    }
+                                }
+                            }
+                        }
                         let mut ptr = inner.start;
                         self.changed
                             .remove(&TypePtr::Branch(BranchPtr::from(inner)));
@@ -513,10 +730,36 @@ impl Transaction {
         self.delete_set.squash();
         self.after_state = self.store.blocks.get_state_vector();
         // 2. emit 'beforeObserverCalls'
+        if self.store.added_roots.len() > self.added_roots_offset {
+            let offset = self.added_roots_offset;
+            let added = self.store_mut().added_roots.split_off(offset);
+            if let Some(eh) = self.store.roots_events.as_ref() {
+                eh.publish(self, &RootsEvent { added });
+            }
+        }
+        // weak links are notified about changes of the content they reference
+        if !self.changed.is_empty() && !self.store.weak_links.is_empty() {
+            let mut changed_links = Vec::new();
+            for ptr in self.changed.keys() {
+                if let TypePtr::Branch(branch) = ptr {
+                    if let Some(links) = self.store.weak_links.get(branch) {
+                        changed_links.extend(
+                            links
+                                .iter()
+                                .filter(|&&link| WeakLink::from(link).is_changed(self)),
+                        );
+                    }
+                }
+            }
+            for link in changed_links {
+                self.changed.entry(TypePtr::Branch(link)).or_default();
+            }
+        }
         // 3. for each change observed by the transaction call 'afterTransaction'
         if !self.changed.is_empty() {
             let mut changed_parents: HashMap<BranchPtr, Vec<usize>> = HashMap::new();
             let mut event_cache = Vec::new();
+            let mut event_roots = Vec::new();
 
             for (ptr, subs) in self.changed.iter() {
                 if let TypePtr::Branch(branch) = ptr {
@@ -539,6 +782,7 @@ impl Transaction {
 
                             break;
                         }
+                        event_roots.push(current);
                     }
                 }
             }
@@ -562,6 +806,21 @@ impl Transaction {
                 let events = Events::new(&mut unsorted);
                 branch.trigger_deep(self, &events);
             }
+
+            // document-level deep observe events
+            if let Some(eh) = self.store.all_deep_events.as_ref() {
+                let mut events = Vec::with_capacity(event_cache.len());
+                for (e, &root) in event_cache.iter_mut().zip(event_roots.iter()) {
+                    // types which parents have been garbage collected cannot be tracked back
+                    if let Some(name) = self.store.get_type_key(root) {
+                        e.set_current_target(root);
+                        events.push((name.clone(), &*e));
+                    }
+                }
+                if !events.is_empty() {
+                    eh.publish(self, &DocEvents::new(events));
+                }
+            }
         }
 
         // 4. try GC delete set
@@ -653,7 +912,9 @@ impl Transaction {
         }
     }
 
-    pub(crate) fn add_changed_type(&mut self, parent: BranchPtr, parent_sub: Option<Rc<str>>) {
+    pub(crate) fn add_changed_type(&mut self, mut parent: BranchPtr, parent_sub: Option<Rc<str>>) {
+        // changes of tree nodes or their parents invalidate a tree layout
+        parent.tree_layout = None;
         let trigger = if let Some(ptr) = parent.item {
             (ptr.id().clock < self.before_state.get(&ptr.id().client)) && !ptr.is_deleted()
         } else {
@@ -752,3 +1013,135 @@ impl Drop for Transaction {
         self.commit()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::types::{format_path, parse_path, PathError, PathSegment, Value};
+    use crate::{Doc, PrelimArray, PrelimMap, PrelimXml};
+    use lib0::any::Any;
+    use std::collections::HashMap;
+
+    #[test]
+    fn path_parsing() {
+        let path = parse_path("/todos/3/title~1subtitle~0");
+        assert_eq!(
+            path,
+            vec![
+                PathSegment::Key("todos".into()),
+                PathSegment::Index(3),
+                PathSegment::Key("title/subtitle~".into()),
+            ]
+        );
+        assert_eq!(format_path(&path), "todos/3/title~1subtitle~0");
+        assert!(parse_path("").is_empty());
+    }
+
+    #[test]
+    fn get_by_path() {
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let root = txn.get_map("root");
+        let mut todo = HashMap::new();
+        todo.insert("title".to_string(), Any::String("buy milk".into()));
+        let todos = PrelimArray::from(vec![PrelimMap::from(todo)]);
+        root.insert(&mut txn, "todos", todos);
+        root.insert(&mut txn, "1", "numeric key");
+
+        let xml = txn.get_xml_element("xml");
+        let child = xml.push_elem_back(&mut txn, "p");
+        child.insert_attribute(&mut txn, "class", "header");
+
+        assert_eq!(
+            txn.get_by_path(&parse_path("root/todos/0/title")),
+            Some(Value::from("buy milk"))
+        );
+        assert_eq!(
+            txn.get_by_path(&parse_path("root/1")),
+            Some(Value::from("numeric key"))
+        );
+        assert_eq!(
+            txn.get_by_path(&parse_path("xml/0/class")),
+            Some(Value::from("header"))
+        );
+        assert_eq!(txn.get_by_path(&parse_path("root/todos/1/title")), None);
+        assert_eq!(txn.get_by_path(&parse_path("missing")), None);
+        assert!(txn.get_by_path(&parse_path("root")).is_some());
+    }
+
+    #[test]
+    fn set_and_remove_by_path() {
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let root = txn.get_map("root");
+        root.insert(&mut txn, "todos", PrelimArray::from(vec!["a", "b"]));
+
+        txn.set_by_path(&parse_path("root/todos/1"), "c").unwrap();
+        txn.set_by_path(&parse_path("root/todos/2"), "d").unwrap();
+        txn.set_by_path(&parse_path("root/title"), "list").unwrap();
+        assert_eq!(
+            txn.set_by_path(&parse_path("root/todos/5"), "e"),
+            Err(PathError::NotFound(PathSegment::Index(5)))
+        );
+        assert_eq!(
+            txn.set_by_path(&parse_path("root/title/0"), "e"),
+            Err(PathError::InvalidSegment(PathSegment::Index(0)))
+        );
+        assert_eq!(
+            txn.set_by_path(&parse_path("root"), "e"),
+            Err(PathError::InvalidRoot)
+        );
+
+        let todos = root.get("todos").unwrap().to_yarray().unwrap();
+        assert_eq!(
+            todos.to_json(),
+            Any::Array(vec!["a".into(), "c".into(), "d".into()].into_boxed_slice())
+        );
+        assert_eq!(root.get("title"), Some(Value::from("list")));
+
+        assert_eq!(
+            txn.remove_by_path(&parse_path("root/todos/0")),
+            Some(Value::from("a"))
+        );
+        assert_eq!(
+            txn.remove_by_path(&parse_path("root/title")),
+            Some(Value::from("list"))
+        );
+        assert_eq!(txn.remove_by_path(&parse_path("root/title")), None);
+        assert_eq!(todos.len(), 2);
+    }
+
+    #[test]
+    fn set_by_path_in_xml() {
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let xml = txn.get_xml_element("xml");
+        xml.push_elem_back(&mut txn, "p");
+
+        txn.set_by_path(&parse_path("xml/0/class"), "header")
+            .unwrap();
+        txn.set_by_path(&parse_path("xml/1"), PrelimXml::Elem("img".into()))
+            .unwrap();
+        txn.set_by_path(&parse_path("xml/1/src"), "a.png").unwrap();
+        assert_eq!(
+            xml.to_string(),
+            "<UNDEFINED><p \"class\"=\"header\"></p><img \"src\"=\"a.png\"></img></UNDEFINED>"
+        );
+
+        // replace existing child node
+        txn.set_by_path(&parse_path("xml/0"), PrelimXml::Elem("span".into()))
+            .unwrap();
+        assert_eq!(
+            xml.to_string(),
+            "<UNDEFINED><span></span><img \"src\"=\"a.png\"></img></UNDEFINED>"
+        );
+        assert_eq!(
+            txn.set_by_path(&parse_path("xml/3"), PrelimXml::Text),
+            Err(PathError::NotFound(PathSegment::Index(3)))
+        );
+
+        let fragment = txn.get_xml_fragment("fragment");
+        txn.set_by_path(&parse_path("fragment/0"), PrelimXml::Elem("div".into()))
+            .unwrap();
+        assert_eq!(fragment.to_string(), "<div></div>");
+    }
+}
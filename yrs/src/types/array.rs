@@ -1,16 +1,18 @@
 use crate::block::{ItemContent, Prelim};
 use crate::block_iter::{BlockIter, SliceConcat};
 use crate::event::Subscription;
-use crate::moving::RelativePosition;
+use crate::moving::{move_range, RelativePosition};
+use crate::types::weak::{LinkSource, WeakPrelim};
 use crate::types::{
-    event_change_set, Branch, BranchPtr, Change, ChangeSet, Observers, Path, Value, TYPE_REFS_ARRAY,
+    event_change_set, Branch, BranchPtr, Change, ChangeSet, MoveChange, Observers, Path,
+    PrelimJson, Value, TYPE_REFS_ARRAY,
 };
 use crate::{SubscriptionId, Transaction, ID};
 use lib0::any::Any;
 use std::cell::UnsafeCell;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::marker::PhantomData;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Bound, Deref, DerefMut, RangeBounds};
 
 /// A collection used to store data in an indexed sequence structure. This type is internally
 /// implemented as a double linked list, which may squash values inserted directly one after another
@@ -54,6 +56,17 @@ impl Array {
         }
     }
 
+    /// Inserts a JSON-like `value` at the given `index`. Unlike [Array::insert], JSON objects and
+    /// arrays are recursively converted into nested [Map]s and [Array]s, so that their elements
+    /// can be concurrently updated by different peers.
+    ///
+    /// Use [PrelimJson::strings_as_text] in order to also turn JSON strings into [Text].
+    ///
+    /// Using `index` value that's higher than current array length results in panic.
+    pub fn insert_json<V: Into<PrelimJson>>(&self, txn: &mut Transaction, index: u32, value: V) {
+        self.insert(txn, index, value.into())
+    }
+
     /// Inserts multiple `values` at the given `index`. Inserting at index `0` is equivalent to
     /// prepending current array with given `values`, while inserting at array length is equivalent
     /// to appending that value at the end of it.
@@ -96,6 +109,67 @@ impl Array {
         }
     }
 
+    /// Removes `remove_len` elements starting at given `index` and inserts provided `values` in
+    /// their place. Both operations are performed using a single traversal of an array, while all
+    /// `values` are inserted as a single block.
+    ///
+    /// This method panics in case when `index` is outside of the bounds of an array or when there
+    /// are not enough elements to remove.
+    pub fn splice<T, V>(&self, txn: &mut Transaction, index: u32, remove_len: u32, values: T)
+    where
+        T: IntoIterator<Item = V>,
+        V: Into<Any>,
+    {
+        let mut walker = BlockIter::new(self.0);
+        if !walker.try_forward(txn, index) {
+            panic!("Index {} is outside of the range of an array", index);
+        }
+        if remove_len > 0 {
+            walker.delete(txn, remove_len);
+        }
+        let values: Vec<Any> = values.into_iter().map(Into::into).collect();
+        if !values.is_empty() {
+            walker.insert_contents(txn, PrelimRange(values));
+        }
+    }
+
+    /// Retains only the elements of a current array, for which a given `predicate` returns true.
+    /// Array is traversed only once and consecutive elements are removed together as a single
+    /// range.
+    pub fn retain<F>(&self, txn: &mut Transaction, mut predicate: F)
+    where
+        F: FnMut(&Value) -> bool,
+    {
+        let mut walker = BlockIter::new(self.0);
+        loop {
+            let mut probe = walker.clone();
+            let mut removed = 0;
+            let mut kept = false;
+            while !probe.finished() {
+                match probe.read_value(txn) {
+                    Some(value) if predicate(&value) => {
+                        kept = true;
+                        break;
+                    }
+                    Some(_) => removed += 1,
+                    None => break,
+                }
+            }
+            if removed == 0 {
+                walker = probe;
+            } else {
+                walker.delete(txn, removed);
+                if kept {
+                    // skip over the element that has been kept
+                    walker.forward(txn, 1);
+                }
+            }
+            if !kept {
+                break;
+            }
+        }
+    }
+
     /// Retrieves a value stored at a given `index`. Returns `None` when provided index was out
     /// of the range of a current array.
     pub fn get(&self, index: u32) -> Option<Value> {
@@ -108,6 +182,17 @@ impl Array {
         }
     }
 
+    /// Returns a preliminary [WeakLink] quoting a range of `len` elements starting at a given
+    /// `index`. Once inserted into another collection, a link resolves to the current state of
+    /// the quoted elements, even if they have been moved to a different position.
+    ///
+    /// Using a range which is empty or exceeds current array length results in panic.
+    ///
+    /// [WeakLink]: crate::types::weak::WeakLink
+    pub fn quote(&self, txn: &mut Transaction, index: u32, len: u32) -> WeakPrelim {
+        WeakPrelim::new(LinkSource::quote(txn, self.0, index, len))
+    }
+
     /// Moves element found at `source` index into `target` index position.
     pub fn move_to(&self, txn: &mut Transaction, source: u32, target: u32) {
         if source == target || source + 1 == target {
@@ -156,36 +241,118 @@ impl Array {
             // It doesn't make sense to move a range into the same range (it's basically a no-op).
             return;
         }
-        let left = RelativePosition::from_type_index(txn, self.0, start, assoc_start)
-            .expect("unbounded relative positions are not supported yet");
-        let right = RelativePosition::from_type_index(txn, self.0, end + 1, assoc_end)
-            .expect("unbounded relative positions are not supported yet");
-        let mut walker = BlockIter::new(self.0);
-        if walker.try_forward(txn, target) {
-            walker.insert_move(txn, left, right);
+        let assoc = (assoc_start, assoc_end);
+        move_range(txn, self.0, start..=end, assoc, self.0, target);
+    }
+
+    /// Moves element found at `source` index of a current array into another `target` array at
+    /// given `index` position. Moved element keeps its identity, so that concurrent updates made
+    /// to it (eg. when it's a nested shared collection) are preserved. See
+    /// [Array::move_range_into] for compatibility notes.
+    ///
+    /// Example:
+    /// ```
+    /// use yrs::Doc;
+    /// let doc = Doc::new();
+    /// let mut txn = doc.transact();
+    /// let a = txn.get_array("a");
+    /// let b = txn.get_array("b");
+    /// a.insert_range(&mut txn, 0, [1, 2, 3]);
+    /// b.insert_range(&mut txn, 0, [4, 5]);
+    /// // move element 2 between 4 and 5
+    /// a.move_into(&mut txn, 1, &b, 1);
+    /// assert_eq!(a.len(), 2);
+    /// assert_eq!(b.len(), 3);
+    /// ```
+    pub fn move_into(&self, txn: &mut Transaction, source: u32, target: &Array, index: u32) {
+        self.move_range_into(txn, source, source, target, index)
+    }
+
+    /// Moves all elements found within `start`..`end` indexes range (both side inclusive) of
+    /// a current array into another `target` array at given `index` position. All elements
+    /// inserted concurrently by other peers inside of moved range will be moved as well after
+    /// synchronization. Elements inserted concurrently right before `start` or right after `end`
+    /// are not part of the moved range.
+    ///
+    /// Moves between different sequences are an extension of the Yjs move protocol: updates
+    /// produced by this method can only be interpreted by other Yrs peers, while Yjs clients
+    /// will keep the moved elements in their original sequence.
+    pub fn move_range_into(
+        &self,
+        txn: &mut Transaction,
+        start: u32,
+        end: u32,
+        target: &Array,
+        index: u32,
+    ) {
+        if self.0 == target.0 {
+            self.move_range_to(txn, start, true, end, false, index)
         } else {
-            panic!("Index {} is outside of the range of an array", target);
+            move_range(txn, self.0, start..=end, (true, false), target.0, index);
         }
     }
 
     /// Returns an iterator, that can be used to lazely traverse over all values stored in a current
-    /// array.
+    /// array. Returned iterator can be also traversed from the back.
     pub fn iter(&self) -> ArrayIter {
-        ArrayIter::new(self)
+        ArrayIter::new(self, 0, self.len())
+    }
+
+    /// Returns an iterator, that can be used to lazely traverse over values stored within a given
+    /// `range` of indexes of a current array. Returned iterator can be also traversed from the
+    /// back.
+    ///
+    /// This method panics if `range` is outside of the bounds of an array.
+    pub fn range<R: RangeBounds<u32>>(&self, range: R) -> ArrayIter<'_> {
+        let (start, end) = self.bounds(range);
+        ArrayIter::new(self, start, end)
     }
 
     /// Converts all contents of current array into a JSON-like representation.
     pub fn to_json(&self) -> Any {
-        let len = self.0.len();
+        self.slice_to_json(..)
+    }
+
+    /// Converts contents of current array found within a given `range` of indexes into a JSON-like
+    /// representation.
+    ///
+    /// This method panics if `range` is outside of the bounds of an array.
+    pub fn slice_to_json<R: RangeBounds<u32>>(&self, range: R) -> Any {
+        let (start, end) = self.bounds(range);
         let mut walker = BlockIter::new(self.0);
         let mut txn = self.0.try_transact().unwrap();
+        if !walker.try_forward(&mut txn, start) {
+            panic!("Index {} is outside of the range of an array", start);
+        }
         let values = walker
-            .slice::<ArraySliceConcat>(&mut txn, len, Vec::default())
+            .slice::<ArraySliceConcat>(&mut txn, end - start, Vec::default())
             .unwrap();
         let res = values.into_iter().map(Value::to_json).collect();
         Any::Array(res)
     }
 
+    fn bounds<R: RangeBounds<u32>>(&self, range: R) -> (u32, u32) {
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i + 1,
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => self.len(),
+        };
+        if start > end || end > self.len() {
+            panic!(
+                "Range {}..{} is outside of the range of an array of length {}",
+                start,
+                end,
+                self.len()
+            );
+        }
+        (start, end)
+    }
+
     /// Subscribes a given callback to be triggered whenever current array is changed.
     /// A callback is triggered whenever a transaction gets committed. This function does not
     /// trigger if changes have been observed by nested shared collections.
@@ -226,15 +393,25 @@ impl AsMut<Branch> for Array {
 
 pub struct ArrayIter<'a> {
     inner: BlockIter,
+    /// Remaining values read ahead, once iterator has been traversed from the back.
+    back: Option<VecDeque<Value>>,
+    end: u32,
     txn: Transaction,
     _marker: PhantomData<&'a Array>,
 }
 
 impl<'a> ArrayIter<'a> {
-    fn new(array: &'a Array) -> Self {
+    fn new(array: &'a Array, start: u32, end: u32) -> Self {
+        let mut txn = array.0.try_transact().unwrap();
+        let mut inner = BlockIter::new(array.0);
+        if !inner.try_forward(&mut txn, start) {
+            panic!("Index {} is outside of the range of an array", start);
+        }
         ArrayIter {
-            inner: BlockIter::new(array.0),
-            txn: array.0.try_transact().unwrap(),
+            inner,
+            back: None,
+            end,
+            txn,
             _marker: PhantomData,
         }
     }
@@ -244,7 +421,9 @@ impl<'b> Iterator for ArrayIter<'b> {
     type Item = Value;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.inner.finished() {
+        if let Some(back) = self.back.as_mut() {
+            back.pop_front()
+        } else if self.inner.finished() || self.inner.index() >= self.end {
             None
         } else {
             let mut res = self
@@ -253,8 +432,37 @@ impl<'b> Iterator for ArrayIter<'b> {
             res.pop()
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = match self.back.as_ref() {
+            Some(back) => back.len(),
+            None => self.end.saturating_sub(self.inner.index()) as usize,
+        };
+        (len, Some(len))
+    }
 }
 
+impl<'b> DoubleEndedIterator for ArrayIter<'b> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let back = match self.back.as_mut() {
+            Some(back) => back,
+            None => {
+                // moved ranges can only be resolved while walking forward, so all remaining
+                // values are read ahead on the first use
+                let len = self.end.saturating_sub(self.inner.index());
+                let values = self
+                    .inner
+                    .slice::<ArraySliceConcat>(&mut self.txn, len, Vec::default())
+                    .unwrap_or_default();
+                self.back.get_or_insert(values.into())
+            }
+        };
+        back.pop_back()
+    }
+}
+
+impl<'b> ExactSizeIterator for ArrayIter<'b> {}
+
 impl From<BranchPtr> for Array {
     fn from(inner: BranchPtr) -> Self {
         Array(inner)
@@ -347,6 +555,12 @@ impl ArrayEvent {
         self.changes(txn).delta.as_slice()
     }
 
+    /// Returns ranges of elements, which have been moved within a bounds of current transaction.
+    /// Each move is also a part of a [Self::delta] as a removal and insertion of the same elements.
+    pub fn moves(&self, txn: &Transaction) -> &[MoveChange] {
+        self.changes(txn).moves.as_slice()
+    }
+
     /// Returns a collection of block identifiers that have been added within a bounds of
     /// current transaction.
     pub fn inserts(&self, txn: &Transaction) -> &HashSet<ID> {
@@ -376,7 +590,7 @@ impl SliceConcat for ArraySliceConcat {
             if offset != 0 {
                 for _ in content.drain(0..offset) { /* do nothing */ }
             }
-            for _ in content.drain(len..) { /* do nothing */ }
+            content.truncate(len);
             content
         }
     }
@@ -390,9 +604,10 @@ impl SliceConcat for ArraySliceConcat {
 
 #[cfg(test)]
 mod test {
+    use crate::block::{Block, ItemContent};
     use crate::test_utils::{exchange_updates, run_scenario, RngExt};
     use crate::types::map::PrelimMap;
-    use crate::types::{Change, DeepObservable, Event, Path, PathSegment, Value};
+    use crate::types::{Change, DeepObservable, Event, MoveChange, Path, PathSegment, Value};
     use crate::{Doc, PrelimArray, StateVector, Update, ID};
     use lib0::any::Any;
     use rand::prelude::StdRng;
@@ -1226,6 +1441,56 @@ mod test {
         }
     }
 
+    #[test]
+    fn move_events() {
+        let d1 = Doc::with_client_id(1);
+        let mut a1 = d1.transact().get_array("array");
+
+        let d2 = Doc::with_client_id(2);
+        let mut a2 = d2.transact().get_array("array");
+
+        let m1: Rc<RefCell<Vec<MoveChange>>> = Rc::new(RefCell::new(Vec::default()));
+        let inner = m1.clone();
+        let _s1 = a1.observe(move |txn, e| {
+            let mut x = inner.borrow_mut();
+            *x = e.moves(txn).to_vec();
+        });
+
+        let m2: Rc<RefCell<Vec<MoveChange>>> = Rc::new(RefCell::new(Vec::default()));
+        let inner = m2.clone();
+        let _s2 = a2.observe(move |txn, e| {
+            let mut x = inner.borrow_mut();
+            *x = e.moves(txn).to_vec();
+        });
+
+        a1.insert_range(&mut d1.transact(), 0, [1, 2, 3, 4]);
+        assert_eq!(m1.borrow().deref(), &vec![]);
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(m2.borrow().deref(), &vec![]);
+
+        a1.move_range_to(&mut d1.transact(), 0, true, 1, false, 4);
+        assert_eq!(a1.to_json(), vec![3, 4, 1, 2].into());
+        let expected = vec![MoveChange {
+            from: 0,
+            to: 2,
+            len: 2,
+        }];
+        assert_eq!(m1.borrow().deref(), &expected);
+
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(a2.to_json(), vec![3, 4, 1, 2].into());
+        assert_eq!(m2.borrow().deref(), &expected);
+
+        // a copy is not a move
+        {
+            let mut txn = d1.transact();
+            a1.remove(&mut txn, 3);
+            a1.insert(&mut txn, 0, 2);
+        }
+        assert_eq!(a1.to_json(), vec![2, 3, 4, 1].into());
+        assert_eq!(m1.borrow().deref(), &vec![]);
+    }
+
     #[test]
     fn move_cycles() {
         let d1 = Doc::with_client_id(1);
@@ -1250,6 +1515,223 @@ mod test {
         assert_eq!(a1.to_json(), a2.to_json());
     }
 
+    fn to_json_vec<I: Iterator<Item = Value>>(values: I) -> Vec<Any> {
+        values.map(Value::to_json).collect()
+    }
+
+    fn numbers(values: &[i32]) -> Vec<Any> {
+        values.iter().map(|&i| Any::Number(i as f64)).collect()
+    }
+
+    #[test]
+    fn iterate_moved_range_at_the_end_of_block_list() {
+        let doc = Doc::with_client_id(1);
+        let array = doc.transact().get_array("array");
+        array.insert_range(&mut doc.transact(), 0, [1, 2, 3, 4]);
+        // moved elements are the last ones in a block list, but not the last ones in an array
+        array.move_range_to(&mut doc.transact(), 2, true, 3, false, 0);
+
+        assert_eq!(to_json_vec(array.iter()), numbers(&[3, 4, 1, 2]));
+        assert_eq!(to_json_vec(array.range(1..3)), numbers(&[4, 1]));
+        let values: Vec<_> = (0..4).filter_map(|i| array.get(i)).collect();
+        assert_eq!(to_json_vec(values.into_iter()), numbers(&[3, 4, 1, 2]));
+    }
+
+    #[test]
+    fn iterate_from_the_middle_of_moved_block() {
+        let doc = Doc::with_client_id(1);
+        let array = doc.transact().get_array("array");
+        array.insert_range(&mut doc.transact(), 0, [1, 2, 3, 4, 5]);
+        // moved range starts in the middle of a block
+        array.move_range_to(&mut doc.transact(), 1, true, 2, false, 5);
+
+        assert_eq!(to_json_vec(array.iter()), numbers(&[1, 4, 5, 2, 3]));
+        assert_eq!(to_json_vec(array.range(3..)), numbers(&[2, 3]));
+        assert_eq!(to_json_vec(array.range(4..)), numbers(&[3]));
+        assert_eq!(array.get(4).map(Value::to_json), Some(3.into()));
+    }
+
+    #[test]
+    fn insert_into_moved_range() {
+        let doc = Doc::with_client_id(1);
+        let array = doc.transact().get_array("array");
+        array.insert_range(&mut doc.transact(), 0, [1, 2, 3, 4]);
+        array.move_range_to(&mut doc.transact(), 2, true, 3, false, 0);
+
+        // consecutive insertions inside and right after a moved range keep their order
+        array.insert_range(&mut doc.transact(), 1, [5, 6, 7]);
+        assert_eq!(to_json_vec(array.iter()), numbers(&[3, 5, 6, 7, 4, 1, 2]));
+        {
+            let mut txn = doc.transact();
+            array.insert(&mut txn, 5, 8);
+            array.insert(&mut txn, 6, 9);
+        }
+        assert_eq!(
+            to_json_vec(array.iter()),
+            numbers(&[3, 5, 6, 7, 4, 8, 9, 1, 2])
+        );
+        array.push_back(&mut doc.transact(), 10);
+        assert_eq!(array.len(), 10);
+        assert_eq!(
+            to_json_vec(array.iter()),
+            numbers(&[3, 5, 6, 7, 4, 8, 9, 1, 2, 10])
+        );
+    }
+
+    #[test]
+    fn move_range_of_elements_moved_before() {
+        let doc = Doc::with_client_id(1);
+        let array = doc.transact().get_array("array");
+        array.insert_range(&mut doc.transact(), 0, [1, 2, 3, 4]);
+        array.move_to(&mut doc.transact(), 3, 0);
+        assert_eq!(to_json_vec(array.iter()), numbers(&[4, 1, 2, 3]));
+
+        // visible neighbors 4 and 1 are not neighbors in a block list, so they are moved by
+        // two consecutive moves inserted at the end of an array
+        array.move_range_to(&mut doc.transact(), 0, true, 1, false, 4);
+        assert_eq!(to_json_vec(array.iter()), numbers(&[2, 3, 4, 1]));
+        assert_eq!(array.len(), 4);
+    }
+
+    #[test]
+    fn gc_moves() {
+        fn is_move(doc: &Doc, id: &ID) -> bool {
+            let txn = doc.transact();
+            let block = txn.store().blocks.get_block(id).unwrap();
+            matches!(
+                block.as_item().map(|i| &i.content),
+                Some(ItemContent::Move(_))
+            )
+        }
+
+        let doc = Doc::with_client_id(1);
+        let outer = doc.transact().get_array("array");
+        outer.insert(&mut doc.transact(), 0, PrelimArray::from([1, 2, 3])); // clock: 0..=3
+        let inner = match outer.get(0) {
+            Some(Value::YArray(array)) => array,
+            other => panic!("expected nested array, found {:?}", other),
+        };
+        inner.move_to(&mut doc.transact(), 0, 3); // clock: 4
+        inner.move_to(&mut doc.transact(), 2, 0); // clock: 5
+        assert_eq!(inner.to_json(), vec![1, 2, 3].into());
+
+        // first move has been deleted, but it's still overridden by the second one
+        let first = ID::new(1, 4);
+        assert!(doc
+            .transact()
+            .store()
+            .blocks
+            .get_block(&first)
+            .unwrap()
+            .is_deleted());
+        assert!(is_move(&doc, &first));
+
+        // moves of a garbage collected parent are garbage collected as well
+        outer.remove(&mut doc.transact(), 0);
+        for clock in 1..=5 {
+            let txn = doc.transact();
+            let block = txn.store().blocks.get_block(&ID::new(1, clock)).unwrap();
+            assert!(
+                matches!(block.deref(), Block::GC(_)),
+                "{:?} is not GCed",
+                block
+            );
+        }
+    }
+
+    #[test]
+    fn move_into_other_array() {
+        let d1 = Doc::with_client_id(1);
+        let mut a1 = d1.transact().get_array("a");
+        let b1 = d1.transact().get_array("b");
+        let d2 = Doc::with_client_id(2);
+        let a2 = d2.transact().get_array("a");
+        let b2 = d2.transact().get_array("b");
+
+        let deltas: Rc<RefCell<Vec<Change>>> = Rc::new(RefCell::new(Vec::default()));
+        let inner = deltas.clone();
+        let _sub = a1.observe(move |txn, e| {
+            inner.borrow_mut().extend_from_slice(e.delta(txn));
+        });
+
+        a1.insert_range(&mut d1.transact(), 0, [1, 2, 3, 4]);
+        b1.insert_range(&mut d1.transact(), 0, ["a", "b"]);
+        deltas.borrow_mut().clear();
+
+        a1.move_range_into(&mut d1.transact(), 1, 2, &b1, 1);
+        assert_eq!(a1.to_json(), vec![1, 4].into());
+        assert_eq!(a1.len(), 2);
+        assert_eq!(b1.len(), 4);
+        assert_eq!(
+            b1.to_json(),
+            Any::Array(vec!["a".into(), 2.into(), 3.into(), "b".into()].into_boxed_slice())
+        );
+        assert_eq!(
+            deltas.borrow().as_slice(),
+            &[Change::Retain(1), Change::Removed(2)]
+        );
+
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(a2.to_json(), a1.to_json());
+        assert_eq!(b2.to_json(), b1.to_json());
+        assert_eq!(b2.len(), 4);
+
+        // removing moved element updates the array it has been moved into
+        b2.remove(&mut d2.transact(), 2);
+        assert_eq!(b2.len(), 3);
+        assert_eq!(a2.len(), 2);
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(
+            b1.to_json(),
+            Any::Array(vec!["a".into(), 2.into(), "b".into()].into_boxed_slice())
+        );
+        assert_eq!(b1.len(), 3);
+        assert_eq!(a1.len(), 2);
+    }
+
+    #[test]
+    fn move_into_other_array_concurrent() {
+        let d1 = Doc::with_client_id(1);
+        let a1 = d1.transact().get_array("a");
+        let b1 = d1.transact().get_array("b");
+        let d2 = Doc::with_client_id(2);
+        let a2 = d2.transact().get_array("a");
+        let b2 = d2.transact().get_array("b");
+
+        a1.insert_range(&mut d1.transact(), 0, [1, 2, 3, 4]);
+        a1.insert(&mut d1.transact(), 4, PrelimMap::<Any>::new());
+        exchange_updates(&[&d1, &d2]);
+
+        // concurrently: move a range into another array and update moved elements
+        a1.move_range_into(&mut d1.transact(), 1, 4, &b1, 0);
+        {
+            let mut txn = d2.transact();
+            a2.insert(&mut txn, 2, 5);
+            if let Some(Value::YMap(map)) = a2.get(5) {
+                map.insert(&mut txn, "key".to_string(), "value");
+            }
+            a2.remove(&mut txn, 0);
+        }
+
+        exchange_updates(&[&d1, &d2]);
+
+        for (a, b) in [(&a1, &b1), (&a2, &b2)] {
+            assert_eq!(a.to_json(), Any::Array(Box::default()));
+            assert_eq!(a.len(), 0);
+            let mut map = HashMap::new();
+            map.insert("key".to_string(), Any::String("value".into()));
+            let expected = vec![
+                2.into(),
+                5.into(),
+                3.into(),
+                4.into(),
+                Any::Map(Box::new(map)),
+            ];
+            assert_eq!(b.to_json(), Any::Array(expected.into_boxed_slice()));
+            assert_eq!(b.len(), 5);
+        }
+    }
+
     fn move_tests<P: AsRef<std::path::Path>>(path: P) {
         let mut file = File::open(path).unwrap();
         let mut buf = Vec::new();
@@ -1272,4 +1754,285 @@ mod test {
             assert_eq!(actual, expected, "failed at test case nr {}", i);
         }
     }
+
+    #[test]
+    fn insert_json() {
+        let mut obj = HashMap::new();
+        obj.insert("key".to_string(), Any::String("value".into()));
+        let json = Any::Array(
+            vec![
+                Any::Number(1.0),
+                Any::Map(Box::new(obj)),
+                Any::Array(vec![Any::Bool(true)].into_boxed_slice()),
+            ]
+            .into_boxed_slice(),
+        );
+
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let array = txn.get_array("array");
+        array.insert_json(&mut txn, 0, Any::String("first".into()));
+        array.insert_json(&mut txn, 1, json.clone());
+
+        assert_eq!(array.len(), 2);
+        assert_eq!(array.get(0), Some(Value::from("first")));
+        let nested = array.get(1).unwrap().to_yarray().unwrap();
+        assert_eq!(nested.to_json(), json);
+        assert_eq!(nested.get(0), Some(Value::from(1.0)));
+        assert!(nested.get(1).unwrap().to_ymap().is_some());
+        assert!(nested.get(2).unwrap().to_yarray().is_some());
+    }
+
+    #[test]
+    fn insert_json_primitive_runs() {
+        let json = Any::from_json(r#"[1, "a", {"key": 2}, true, null, [3]]"#).unwrap();
+
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let array = txn.get_array("array");
+        array.insert_json(&mut txn, 0, json.clone());
+
+        let nested = array.get(0).unwrap().to_yarray().unwrap();
+        assert_eq!(nested.to_json(), json);
+        // primitive values placed next to each other are stored within the same block
+        let mut lengths = Vec::new();
+        let mut current = nested.0.start;
+        while let Some(Block::Item(item)) = current.as_deref() {
+            lengths.push(item.len());
+            current = item.right;
+        }
+        assert_eq!(lengths, vec![2, 1, 2, 1]);
+    }
+
+    #[test]
+    fn splice() {
+        let d1 = Doc::with_client_id(1);
+        let mut a1 = d1.transact().get_array("array");
+        a1.insert_range(&mut d1.transact(), 0, [1, 2, 3, 4, 5]);
+
+        let delta = Rc::new(RefCell::new(None));
+        let delta_c = delta.clone();
+        let _sub = a1.observe(move |txn, e| {
+            *delta_c.borrow_mut() = Some(e.delta(txn).to_vec());
+        });
+
+        a1.splice(&mut d1.transact(), 1, 3, ["a", "b"]);
+        assert_eq!(
+            a1.to_json(),
+            Any::Array(vec![1.into(), "a".into(), "b".into(), 5.into()].into_boxed_slice())
+        );
+        assert_eq!(
+            delta.borrow_mut().take(),
+            Some(vec![
+                Change::Retain(1),
+                Change::Removed(3),
+                Change::Added(vec!["a".into(), "b".into()])
+            ])
+        );
+
+        a1.splice(&mut d1.transact(), 4, 0, [6]);
+        a1.splice(&mut d1.transact(), 0, 2, Vec::<Any>::new());
+        assert_eq!(
+            a1.to_json(),
+            Any::Array(vec!["b".into(), 5.into(), 6.into()].into_boxed_slice())
+        );
+
+        let d2 = Doc::with_client_id(2);
+        let a2 = d2.transact().get_array("array");
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(a2.to_json(), a1.to_json());
+    }
+
+    #[test]
+    fn splice_after_move() {
+        let d1 = Doc::with_client_id(1);
+        let a1 = d1.transact().get_array("array");
+        a1.insert_range(&mut d1.transact(), 0, [1, 2, 3, 4, 5]);
+        a1.move_to(&mut d1.transact(), 0, 2);
+        assert_eq!(a1.to_json(), Any::from_json("[2,1,3,4,5]").unwrap());
+
+        let d2 = Doc::with_client_id(2);
+        let a2 = d2.transact().get_array("array");
+        exchange_updates(&[&d1, &d2]);
+
+        a1.splice(&mut d1.transact(), 1, 1, [9]);
+        assert_eq!(a1.to_json(), Any::from_json("[2,9,3,4,5]").unwrap());
+
+        // freshly decoded document has no search markers
+        a2.splice(&mut d2.transact(), 1, 1, [9]);
+        assert_eq!(a2.to_json(), Any::from_json("[2,9,3,4,5]").unwrap());
+
+        // moved range placed at the end of an array
+        let d3 = Doc::with_client_id(3);
+        let a3 = d3.transact().get_array("array");
+        a3.insert_range(&mut d3.transact(), 0, [1, 2, 3]);
+        a3.move_to(&mut d3.transact(), 0, 3);
+        a3.splice(&mut d3.transact(), 2, 1, [9]);
+        assert_eq!(a3.to_json(), Any::from_json("[2,3,9]").unwrap());
+        a3.move_range_to(&mut d3.transact(), 0, true, 1, false, 3);
+        a3.splice(&mut d3.transact(), 1, 2, [7, 8]);
+        assert_eq!(a3.to_json(), Any::from_json("[9,7,8]").unwrap());
+    }
+
+    #[test]
+    fn retain_after_move() {
+        let d1 = Doc::with_client_id(1);
+        let a1 = d1.transact().get_array("array");
+        a1.insert_range(&mut d1.transact(), 0, [1, 2, 3, 4, 5]);
+        a1.move_to(&mut d1.transact(), 0, 2);
+        a1.move_to(&mut d1.transact(), 4, 0);
+        assert_eq!(a1.to_json(), Any::from_json("[5,2,1,3,4]").unwrap());
+
+        a1.retain(&mut d1.transact(), |value| {
+            let n = value.clone().to_json();
+            n != Any::Number(2.0) && n != Any::Number(3.0)
+        });
+        assert_eq!(a1.to_json(), Any::from_json("[5,1,4]").unwrap());
+    }
+
+    #[test]
+    fn retain() {
+        let d1 = Doc::with_client_id(1);
+        let a1 = d1.transact().get_array("array");
+        a1.insert_range(&mut d1.transact(), 0, [1, 2, 3, 4, 5, 6, 7]);
+        a1.push_back(&mut d1.transact(), 8);
+
+        let mut visited = Vec::new();
+        let mut txn = d1.transact();
+        a1.retain(&mut txn, |value| {
+            let n = value.clone().to_json();
+            visited.push(n.clone());
+            n == Any::Number(1.0) || n == Any::Number(6.0) || n == Any::Number(8.0)
+        });
+        assert_eq!(visited.len(), 8);
+
+        // consecutive elements are removed as a single range
+        let ranges: Vec<_> = txn
+            .delete_set
+            .iter()
+            .flat_map(|(_, range)| range.iter().cloned().collect::<Vec<_>>())
+            .collect();
+        assert_eq!(ranges, vec![1..5, 6..7]);
+        txn.commit();
+        assert_eq!(
+            a1.to_json(),
+            Any::Array(vec![1.into(), 6.into(), 8.into()].into_boxed_slice())
+        );
+
+        a1.retain(&mut d1.transact(), |_| false);
+        assert_eq!(a1.len(), 0);
+
+        let d2 = Doc::with_client_id(2);
+        let a2 = d2.transact().get_array("array");
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(a2.to_json(), a1.to_json());
+    }
+
+    #[test]
+    fn range_and_reverse_iteration() {
+        let doc = Doc::with_client_id(1);
+        let array = doc.transact().get_array("array");
+        array.insert_range(&mut doc.transact(), 0, [0, 1, 2, 3]);
+        array.insert_range(&mut doc.transact(), 4, [4, 5, 6, 7, 8, 9]);
+        array.remove_range(&mut doc.transact(), 2, 2);
+        array.insert(&mut doc.transact(), 2, PrelimMap::<Any>::new());
+        // [0, 1, {}, 4, 5, 6, 7, 8, 9]
+
+        let to_json =
+            |values: Vec<Value>| -> Vec<Any> { values.into_iter().map(Value::to_json).collect() };
+        let numbers = |values: &[i32]| -> Vec<Any> {
+            values.iter().map(|&i| Any::Number(i as f64)).collect()
+        };
+
+        let actual: Vec<_> = array.range(3..6).collect();
+        assert_eq!(to_json(actual), numbers(&[4, 5, 6]));
+        let actual: Vec<_> = array.range(6..).collect();
+        assert_eq!(to_json(actual), numbers(&[7, 8, 9]));
+        let actual: Vec<_> = array.range(..=1).collect();
+        assert_eq!(to_json(actual), numbers(&[0, 1]));
+        assert_eq!(array.range(4..4).count(), 0);
+        assert_eq!(array.range(1..8).len(), 7);
+
+        let actual: Vec<_> = array.iter().rev().collect();
+        let mut expected = to_json(array.iter().collect());
+        expected.reverse();
+        assert_eq!(to_json(actual), expected);
+
+        let actual: Vec<_> = array.range(1..7).rev().collect();
+        assert_eq!(
+            to_json(actual),
+            vec![
+                7.into(),
+                6.into(),
+                5.into(),
+                4.into(),
+                Any::Map(Box::new(HashMap::new())),
+                1.into()
+            ]
+        );
+
+        // front and back meet in the middle
+        let mut iter = array.range(3..7);
+        assert_eq!(iter.next().map(Value::to_json), Some(4.into()));
+        assert_eq!(iter.next_back().map(Value::to_json), Some(7.into()));
+        assert_eq!(iter.next_back().map(Value::to_json), Some(6.into()));
+        assert_eq!(iter.next().map(Value::to_json), Some(5.into()));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn iter_rev_after_moves() {
+        let reversed = |array: &crate::Array| -> Vec<Any> {
+            let mut expected = match array.to_json() {
+                Any::Array(values) => values.into_vec(),
+                other => panic!("expected array, got {}", other),
+            };
+            expected.reverse();
+            expected
+        };
+        let cases: &[&[(u32, u32)]] = &[&[(0, 5)], &[(4, 0)], &[(0, 2), (4, 1)], &[(2, 0), (0, 5)]];
+        for moves in cases {
+            let doc = Doc::with_client_id(1);
+            let array = doc.transact().get_array("array");
+            array.insert_range(&mut doc.transact(), 0, [1, 2, 3, 4, 5]);
+            for &(source, target) in moves.iter() {
+                array.move_to(&mut doc.transact(), source, target);
+            }
+            let actual: Vec<_> = array.iter().rev().map(Value::to_json).collect();
+            assert_eq!(actual, reversed(&array), "failed for moves {:?}", moves);
+            let actual: Vec<_> = array.range(1..4).rev().map(Value::to_json).collect();
+            let expected: Vec<_> = reversed(&array)[1..4].to_vec();
+            assert_eq!(actual, expected, "failed for moves {:?}", moves);
+        }
+    }
+
+    #[test]
+    fn slice_to_json() {
+        let doc = Doc::with_client_id(1);
+        let array = doc.transact().get_array("array");
+        array.insert_range(&mut doc.transact(), 0, [0, 1, 2, 3, 4]);
+        array.push_back(&mut doc.transact(), "five");
+        array.remove(&mut doc.transact(), 1);
+
+        assert_eq!(
+            array.slice_to_json(1..4),
+            Any::Array(vec![2.into(), 3.into(), 4.into()].into_boxed_slice())
+        );
+        assert_eq!(
+            array.slice_to_json(3..),
+            Any::Array(vec![4.into(), "five".into()].into_boxed_slice())
+        );
+        assert_eq!(array.slice_to_json(2..2), Any::Array(Box::default()));
+        assert_eq!(array.slice_to_json(..), array.to_json());
+    }
+
+    #[test]
+    #[should_panic]
+    fn range_out_of_bounds() {
+        let doc = Doc::with_client_id(1);
+        let array = doc.transact().get_array("array");
+        array.insert_range(&mut doc.transact(), 0, [0, 1, 2]);
+        array.range(1..4);
+    }
 }
@@ -0,0 +1,309 @@
+use crate::block::{Block, ItemContent, ItemPosition, Prelim};
+use crate::event::Subscription;
+use crate::types::{
+    event_keys, Branch, BranchPtr, EntryChange, Observers, Path, Value, TYPE_REFS_COUNTER,
+};
+use crate::{SubscriptionId, Transaction};
+use lib0::any::Any;
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+/// Counter is a shared type representing a single integer number, which - unlike numbers stored
+/// inside of a [Map] - can be concurrently incremented and decremented by different peers without
+/// losing any of their updates.
+///
+/// Each peer keeps its own contribution stored under an entry identified by its client id and is
+/// the only one ever updating it. The value of a counter is a sum of all contributions, so
+/// concurrent changes made by different peers are always added up together.
+///
+/// [Map]: crate::Map
+#[repr(transparent)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Counter(BranchPtr);
+
+impl Counter {
+    /// Returns a current value of this counter. If a sum of contributions of all peers doesn't fit
+    /// into [i64], it's saturated at [i64::MAX] or [i64::MIN]. Since contributions are summed up
+    /// before saturating, all peers end up with the same value no matter in which order they
+    /// received the updates.
+    pub fn get(&self) -> i64 {
+        let mut sum: i128 = 0;
+        for ptr in self.0.map.values() {
+            if let Block::Item(item) = ptr.deref() {
+                if !item.is_deleted() {
+                    sum += Self::contribution(item.content.get_last()) as i128;
+                }
+            }
+        }
+        saturate(sum)
+    }
+
+    /// Increments a value of this counter by a given `delta`, which can be negative.
+    ///
+    /// Contribution of a current peer saturates at [i64::MAX] or [i64::MIN] instead of
+    /// overflowing, so incrementing it past these bounds has no effect.
+    pub fn increment(&self, txn: &mut Transaction, delta: i64) {
+        if delta == 0 {
+            return;
+        }
+
+        let key: Rc<str> = txn.store().options.client_id.to_string().into();
+        let current = Self::contribution(self.0.get(&key));
+        let pos = {
+            let inner = self.0;
+            let left = inner.map.get(&key);
+            ItemPosition {
+                parent: inner.into(),
+                left: left.cloned(),
+                right: None,
+                index: 0,
+                current_attrs: None,
+            }
+        };
+
+        txn.create_item(&pos, current.saturating_add(delta), Some(key));
+    }
+
+    /// Decrements a value of this counter by a given `delta`. It's an equivalent of calling
+    /// [Counter::increment] with a negated `delta`.
+    pub fn decrement(&self, txn: &mut Transaction, delta: i64) {
+        self.increment(txn, delta.saturating_neg())
+    }
+
+    /// Converts current counter into a JSON-like number.
+    pub fn to_json(&self) -> Any {
+        Any::BigInt(self.get())
+    }
+
+    /// Subscribes a given callback to be triggered whenever current counter is changed.
+    /// A callback is triggered whenever a transaction gets committed.
+    ///
+    /// Change of counter value can be read by using [CounterEvent::delta] method.
+    ///
+    /// Returns an [Observer] which, when dropped, will unsubscribe current callback.
+    pub fn observe<F>(&mut self, f: F) -> Subscription<CounterEvent>
+    where
+        F: Fn(&Transaction, &CounterEvent) + 'static,
+    {
+        if let Observers::Counter(eh) = self.0.observers.get_or_insert_with(Observers::counter) {
+            eh.subscribe(f)
+        } else {
+            panic!("Observed collection is of different type") //TODO: this should be Result::Err
+        }
+    }
+
+    /// Unsubscribes a previously subscribed event callback identified by given `subscription_id`.
+    pub fn unobserve(&mut self, subscription_id: SubscriptionId) {
+        if let Some(Observers::Counter(eh)) = self.0.observers.as_mut() {
+            eh.unsubscribe(subscription_id);
+        }
+    }
+
+    fn contribution(value: Option<Value>) -> i64 {
+        match value {
+            Some(Value::Any(Any::BigInt(n))) => n,
+            Some(Value::Any(Any::Number(n))) => n as i64,
+            _ => 0,
+        }
+    }
+}
+
+/// Converts a sum of counter contributions into [i64], saturating at its bounds.
+fn saturate(sum: i128) -> i64 {
+    sum.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+impl AsRef<Branch> for Counter {
+    fn as_ref(&self) -> &Branch {
+        self.0.deref()
+    }
+}
+
+impl AsMut<Branch> for Counter {
+    fn as_mut(&mut self) -> &mut Branch {
+        self.0.deref_mut()
+    }
+}
+
+impl From<BranchPtr> for Counter {
+    fn from(inner: BranchPtr) -> Self {
+        Counter(inner)
+    }
+}
+
+/// A preliminary counter. It can be used to early initialize the value of a [Counter], when it's
+/// about to be inserted into another Yrs collection, such as [Array] or [Map].
+///
+/// [Array]: crate::Array
+/// [Map]: crate::Map
+pub struct PrelimCounter(i64);
+
+impl PrelimCounter {
+    pub fn new(value: i64) -> Self {
+        PrelimCounter(value)
+    }
+}
+
+impl Prelim for PrelimCounter {
+    fn into_content(self, _txn: &mut Transaction) -> (ItemContent, Option<Self>) {
+        let inner = Branch::new(TYPE_REFS_COUNTER, None);
+        (ItemContent::Type(inner), Some(self))
+    }
+
+    fn integrate(self, txn: &mut Transaction, inner_ref: BranchPtr) {
+        Counter::from(inner_ref).increment(txn, self.0);
+    }
+}
+
+/// Event generated by [Counter::observe] method. Emitted during transaction commit phase.
+pub struct CounterEvent {
+    pub(crate) current_target: BranchPtr,
+    target: Counter,
+    keys: HashSet<Option<Rc<str>>>,
+}
+
+impl CounterEvent {
+    pub(crate) fn new(branch_ref: BranchPtr, key_changes: HashSet<Option<Rc<str>>>) -> Self {
+        CounterEvent {
+            target: Counter::from(branch_ref),
+            current_target: branch_ref,
+            keys: key_changes,
+        }
+    }
+
+    /// Returns a [Counter] instance which emitted this event.
+    pub fn target(&self) -> &Counter {
+        &self.target
+    }
+
+    /// Returns a path from root type down to [Counter] instance which emitted this event.
+    pub fn path(&self) -> Path {
+        Branch::path(self.current_target, self.target.0)
+    }
+
+    /// Returns a difference between a value of a [Counter] after and before the transaction,
+    /// which emitted this event. Like [Counter::get], it saturates at the [i64] bounds.
+    pub fn delta(&self, txn: &Transaction) -> i64 {
+        let mut delta: i128 = 0;
+        for change in event_keys(txn, self.target.0, &self.keys).into_values() {
+            let contribution = |value| Counter::contribution(Some(value)) as i128;
+            delta += match change {
+                EntryChange::Inserted(new) => contribution(new),
+                EntryChange::Updated(old, new) => contribution(new) - contribution(old),
+                EntryChange::Removed(old) => -contribution(old),
+            };
+        }
+        saturate(delta)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::exchange_updates;
+    use crate::types::counter::PrelimCounter;
+    use crate::updates::decoder::Decode;
+    use crate::updates::encoder::{Encoder, EncoderV2};
+    use crate::{Doc, StateVector, Update};
+    use lib0::any::Any;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn concurrent_increments() {
+        let d1 = Doc::with_client_id(1);
+        let c1 = d1.transact().get_counter("votes");
+        let d2 = Doc::with_client_id(2);
+        let c2 = d2.transact().get_counter("votes");
+
+        c1.increment(&mut d1.transact(), 3);
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(c2.get(), 3);
+
+        c1.increment(&mut d1.transact(), 2);
+        c2.decrement(&mut d2.transact(), 1);
+        c2.increment(&mut d2.transact(), 5);
+
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(c1.get(), 9);
+        assert_eq!(c2.get(), 9);
+        assert_eq!(c1.to_json(), Any::BigInt(9));
+    }
+
+    #[test]
+    fn nested_counter_encoding_v2() {
+        let d1 = Doc::with_client_id(1);
+        let map = d1.transact().get_map("stats");
+        {
+            let mut txn = d1.transact();
+            map.insert(&mut txn, "views", PrelimCounter::new(10));
+        }
+        let counter = map.get("views").unwrap().to_ycounter().unwrap();
+        counter.decrement(&mut d1.transact(), 4);
+
+        let mut encoder = EncoderV2::new();
+        d1.transact()
+            .encode_diff(&StateVector::default(), &mut encoder);
+        let update = encoder.to_vec();
+
+        let d2 = Doc::with_client_id(2);
+        let map = d2.transact().get_map("stats");
+        d2.transact()
+            .apply_update(Update::decode_v2(update.as_slice()).unwrap());
+        let counter = map.get("views").unwrap().to_ycounter().unwrap();
+        assert_eq!(counter.get(), 6);
+        assert_eq!(map.get("views").unwrap().to_json(), Any::BigInt(6));
+    }
+
+    #[test]
+    fn observe_delta() {
+        let d1 = Doc::with_client_id(1);
+        let c1 = d1.transact().get_counter("counter");
+        let d2 = Doc::with_client_id(2);
+        let mut c2 = d2.transact().get_counter("counter");
+
+        let delta = Rc::new(Cell::new(0));
+        let delta_c = delta.clone();
+        let _sub = c2.observe(move |txn, e| delta_c.set(e.delta(txn)));
+
+        c2.increment(&mut d2.transact(), 2);
+        assert_eq!(delta.get(), 2);
+
+        {
+            let mut txn = d1.transact();
+            c1.increment(&mut txn, 7);
+            c1.decrement(&mut txn, 3);
+        }
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(delta.get(), 4);
+        assert_eq!(c2.get(), 6);
+    }
+
+    #[test]
+    fn saturate_on_overflow() {
+        let d1 = Doc::with_client_id(1);
+        let c1 = d1.transact().get_counter("counter");
+        let d2 = Doc::with_client_id(2);
+        let c2 = d2.transact().get_counter("counter");
+
+        c1.increment(&mut d1.transact(), i64::MAX);
+        c1.increment(&mut d1.transact(), 1);
+        assert_eq!(c1.get(), i64::MAX);
+        c1.decrement(&mut d1.transact(), 1);
+        assert_eq!(c1.get(), i64::MAX - 1);
+
+        c2.increment(&mut d2.transact(), 5);
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(c1.get(), i64::MAX);
+        assert_eq!(c2.get(), i64::MAX);
+
+        // sum is saturated only after all contributions are added up
+        c2.decrement(&mut d2.transact(), 10);
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(c1.get(), i64::MAX - 6);
+        assert_eq!(c2.get(), i64::MAX - 6);
+
+        c2.decrement(&mut d2.transact(), i64::MIN);
+        assert_eq!(c2.get(), i64::MAX);
+    }
+}
@@ -1,7 +1,10 @@
 use crate::block::{Block, ItemContent, ItemPosition, Prelim};
 use crate::event::Subscription;
+use crate::moving::RelativePosition;
+use crate::types::weak::{LinkSource, WeakPrelim};
 use crate::types::{
-    event_keys, Branch, BranchPtr, Entries, EntryChange, Observers, Path, Value, TYPE_REFS_MAP,
+    event_keys, Branch, BranchPtr, Entries, EntryChange, Observers, Path, PrelimJson, Value,
+    TYPE_REFS_MAP,
 };
 use crate::*;
 use lib0::any::Any;
@@ -104,6 +107,21 @@ impl Map {
         previous
     }
 
+    /// Inserts a JSON-like `value` under given `key` into current map. Unlike [Map::insert],
+    /// JSON objects and arrays are recursively converted into nested [Map]s and [Array]s, so that
+    /// their entries can be concurrently updated by different peers. Returns a value stored
+    /// previously under the same key (if any existed).
+    ///
+    /// Use [PrelimJson::strings_as_text] in order to also turn JSON strings into [Text].
+    pub fn insert_json<K: Into<Rc<str>>, V: Into<PrelimJson>>(
+        &self,
+        txn: &mut Transaction,
+        key: K,
+        value: V,
+    ) -> Option<Value> {
+        self.insert(txn, key, value.into())
+    }
+
     /// Removes a stored within current map under a given `key`. Returns that value or `None` if
     /// no entry with a given `key` was present in current map.
     pub fn remove(&self, txn: &mut Transaction, key: &str) -> Option<Value> {
@@ -116,6 +134,26 @@ impl Map {
         self.0.get(key)
     }
 
+    /// Returns a preliminary [WeakLink] to an entry stored under a given `key`, or `None` if no
+    /// entry with such `key` existed. Once inserted into another collection, a link resolves to
+    /// the latest value assigned under that `key`.
+    ///
+    /// [WeakLink]: crate::types::weak::WeakLink
+    pub fn link(&self, key: &str) -> Option<WeakPrelim> {
+        let ptr = self.0.map.get(key)?;
+        if let Block::Item(item) = ptr.deref() {
+            if !item.is_deleted() {
+                let id = item.last_id();
+                let source = LinkSource::new(
+                    RelativePosition::create(id, true),
+                    RelativePosition::create(id, false),
+                );
+                return Some(WeakPrelim::new(source));
+            }
+        }
+        None
+    }
+
     /// Checks if an entry with given `key` can be found within current map.
     pub fn contains(&self, key: &str) -> bool {
         if let Some(ptr) = self.0.map.get(key) {
@@ -139,7 +177,7 @@ impl Map {
     ///
     /// All map changes can be tracked by using [Event::keys] method.
     ///
-    /// Returns an [Observer] which, when dropped, will unsubscribe current callback.
+    /// Returns a [Subscription] which, when dropped, will unsubscribe current callback.
     pub fn observe<F>(&mut self, f: F) -> Subscription<MapEvent>
     where
         F: Fn(&Transaction, &MapEvent) -> () + 'static,
@@ -151,6 +189,24 @@ impl Map {
         }
     }
 
+    /// Subscribes a given callback to be triggered whenever an entry under a given `key` of
+    /// current map is changed. Unlike [Map::observe], callback is not triggered when a
+    /// transaction changed only other entries.
+    ///
+    /// Returns a [Subscription] which, when dropped, will unsubscribe current callback.
+    pub fn observe_key<K, F>(&mut self, key: K, f: F) -> Subscription<MapEvent>
+    where
+        K: Into<Rc<str>>,
+        F: Fn(&Transaction, &MapEvent) + 'static,
+    {
+        let key = key.into();
+        self.observe(move |txn, e| {
+            if e.keys(txn).contains_key(&key) {
+                f(txn, e)
+            }
+        })
+    }
+
     /// Unsubscribes a previously subscribed event callback identified by given `subscription_id`.
     pub fn unobserve(&mut self, subscription_id: SubscriptionId) {
         if let Some(Observers::Map(eh)) = self.0.observers.as_mut() {
@@ -299,7 +355,9 @@ impl MapEvent {
 mod test {
     use crate::test_utils::{exchange_updates, run_scenario};
     use crate::types::text::PrelimText;
-    use crate::types::{DeepObservable, EntryChange, Event, Map, Path, PathSegment, Value};
+    use crate::types::{
+        DeepObservable, EntryChange, Event, Map, Path, PathSegment, PrelimJson, Value,
+    };
     use crate::updates::decoder::Decode;
     use crate::updates::encoder::{Encoder, EncoderV1};
     use crate::{Doc, PrelimArray, PrelimMap, StateVector, Update};
@@ -858,4 +916,155 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn insert_json() {
+        let mut json = HashMap::new();
+        json.insert("title".to_string(), Any::String("hello".into()));
+        json.insert(
+            "tags".to_string(),
+            Any::Array(vec![Any::String("a".into()), Any::Number(1.0)].into_boxed_slice()),
+        );
+        let json = Any::Map(Box::new(json));
+
+        let d1 = Doc::with_client_id(1);
+        let d2 = Doc::with_client_id(2);
+        {
+            let mut t1 = d1.transact();
+            let m1 = t1.get_map("map");
+            m1.insert_json(&mut t1, "json", json.clone());
+            m1.insert_json(
+                &mut t1,
+                "rich",
+                PrelimJson::new(json.clone()).strings_as_text(),
+            );
+        }
+        exchange_updates(&[&d1, &d2]);
+
+        {
+            let mut t1 = d1.transact();
+            let nested = t1.get_map("map").get("json").unwrap().to_ymap().unwrap();
+            nested.insert(&mut t1, "title", "updated");
+        }
+        {
+            let mut t2 = d2.transact();
+            let nested = t2.get_map("map").get("json").unwrap().to_ymap().unwrap();
+            let tags = nested.get("tags").unwrap().to_yarray().unwrap();
+            tags.push_back(&mut t2, true);
+        }
+        exchange_updates(&[&d1, &d2]);
+
+        for doc in [&d1, &d2] {
+            let mut txn = doc.transact();
+            let map = txn.get_map("map");
+            let nested = map.get("json").unwrap().to_ymap().unwrap();
+            assert_eq!(nested.get("title"), Some(Value::from("updated")));
+            let tags = nested.get("tags").unwrap().to_yarray().unwrap();
+            assert_eq!(
+                tags.to_json(),
+                Any::Array(
+                    vec![Any::String("a".into()), Any::Number(1.0), Any::Bool(true)]
+                        .into_boxed_slice()
+                )
+            );
+
+            let rich = map.get("rich").unwrap().to_ymap().unwrap();
+            let title = rich.get("title").unwrap().to_ytext().unwrap();
+            assert_eq!(title.to_string(), "hello");
+            let tags = rich.get("tags").unwrap().to_yarray().unwrap();
+            assert!(tags.get(0).unwrap().to_ytext().is_some());
+            assert_eq!(tags.get(1), Some(Value::from(1.0)));
+        }
+    }
+
+    #[test]
+    fn observe_key() {
+        let doc = Doc::with_client_id(1);
+        let mut map = doc.transact().get_map("map");
+        let calls = Rc::new(RefCell::new(0));
+        let calls_c = calls.clone();
+        let _sub = map.observe_key("a", move |_, _| *calls_c.borrow_mut() += 1);
+
+        map.insert(&mut doc.transact(), "b", 1);
+        assert_eq!(*calls.borrow(), 0);
+
+        map.insert(&mut doc.transact(), "a", 1);
+        assert_eq!(*calls.borrow(), 1);
+
+        {
+            let mut txn = doc.transact();
+            map.insert(&mut txn, "a", 2);
+            map.insert(&mut txn, "b", 2);
+        }
+        assert_eq!(*calls.borrow(), 2);
+
+        map.remove(&mut doc.transact(), "a");
+        assert_eq!(*calls.borrow(), 3);
+    }
+
+    #[test]
+    fn observe_path() {
+        let doc = Doc::with_client_id(1);
+        let mut map = doc.transact().get_map("map");
+        {
+            let mut txn = doc.transact();
+            let todo1 = PrelimMap::from({
+                let mut m = HashMap::new();
+                m.insert("title".to_string(), "a");
+                m
+            });
+            let todo2 = PrelimMap::from({
+                let mut m = HashMap::new();
+                m.insert("title".to_string(), "b");
+                m
+            });
+            map.insert(&mut txn, "todos", PrelimArray::from(vec![todo1, todo2]));
+            map.insert(&mut txn, "name", "list");
+        }
+
+        let paths = Rc::new(RefCell::new(Vec::new()));
+        let paths_c = paths.clone();
+        let _sub = map.observe_path(
+            Path::from(vec![
+                PathSegment::Key("todos".into()),
+                PathSegment::Index(1),
+            ]),
+            move |_, events| {
+                let p: Vec<Path> = events.iter().map(Event::path).collect();
+                paths_c.borrow_mut().push(p);
+            },
+        );
+
+        let todos = map.get("todos").unwrap().to_yarray().unwrap();
+        let todo1 = todos.get(0).unwrap().to_ymap().unwrap();
+        let todo2 = todos.get(1).unwrap().to_ymap().unwrap();
+
+        // changes outside of observed path
+        map.insert(&mut doc.transact(), "name", "list2");
+        todo1.insert(&mut doc.transact(), "title", "c");
+        todos.push_back(&mut doc.transact(), "d");
+        assert!(paths.borrow().is_empty());
+
+        // change made to an observed type
+        todo2.insert(&mut doc.transact(), "title", "e");
+        assert_eq!(
+            paths.borrow_mut().pop(),
+            Some(vec![Path::from(vec![
+                PathSegment::Key("todos".into()),
+                PathSegment::Index(1),
+            ])])
+        );
+
+        // change shifting observed index
+        todos.remove(&mut doc.transact(), 0);
+        assert_eq!(
+            paths.borrow_mut().pop(),
+            Some(vec![Path::from(vec![PathSegment::Key("todos".into())])])
+        );
+
+        // change replacing a parent
+        map.insert(&mut doc.transact(), "todos", "none");
+        assert_eq!(paths.borrow_mut().pop(), Some(vec![Path::new()]));
+        assert!(paths.borrow().is_empty());
+    }
 }
@@ -0,0 +1,325 @@
+//! Conversion of CommonMark documents into a formatted [Text](crate::Text) content.
+//!
+//! Markdown is mapped onto Quill-style rich text: inline formatting is described by `bold`,
+//! `italic`, `strike`, `code` and `link` attributes, while every line of text is terminated with
+//! a new line character, which carries block formatting of that line: `header` (with a heading
+//! level), `list` (`bullet`, `ordered`, `checked` or `unchecked`) with an optional `indent` for
+//! nested lists, `blockquote` and `code-block`. Images are inserted as `{"image": url}` embeds
+//! and thematic breaks as `{"divider": true}` embeds placed in their own lines.
+
+use crate::types::{Attrs, Delta, Value};
+use lib0::any::Any;
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use std::collections::HashMap;
+
+/// Parses a given CommonMark `markdown` document into a list of [Delta::Inserted] chunks, which
+/// can be inserted one after another into a [Text](crate::Text) to reproduce the document.
+/// See [Text::insert_markdown](crate::Text::insert_markdown).
+pub fn parse(markdown: &str) -> Vec<Delta> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    let mut builder = Builder::default();
+    for event in Parser::new_ext(markdown, options) {
+        builder.event(event);
+    }
+    builder.line_break();
+    builder.delta
+}
+
+#[derive(Default)]
+struct Builder {
+    delta: Vec<Delta>,
+    /// Set to true when some content has been inserted since the last line break.
+    line_open: bool,
+    bold: u32,
+    italic: u32,
+    strike: u32,
+    links: Vec<String>,
+    /// Number of images being currently processed: their alternative text is not inserted.
+    images: u32,
+    heading: Option<u32>,
+    code_block: bool,
+    blockquote: u32,
+    /// Stack of currently open lists: `true` if a list is ordered.
+    lists: Vec<bool>,
+    /// Task list marker of the current list item.
+    task: Option<bool>,
+}
+
+impl Builder {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) | Event::Html(text) => self.text(&text),
+            Event::Code(code) => {
+                let mut attrs = self.inline_attributes();
+                attrs.insert("code".into(), Any::Bool(true));
+                self.push(Value::Any(Any::String(code.as_ref().into())), attrs);
+            }
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.line_break(),
+            Event::Rule => {
+                self.line_break();
+                let mut divider = HashMap::new();
+                divider.insert("divider".to_string(), Any::Bool(true));
+                self.push(Value::Any(Any::Map(Box::new(divider))), Attrs::new());
+                self.line_break();
+            }
+            Event::TaskListMarker(checked) => self.task = Some(checked),
+            Event::FootnoteReference(_) => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading(level, _, _) => {
+                self.line_break();
+                self.heading = Some(level as u32);
+            }
+            Tag::CodeBlock(_) => {
+                self.line_break();
+                self.code_block = true;
+            }
+            Tag::BlockQuote => {
+                self.line_break();
+                self.blockquote += 1;
+            }
+            Tag::List(start) => {
+                // nested list starts in the middle of its parent list item
+                self.line_break();
+                self.lists.push(start.is_some());
+            }
+            Tag::Item => {
+                self.line_break();
+                self.task = None;
+            }
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Strikethrough => self.strike += 1,
+            Tag::Link(_, href, _) => self.links.push(href.to_string()),
+            Tag::Image(_, src, _) => {
+                if self.images == 0 {
+                    let mut image = HashMap::new();
+                    image.insert("image".to_string(), Any::String(src.as_ref().into()));
+                    let attrs = self.inline_attributes();
+                    self.push(Value::Any(Any::Map(Box::new(image))), attrs);
+                }
+                self.images += 1;
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph | Tag::Item => self.line_break(),
+            Tag::Heading(_, _, _) => {
+                self.line_break();
+                self.heading = None;
+            }
+            Tag::CodeBlock(_) => {
+                self.line_break();
+                self.code_block = false;
+            }
+            Tag::BlockQuote => {
+                self.line_break();
+                self.blockquote -= 1;
+            }
+            Tag::List(_) => {
+                self.line_break();
+                self.lists.pop();
+            }
+            Tag::Emphasis => self.italic -= 1,
+            Tag::Strong => self.bold -= 1,
+            Tag::Strikethrough => self.strike -= 1,
+            Tag::Link(_, _, _) => {
+                self.links.pop();
+            }
+            Tag::Image(_, _, _) => self.images -= 1,
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.images > 0 {
+            return;
+        }
+        let mut lines = text.split('\n');
+        if let Some(line) = lines.next() {
+            self.push_str(line);
+        }
+        for line in lines {
+            self.end_line();
+            self.push_str(line);
+        }
+    }
+
+    fn push_str(&mut self, s: &str) {
+        if !s.is_empty() {
+            let attrs = self.inline_attributes();
+            self.push(Value::Any(Any::String(s.into())), attrs);
+        }
+    }
+
+    /// Terminates current line if any content has been inserted into it.
+    fn line_break(&mut self) {
+        if self.line_open {
+            self.end_line();
+        }
+    }
+
+    /// Inserts a new line character carrying block formatting of the current line.
+    fn end_line(&mut self) {
+        let attrs = self.block_attributes();
+        self.push(Value::Any(Any::String("\n".into())), attrs);
+        self.line_open = false;
+    }
+
+    fn push(&mut self, value: Value, attrs: Attrs) {
+        self.line_open = !matches!(&value, Value::Any(Any::String(s)) if s.as_ref() == "\n");
+        let attrs = if attrs.is_empty() {
+            None
+        } else {
+            Some(Box::new(attrs))
+        };
+        if let (
+            Value::Any(Any::String(s)),
+            Some(Delta::Inserted(Value::Any(Any::String(last)), last_attrs)),
+        ) = (&value, self.delta.last_mut())
+        {
+            // merge consecutive text chunks sharing the same formatting
+            if *last_attrs == attrs && s.as_ref() != "\n" && !last.ends_with('\n') {
+                let mut merged = last.to_string();
+                merged.push_str(s);
+                *last = merged.into();
+                return;
+            }
+        }
+        self.delta.push(Delta::Inserted(value, attrs));
+    }
+
+    fn inline_attributes(&self) -> Attrs {
+        let mut attrs = Attrs::new();
+        if self.bold > 0 {
+            attrs.insert("bold".into(), Any::Bool(true));
+        }
+        if self.italic > 0 {
+            attrs.insert("italic".into(), Any::Bool(true));
+        }
+        if self.strike > 0 {
+            attrs.insert("strike".into(), Any::Bool(true));
+        }
+        if let Some(href) = self.links.last() {
+            attrs.insert("link".into(), Any::String(href.as_str().into()));
+        }
+        attrs
+    }
+
+    fn block_attributes(&self) -> Attrs {
+        let mut attrs = Attrs::new();
+        if let Some(level) = self.heading {
+            attrs.insert("header".into(), Any::Number(level as f64));
+        } else if self.code_block {
+            attrs.insert("code-block".into(), Any::Bool(true));
+        }
+        if let Some(&ordered) = self.lists.last() {
+            let list = match self.task {
+                Some(true) => "checked",
+                Some(false) => "unchecked",
+                None if ordered => "ordered",
+                None => "bullet",
+            };
+            attrs.insert("list".into(), Any::String(list.into()));
+            if self.lists.len() > 1 {
+                attrs.insert("indent".into(), Any::Number((self.lists.len() - 1) as f64));
+            }
+        }
+        if self.blockquote > 0 {
+            attrs.insert("blockquote".into(), Any::Bool(true));
+        }
+        attrs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::text::Diff;
+    use crate::types::{Attrs, Value};
+    use crate::{Doc, OffsetKind, Options};
+    use lib0::any::Any;
+    use std::collections::HashMap;
+
+    fn attrs(json: &str) -> Option<Box<Attrs>> {
+        match Any::from_json(json).unwrap() {
+            Any::Map(map) => Some(Box::new(
+                map.into_iter().map(|(k, v)| (k.into(), v)).collect(),
+            )),
+            other => panic!("expected object, got {}", other),
+        }
+    }
+
+    fn text(s: &str, attrs: Option<Box<Attrs>>) -> Diff {
+        Diff::Insert(Value::Any(Any::String(s.into())), attrs)
+    }
+
+    #[test]
+    fn insert_markdown() {
+        let markdown = "# Notes\n\nSome **bold _and italic_** text with `code`,\n[a link](https://yjs.dev) and ~~strike~~.\n\n- one\n- two\n  1. nested\n- [x] done\n\n> quote\n\n```\nfn main() {}\n```\n\n![alt](a.png)\n";
+
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let txt = txn.get_text("text");
+        txt.insert(&mut txn, 0, "[]");
+        let len = txt.insert_markdown(&mut txn, 1, markdown);
+        assert_eq!(len, txt.len() - 2);
+
+        let mut image = HashMap::new();
+        image.insert("image".to_string(), Any::String("a.png".into()));
+        let expected = vec![
+            text("[Notes", None),
+            text("\n", attrs(r#"{"header":1}"#)),
+            text("Some ", None),
+            text("bold ", attrs(r#"{"bold":true}"#)),
+            text("and italic", attrs(r#"{"bold":true,"italic":true}"#)),
+            text(" text with ", None),
+            text("code", attrs(r#"{"code":true}"#)),
+            text(", ", None),
+            text("a link", attrs(r#"{"link":"https://yjs.dev"}"#)),
+            text(" and ", None),
+            text("strike", attrs(r#"{"strike":true}"#)),
+            text(".\none", None),
+            text("\n", attrs(r#"{"list":"bullet"}"#)),
+            text("two", None),
+            text("\n", attrs(r#"{"list":"bullet"}"#)),
+            text("nested", None),
+            text("\n", attrs(r#"{"list":"ordered","indent":1}"#)),
+            text("done", None),
+            text("\n", attrs(r#"{"list":"checked"}"#)),
+            text("quote", None),
+            text("\n", attrs(r#"{"blockquote":true}"#)),
+            text("fn main() {}", None),
+            text("\n", attrs(r#"{"code-block":true}"#)),
+            Diff::Insert(Value::Any(Any::Map(Box::new(image))), None),
+            text("\n]", None),
+        ];
+        assert_eq!(txt.diff(&mut txn), expected);
+    }
+
+    #[test]
+    fn insert_markdown_graphemes() {
+        let doc = Doc::with_options(Options {
+            offset_kind: OffsetKind::Grapheme,
+            ..Options::default()
+        });
+        let mut txn = doc.transact();
+        let txt = txn.get_text("text");
+        txt.insert(&mut txn, 0, "🐱🐶");
+        // a combining accent continues a grapheme cluster started in a previous chunk
+        let len = txt.insert_markdown(&mut txn, 1, "e**\u{301}**x");
+        assert_eq!(len, 3);
+        assert_eq!(txt.len(), 5);
+        assert_eq!(txt.to_string(), "🐱e\u{301}x\n🐶");
+    }
+}
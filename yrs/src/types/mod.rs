@@ -1,7 +1,18 @@
 pub mod array;
+pub mod counter;
 pub mod map;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+pub mod prosemirror;
 pub mod text;
+pub mod text_render;
+pub mod text_search;
+pub mod tree;
+pub mod weak;
 pub mod xml;
+pub mod xml_diff;
+pub mod xml_parser;
+pub mod xml_selector;
 
 use crate::*;
 pub use map::Map;
@@ -9,11 +20,20 @@ pub use text::Text;
 
 use crate::block::{Block, BlockPtr, Item, ItemContent, ItemPosition, Prelim};
 use crate::event::EventHandler;
+use crate::grapheme_index::GraphemeIndex;
+use crate::line_index::LineIndex;
+use crate::search_marker::{update_markers, SearchMarkers};
 use crate::store::StoreRef;
 use crate::types::array::{Array, ArrayEvent};
+use crate::types::counter::{Counter, CounterEvent};
 use crate::types::map::MapEvent;
 use crate::types::text::TextEvent;
-use crate::types::xml::{XmlElement, XmlEvent, XmlText, XmlTextEvent};
+use crate::types::tree::{Layout, Tree, TreeEvent};
+use crate::types::weak::{LinkSource, WeakEvent, WeakLink};
+use crate::types::xml::{
+    Xml, XmlElement, XmlEvent, XmlFragment, XmlFragmentEvent, XmlHook, XmlHookEvent, XmlText,
+    XmlTextEvent,
+};
 use lib0::any::Any;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Formatter;
@@ -44,6 +64,15 @@ pub const TYPE_REFS_XML_HOOK: TypeRefs = 5;
 /// Type ref identifier for a [XmlText] type.
 pub const TYPE_REFS_XML_TEXT: TypeRefs = 6;
 
+/// Type ref identifier for a [WeakLink] type.
+pub const TYPE_REFS_WEAK: TypeRefs = 7;
+
+/// Type ref identifier for a [Counter] type.
+pub const TYPE_REFS_COUNTER: TypeRefs = 8;
+
+/// Type ref identifier for a [Tree] type.
+pub const TYPE_REFS_TREE: TypeRefs = 9;
+
 /// Placeholder type ref identifier for non-specialized AbstractType. Used only for root-level types
 /// which have been integrated from remote peers before they were defined locally.
 pub const TYPE_REFS_UNDEFINED: TypeRefs = 15;
@@ -69,6 +98,11 @@ impl BranchPtr {
                 TYPE_REFS_ARRAY => Some(Event::Array(ArrayEvent::new(*self))),
                 TYPE_REFS_XML_TEXT => Some(Event::XmlText(XmlTextEvent::new(*self, subs))),
                 TYPE_REFS_XML_ELEMENT => Some(Event::XmlElement(XmlEvent::new(*self, subs))),
+                TYPE_REFS_XML_FRAGMENT => Some(Event::XmlFragment(XmlFragmentEvent::new(*self))),
+                TYPE_REFS_XML_HOOK => Some(Event::XmlHook(XmlHookEvent::new(*self, subs))),
+                TYPE_REFS_WEAK => Some(Event::Weak(WeakEvent::new(*self))),
+                TYPE_REFS_COUNTER => Some(Event::Counter(CounterEvent::new(*self, subs))),
+                TYPE_REFS_TREE => Some(Event::Tree(TreeEvent::new(*self, subs))),
                 _ => None,
             }
         }
@@ -137,9 +171,12 @@ impl Into<Value> for BranchPtr {
             TYPE_REFS_MAP => Value::YMap(Map::from(self)),
             TYPE_REFS_TEXT => Value::YText(Text::from(self)),
             TYPE_REFS_XML_ELEMENT => Value::YXmlElement(XmlElement::from(self)),
-            TYPE_REFS_XML_FRAGMENT => Value::YXmlElement(XmlElement::from(self)),
+            TYPE_REFS_XML_FRAGMENT => Value::YXmlFragment(XmlFragment::from(self)),
             TYPE_REFS_XML_TEXT => Value::YXmlText(XmlText::from(self)),
-            //TYPE_REFS_XML_HOOK => Value::YXmlElement(XmlElement::from(self)),
+            TYPE_REFS_WEAK => Value::YWeakLink(WeakLink::from(self)),
+            TYPE_REFS_COUNTER => Value::YCounter(Counter::from(self)),
+            TYPE_REFS_TREE => Value::YTree(Tree::from(self)),
+            TYPE_REFS_XML_HOOK => Value::YXmlHook(XmlHook::from(self)),
             other => panic!("Cannot convert to value - unsupported type ref: {}", other),
         }
     }
@@ -207,6 +244,9 @@ pub struct Branch {
     /// A tag name identifier, used only by [XmlElement].
     pub name: Option<Rc<str>>,
 
+    /// A source of the content referenced by this branch node, used only by [WeakLink].
+    pub(crate) link_source: Option<Box<LinkSource>>,
+
     /// A length of an indexed sequence component of a current branch node. Map component elements
     /// are computed on demand.
     pub block_len: u32,
@@ -219,6 +259,20 @@ pub struct Branch {
     pub(crate) observers: Option<Observers>,
 
     pub(crate) deep_observers: Option<EventHandler<Events>>,
+
+    /// Cached positions of blocks within an indexed sequence component of a current branch node,
+    /// used to speed up lookups by index.
+    pub(crate) search_markers: SearchMarkers,
+
+    /// Cached offsets of line starts, used only by [Text] and [XmlText].
+    pub(crate) line_index: LineIndex,
+
+    /// Cached grapheme clusters, used only by [Text] and [XmlText] of documents using
+    /// [OffsetKind::Grapheme](crate::OffsetKind::Grapheme).
+    pub(crate) grapheme_index: GraphemeIndex,
+
+    /// Cached layout of tree nodes, used only by [Tree].
+    pub(crate) tree_layout: Option<Rc<Layout>>,
 }
 
 impl std::fmt::Debug for Branch {
@@ -250,9 +304,14 @@ impl Branch {
             item: None,
             store: None,
             name,
+            link_source: None,
             type_ref,
             observers: None,
             deep_observers: None,
+            search_markers: SearchMarkers::default(),
+            line_index: LineIndex::default(),
+            grapheme_index: GraphemeIndex::default(),
+            tree_layout: None,
         })
     }
 
@@ -289,12 +348,6 @@ impl Branch {
         Entries::new(&self.map)
     }
 
-    /// Get iterator over Block entries of an array component of a current root type.
-    /// Deleted blocks are skipped by this iterator.
-    pub(crate) fn iter(&self) -> Iter {
-        Iter::new(self.start.as_ref())
-    }
-
     /// Returns a materialized value of non-deleted entry under a given `key` of a map component
     /// of a current root type.
     pub(crate) fn get(&self, key: &str) -> Option<Value> {
@@ -305,28 +358,6 @@ impl Branch {
         }
     }
 
-    /// Given an `index` parameter, returns an item content reference which contains that index
-    /// together with an offset inside of this content, which points precisely to an `index`
-    /// location within wrapping item content.
-    /// If `index` was outside of the array component boundary of current branch node, `None` will
-    /// be returned.
-    pub(crate) fn get_at(&self, mut index: u32) -> Option<(&ItemContent, usize)> {
-        let mut ptr = self.start.as_ref();
-        while let Some(Block::Item(item)) = ptr.map(BlockPtr::deref) {
-            let len = item.len();
-            if !item.is_deleted() && item.is_countable() {
-                if index < len {
-                    return Some((&item.content, index as usize));
-                }
-
-                index -= len;
-            }
-            ptr = item.right.as_ref();
-        }
-
-        None
-    }
-
     /// Removes an entry under given `key` of a map component of a current root type, returning
     /// a materialized representation of value stored underneath if entry existed prior deletion.
     pub(crate) fn remove(&self, txn: &mut Transaction, key: &str) -> Option<Value> {
@@ -339,21 +370,8 @@ impl Branch {
         prev
     }
 
-    /// Returns a first non-deleted item from an array component of a current root type.
-    pub(crate) fn first(&self) -> Option<&Item> {
-        let mut ptr = self.start.as_ref();
-        while let Some(Block::Item(item)) = ptr.map(BlockPtr::deref) {
-            if item.is_deleted() {
-                ptr = item.right.as_ref();
-            } else {
-                return Some(item);
-            }
-        }
-
-        None
-    }
-
-    /// Given an `index` and start block `ptr`, returns a pair of block pointers.
+    /// Given an `index` within a `branch` sequence, returns a pair of block pointers. Lookup
+    /// starts from the closest search marker of a `branch`.
     ///
     /// If `index` happens to point inside of an existing block content, such block will be split at
     /// position of an `index`. In such case left tuple value contains end of a block pointer on
@@ -366,13 +384,22 @@ impl Branch {
     /// values will be `None`.
     fn index_to_ptr(
         txn: &mut Transaction,
-        mut ptr: Option<BlockPtr>,
-        mut index: u32,
+        mut branch: BranchPtr,
+        index: u32,
     ) -> (Option<BlockPtr>, Option<BlockPtr>) {
         let encoding = txn.store.options.offset_kind;
+        let (mut ptr, mut offset) = match branch.search_markers.find(index, false) {
+            Some(marker) => (Some(marker.ptr), marker.index),
+            None => (branch.start, 0),
+        };
         while let Some(Block::Item(item)) = ptr.as_deref() {
             let content_len = item.content_len(encoding);
             if !item.is_deleted() && item.is_countable() {
+                let index = index - offset;
+                if index <= content_len {
+                    let len = branch.content_len;
+                    branch.search_markers.mark(ptr.unwrap(), offset, None, len);
+                }
                 if index == content_len {
                     let left = ptr;
                     let right = item.right.clone();
@@ -396,7 +423,7 @@ impl Branch {
                     }
                     return (ptr, right);
                 }
-                index -= content_len;
+                offset += content_len;
             }
             ptr = item.right.clone();
         }
@@ -405,13 +432,18 @@ impl Branch {
     /// Removes up to a `len` of countable elements from current branch sequence, starting at the
     /// given `index`. Returns number of removed elements.
     pub(crate) fn remove_at(&self, txn: &mut Transaction, index: u32, len: u32) -> u32 {
-        let mut remaining = len;
-        let start = { self.start };
-        let (_, mut ptr) = if index == 0 {
-            (None, start)
+        let (_, ptr) = if index == 0 {
+            (None, self.start)
         } else {
-            Branch::index_to_ptr(txn, start, index)
+            Branch::index_to_ptr(txn, BranchPtr::from(self), index)
         };
+        update_markers(BranchPtr::from(self), index, || {
+            Self::remove_from(txn, ptr, len)
+        })
+    }
+
+    fn remove_from(txn: &mut Transaction, mut ptr: Option<BlockPtr>, len: u32) -> u32 {
+        let mut remaining = len;
         while remaining > 0 {
             if let Some(p) = ptr {
                 let encoding = txn.store().options.offset_kind;
@@ -462,9 +494,9 @@ impl Branch {
         index: u32,
         value: V,
     ) -> BlockPtr {
-        let (start, parent) = {
+        let parent = {
             if index <= self.len() {
-                (self.start, BranchPtr::from(self))
+                BranchPtr::from(self)
             } else {
                 panic!("Cannot insert item at index over the length of an array")
             }
@@ -472,7 +504,7 @@ impl Branch {
         let (left, right) = if index == 0 {
             (None, None)
         } else {
-            Branch::index_to_ptr(txn, start, index)
+            Branch::index_to_ptr(txn, parent, index)
         };
         let pos = ItemPosition {
             parent: parent.into(),
@@ -482,7 +514,7 @@ impl Branch {
             current_attrs: None,
         };
 
-        txn.create_item(&pos, value, None)
+        update_markers(parent, index, || txn.create_item(&pos, value, None))
     }
 
     pub(crate) fn path(from: BranchPtr, to: BranchPtr) -> Path {
@@ -553,6 +585,32 @@ pub trait DeepObservable {
     where
         F: Fn(&Transaction, &Events) -> () + 'static;
 
+    /// Subscribe a callback `f` for events emitted by this and nested collaborative types, which
+    /// could have changed a value found under a given `path` or any of its nested values.
+    /// The `path` is relative to a current type. See [Event::affects_path] for details.
+    ///
+    /// Callback receives only matching events and it's not called at all when no event within a
+    /// committed transaction has matched.
+    ///
+    /// This method returns a subscription, which will automatically unsubscribe current callback
+    /// when dropped.
+    fn observe_path<F>(&mut self, path: Path, f: F) -> Subscription<Events>
+    where
+        F: Fn(&Transaction, &Events) + 'static,
+    {
+        self.observe_deep(move |txn, events| {
+            let matching: Vec<_> = events
+                .0
+                .iter()
+                .filter(|e| unsafe { e.as_ref() }.affects_path(txn, &path))
+                .cloned()
+                .collect();
+            if !matching.is_empty() {
+                f(txn, &Events(matching))
+            }
+        })
+    }
+
     /// Unobserves callback identified by `subscription_id` (which can be obtained by consuming
     /// [Subscription] using `into` cast).
     fn unobserve_deep(&mut self, subscription_id: SubscriptionId);
@@ -584,7 +642,12 @@ pub enum Value {
     YArray(Array),
     YMap(Map),
     YXmlElement(XmlElement),
+    YXmlFragment(XmlFragment),
     YXmlText(XmlText),
+    YXmlHook(XmlHook),
+    YWeakLink(WeakLink),
+    YCounter(Counter),
+    YTree(Tree),
 }
 
 impl Default for Value {
@@ -599,9 +662,13 @@ impl Value {
     ///
     /// - Primitive types ([Value::Any]) are passed right away, as no transformation is needed.
     /// - [Value::YArray] is converted into JSON-like array.
-    /// - [Value::YMap] is converted into JSON-like object map.
-    /// - [Value::YText], [Value::YXmlText] and [Value::YXmlElement] are converted into strings
-    ///   (XML types are stringified XML representation).
+    /// - [Value::YMap] and [Value::YXmlHook] are converted into JSON-like object map.
+    /// - [Value::YText], [Value::YXmlText], [Value::YXmlElement] and [Value::YXmlFragment] are
+    ///   converted into strings (XML types are stringified XML representation).
+    /// - [Value::YWeakLink] is converted into its linked value for map entries, a string for quoted
+    ///   text ranges and JSON-like array for other quoted ranges.
+    /// - [Value::YCounter] is converted into a number.
+    /// - [Value::YTree] is converted into JSON-like array of its root level nodes.
     pub fn to_json(self) -> Any {
         match self {
             Value::Any(a) => a,
@@ -609,7 +676,12 @@ impl Value {
             Value::YArray(v) => v.to_json(),
             Value::YMap(v) => v.to_json(),
             Value::YXmlElement(v) => Any::String(v.to_string().into_boxed_str()),
+            Value::YXmlFragment(v) => Any::String(v.to_string().into_boxed_str()),
             Value::YXmlText(v) => Any::String(v.to_string().into_boxed_str()),
+            Value::YXmlHook(v) => v.to_json(),
+            Value::YWeakLink(v) => v.to_json(),
+            Value::YCounter(v) => v.to_json(),
+            Value::YTree(v) => v.to_json(),
         }
     }
 
@@ -621,7 +693,12 @@ impl Value {
             Value::YArray(v) => v.to_json().to_string(),
             Value::YMap(v) => v.to_json().to_string(),
             Value::YXmlElement(v) => v.to_string(),
+            Value::YXmlFragment(v) => v.to_string(),
             Value::YXmlText(v) => v.to_string(),
+            Value::YXmlHook(v) => v.to_json().to_string(),
+            Value::YWeakLink(v) => v.to_json().to_string(),
+            Value::YCounter(v) => v.get().to_string(),
+            Value::YTree(v) => v.to_json().to_string(),
         }
     }
 
@@ -657,6 +734,14 @@ impl Value {
         }
     }
 
+    pub fn to_yxml_fragment(self) -> Option<XmlFragment> {
+        if let Value::YXmlFragment(xml) = self {
+            Some(xml)
+        } else {
+            None
+        }
+    }
+
     pub fn to_yxml_text(self) -> Option<XmlText> {
         if let Value::YXmlText(xml) = self {
             Some(xml)
@@ -664,6 +749,76 @@ impl Value {
             None
         }
     }
+
+    pub fn to_yxml_hook(self) -> Option<XmlHook> {
+        if let Value::YXmlHook(xml) = self {
+            Some(xml)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_yweak(self) -> Option<WeakLink> {
+        if let Value::YWeakLink(link) = self {
+            Some(link)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_ycounter(self) -> Option<Counter> {
+        if let Value::YCounter(counter) = self {
+            Some(counter)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_ytree(self) -> Option<Tree> {
+        if let Value::YTree(tree) = self {
+            Some(tree)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a value nested under a given path `segment` of a current one. Keys are used to
+    /// access [Map] entries, XML attributes and JSON-like objects, while indexes are used to
+    /// access elements of [Array]s, XML children and JSON-like arrays.
+    pub(crate) fn get_child(&self, segment: &PathSegment) -> Option<Value> {
+        match (self, segment) {
+            (Value::YMap(map), PathSegment::Key(key)) => map.get(key),
+            (Value::YMap(map), PathSegment::Index(index)) => map.get(&index.to_string()),
+            (Value::YArray(array), PathSegment::Index(index)) => array.get(*index),
+            (Value::YXmlElement(elem), PathSegment::Index(index)) => match elem.get(*index)? {
+                Xml::Element(elem) => Some(Value::YXmlElement(elem)),
+                Xml::Text(text) => Some(Value::YXmlText(text)),
+                Xml::Hook(hook) => Some(Value::YXmlHook(hook)),
+            },
+            (Value::YXmlElement(elem), PathSegment::Key(key)) => {
+                elem.get_attribute(key).map(Value::from)
+            }
+            (Value::YXmlFragment(xml), PathSegment::Index(index)) => match xml.get(*index)? {
+                Xml::Element(elem) => Some(Value::YXmlElement(elem)),
+                Xml::Text(text) => Some(Value::YXmlText(text)),
+                Xml::Hook(hook) => Some(Value::YXmlHook(hook)),
+            },
+            (Value::YXmlText(text), PathSegment::Key(key)) => {
+                text.get_attribute(key).map(Value::from)
+            }
+            (Value::YXmlHook(hook), PathSegment::Key(key)) => hook.get(key),
+            (Value::Any(Any::Map(map)), PathSegment::Key(key)) => {
+                map.get(key.as_ref()).cloned().map(Value::Any)
+            }
+            (Value::Any(Any::Map(map)), PathSegment::Index(index)) => {
+                map.get(&index.to_string()).cloned().map(Value::Any)
+            }
+            (Value::Any(Any::Array(array)), PathSegment::Index(index)) => {
+                array.get(*index as usize).cloned().map(Value::Any)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl<T> From<T> for Value
@@ -676,6 +831,110 @@ where
     }
 }
 
+/// A preliminary value used to import JSON-like [Any] data into shared collections. Unlike
+/// [Any] used directly as [Prelim], which is stored as a single opaque primitive, [PrelimJson]
+/// recursively converts JSON objects into nested [Map]s and JSON arrays into nested [Array]s.
+/// This way concurrent updates made to different entries of imported data don't conflict with
+/// each other.
+///
+/// Optionally, JSON strings can also be converted into [Text] - see
+/// [PrelimJson::strings_as_text].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrelimJson {
+    value: Any,
+    strings_as_text: bool,
+}
+
+impl PrelimJson {
+    /// Creates a new preliminary value out of a JSON-like `value`. JSON strings are stored as
+    /// primitive values, unless [PrelimJson::strings_as_text] is used.
+    pub fn new(value: Any) -> Self {
+        PrelimJson {
+            value,
+            strings_as_text: false,
+        }
+    }
+
+    /// Makes all JSON strings (including nested ones) to be converted into [Text] instead of
+    /// being stored as primitive string values.
+    pub fn strings_as_text(mut self) -> Self {
+        self.strings_as_text = true;
+        self
+    }
+
+    /// Returns a type ref of a shared type, which a given `value` will be converted into, or
+    /// `None` if it's going to be stored as a primitive value.
+    fn type_ref(value: &Any, strings_as_text: bool) -> Option<TypeRefs> {
+        match value {
+            Any::Map(_) => Some(TYPE_REFS_MAP),
+            Any::Array(_) => Some(TYPE_REFS_ARRAY),
+            Any::String(_) if strings_as_text => Some(TYPE_REFS_TEXT),
+            _ => None,
+        }
+    }
+}
+
+impl From<Any> for PrelimJson {
+    fn from(value: Any) -> Self {
+        PrelimJson::new(value)
+    }
+}
+
+impl Prelim for PrelimJson {
+    fn into_content(self, _txn: &mut Transaction) -> (ItemContent, Option<Self>) {
+        match PrelimJson::type_ref(&self.value, self.strings_as_text) {
+            Some(type_ref) => {
+                let inner = Branch::new(type_ref, None);
+                (ItemContent::Type(inner), Some(self))
+            }
+            None => (ItemContent::Any(vec![self.value]), None),
+        }
+    }
+
+    fn integrate(self, txn: &mut Transaction, inner_ref: BranchPtr) {
+        let strings_as_text = self.strings_as_text;
+        let nested = |value| PrelimJson {
+            value,
+            strings_as_text,
+        };
+        match self.value {
+            Any::Map(entries) => {
+                let map = Map::from(inner_ref);
+                for (key, value) in entries.into_iter() {
+                    map.insert(txn, key, nested(value));
+                }
+            }
+            Any::Array(values) => {
+                // consecutive primitive values are inserted together as a single block
+                let array = Array::from(inner_ref);
+                let mut index = 0;
+                let mut primitives = Vec::new();
+                for value in values.into_vec() {
+                    if PrelimJson::type_ref(&value, strings_as_text).is_none() {
+                        primitives.push(value);
+                        continue;
+                    }
+                    if !primitives.is_empty() {
+                        let len = primitives.len() as u32;
+                        array.insert_range(txn, index, std::mem::take(&mut primitives));
+                        index += len;
+                    }
+                    array.insert(txn, index, nested(value));
+                    index += 1;
+                }
+                if !primitives.is_empty() {
+                    array.insert_range(txn, index, primitives);
+                }
+            }
+            Any::String(str) => {
+                let text = Text::from(inner_ref);
+                text.push(txn, &str);
+            }
+            _ => {}
+        }
+    }
+}
+
 impl std::fmt::Display for Branch {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.type_ref() {
@@ -722,6 +981,13 @@ impl std::fmt::Display for Branch {
                 }
                 Ok(())
             }
+            TYPE_REFS_XML_FRAGMENT => {
+                if let Some(start) = self.start.as_ref() {
+                    write!(f, "YXmlFragment(start: {})", start)
+                } else {
+                    write!(f, "YXmlFragment")
+                }
+            }
             TYPE_REFS_XML_HOOK => {
                 write!(f, "YXmlHook(")?;
                 let mut iter = self.map.iter();
@@ -740,6 +1006,12 @@ impl std::fmt::Display for Branch {
                     write!(f, "YXmlText")
                 }
             }
+            TYPE_REFS_WEAK => match self.link_source.as_ref() {
+                Some(source) => write!(f, "YWeakLink({})", source),
+                None => write!(f, "YWeakLink"),
+            },
+            TYPE_REFS_COUNTER => write!(f, "YCounter"),
+            TYPE_REFS_TREE => write!(f, "YTree"),
             _ => {
                 write!(f, "UnknownRef")?;
                 if let Some(start) = self.start.as_ref() {
@@ -797,27 +1069,6 @@ impl<'a> Iterator for Entries<'a> {
     }
 }
 
-pub(crate) struct Iter<'a> {
-    ptr: Option<&'a BlockPtr>,
-}
-
-impl<'a> Iter<'a> {
-    fn new(ptr: Option<&'a BlockPtr>) -> Self {
-        Iter { ptr }
-    }
-}
-
-impl<'a> Iterator for Iter<'a> {
-    type Item = &'a Item;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let ptr = self.ptr.take()?;
-        let item = ptr.as_item()?;
-        self.ptr = item.right.as_ref();
-        Some(item)
-    }
-}
-
 /// Type pointer - used to localize a complex [Branch] node within a scope of a document store.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) enum TypePtr {
@@ -868,7 +1119,12 @@ pub(crate) enum Observers {
     Array(EventHandler<crate::types::array::ArrayEvent>),
     Map(EventHandler<crate::types::map::MapEvent>),
     Xml(EventHandler<crate::types::xml::XmlEvent>),
+    XmlFragment(EventHandler<crate::types::xml::XmlFragmentEvent>),
     XmlText(EventHandler<crate::types::xml::XmlTextEvent>),
+    XmlHook(EventHandler<crate::types::xml::XmlHookEvent>),
+    Weak(EventHandler<crate::types::weak::WeakEvent>),
+    Counter(EventHandler<crate::types::counter::CounterEvent>),
+    Tree(EventHandler<crate::types::tree::TreeEvent>),
 }
 
 impl Observers {
@@ -884,9 +1140,24 @@ impl Observers {
     pub fn xml() -> Self {
         Observers::Xml(EventHandler::default())
     }
+    pub fn xml_fragment() -> Self {
+        Observers::XmlFragment(EventHandler::default())
+    }
     pub fn xml_text() -> Self {
         Observers::XmlText(EventHandler::default())
     }
+    pub fn xml_hook() -> Self {
+        Observers::XmlHook(EventHandler::default())
+    }
+    pub fn weak() -> Self {
+        Observers::Weak(EventHandler::default())
+    }
+    pub fn counter() -> Self {
+        Observers::Counter(EventHandler::default())
+    }
+    pub fn tree() -> Self {
+        Observers::Tree(EventHandler::default())
+    }
 
     pub fn publish(
         &self,
@@ -915,11 +1186,36 @@ impl Observers {
                 eh.publish(txn, &e);
                 Event::XmlElement(e)
             }
+            Observers::XmlFragment(eh) => {
+                let e = XmlFragmentEvent::new(branch_ref);
+                eh.publish(txn, &e);
+                Event::XmlFragment(e)
+            }
             Observers::XmlText(eh) => {
                 let e = XmlTextEvent::new(branch_ref, keys);
                 eh.publish(txn, &e);
                 Event::XmlText(e)
             }
+            Observers::XmlHook(eh) => {
+                let e = XmlHookEvent::new(branch_ref, keys);
+                eh.publish(txn, &e);
+                Event::XmlHook(e)
+            }
+            Observers::Weak(eh) => {
+                let e = WeakEvent::new(branch_ref);
+                eh.publish(txn, &e);
+                Event::Weak(e)
+            }
+            Observers::Counter(eh) => {
+                let e = CounterEvent::new(branch_ref, keys);
+                eh.publish(txn, &e);
+                Event::Counter(e)
+            }
+            Observers::Tree(eh) => {
+                let e = TreeEvent::new(branch_ref, keys);
+                eh.publish(txn, &e);
+                Event::Tree(e)
+            }
         }
     }
 }
@@ -941,18 +1237,91 @@ pub enum PathSegment {
     Index(u32),
 }
 
+impl std::fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathSegment::Key(key) => write!(f, "{}", key.replace('~', "~0").replace('/', "~1")),
+            PathSegment::Index(index) => write!(f, "{}", index),
+        }
+    }
+}
+
+/// Parses a string representation of a [Path], eg. `"todos/3/title"`. Path segments are
+/// separated with `/` character. Segments which can be parsed as unsigned integers are treated
+/// as [PathSegment::Index], all other segments are [PathSegment::Key]s. Numeric segments used
+/// against [Map] are also interpreted as keys, so that `"users/1"` can be used to access an entry
+/// stored under key `"1"`.
+///
+/// Similarly to JSON pointers, `~1` and `~0` escape sequences can be used to represent `/` and `~`
+/// characters within keys. Leading `/` is optional.
+pub fn parse_path(path: &str) -> Path {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    if path.is_empty() {
+        return Path::new();
+    }
+    path.split('/')
+        .map(|segment| match segment.parse::<u32>() {
+            Ok(index) => PathSegment::Index(index),
+            Err(_) => {
+                let key = segment.replace("~1", "/").replace("~0", "~");
+                PathSegment::Key(key.into())
+            }
+        })
+        .collect()
+}
+
+/// Formats a given `path` into its string representation, reversible with [parse_path].
+pub fn format_path(path: &Path) -> String {
+    let segments: Vec<String> = path.iter().map(PathSegment::to_string).collect();
+    segments.join("/")
+}
+
+/// Error returned by path-based operations, such as [Transaction::set_by_path].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathError {
+    /// Path was empty or it didn't start with a name of a root-level type. Root-level types
+    /// themselves cannot be replaced or removed.
+    InvalidRoot,
+    /// No value was found under a given path segment.
+    NotFound(PathSegment),
+    /// Last path segment cannot be used to modify a value found under the preceding segments,
+    /// eg. a value was about to be set inside of a [Text] or a JSON-like primitive.
+    InvalidSegment(PathSegment),
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::InvalidRoot => write!(f, "path must start with a root type name"),
+            PathError::NotFound(segment) => write!(f, "no value found under `{}`", segment),
+            PathError::InvalidSegment(segment) => {
+                write!(f, "path segment `{}` cannot be applied", segment)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
 pub(crate) struct ChangeSet<D> {
     added: HashSet<ID>,
     deleted: HashSet<ID>,
     delta: Vec<D>,
+    moves: Vec<MoveChange>,
 }
 
 impl<D> ChangeSet<D> {
-    pub fn new(added: HashSet<ID>, deleted: HashSet<ID>, delta: Vec<D>) -> Self {
+    pub fn new(
+        added: HashSet<ID>,
+        deleted: HashSet<ID>,
+        delta: Vec<D>,
+        moves: Vec<MoveChange>,
+    ) -> Self {
         ChangeSet {
             added,
             deleted,
             delta,
+            moves,
         }
     }
 }
@@ -974,6 +1343,21 @@ pub enum Change {
     Retain(u32),
 }
 
+/// A consecutive range of elements relocated within the same sequence by a move operation.
+/// Moved elements are still reported in a delta as a pair of [Change::Removed] and
+/// [Change::Added] chunks, which lets observers tell a move apart from a copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveChange {
+    /// Index of the first moved element before the transaction, counted over [Change::Retain]
+    /// and [Change::Removed] chunks of the delta.
+    pub from: u32,
+    /// Index of the first moved element after the transaction, counted over [Change::Retain]
+    /// and [Change::Added] chunks of the delta.
+    pub to: u32,
+    /// Number of consecutive elements moved.
+    pub len: u32,
+}
+
 /// A single change done over a map-component of shared data type.
 #[derive(Debug, Clone, PartialEq)]
 pub enum EntryChange {
@@ -1072,6 +1456,12 @@ pub(crate) fn event_change_set(txn: &Transaction, start: Option<BlockPtr>) -> Ch
     let mut curr_move_end = None;
     let mut last_op = None;
 
+    // positions of elements moved within this transaction, used to pair removals with insertions
+    let mut old_index = 0;
+    let mut new_index = 0;
+    let mut moved_from = HashMap::new();
+    let mut moved_to = Vec::new();
+
     #[derive(Default)]
     struct MoveStackItem {
         end: Option<BlockPtr>,
@@ -1093,7 +1483,6 @@ pub(crate) fn event_change_set(txn: &Transaction, start: Option<BlockPtr>) -> Ch
         false
     }
 
-    let encoding = txn.store().options.offset_kind;
     let mut current = start;
     loop {
         if current == curr_move_end && curr_move.is_some() {
@@ -1141,7 +1530,12 @@ pub(crate) fn event_change_set(txn: &Transaction, start: Option<BlockPtr>) -> Ch
                         {
                             match item.moved {
                                 Some(ptr) if txn.has_added(ptr.id()) => {
-                                    let len = item.content_len(encoding);
+                                    let len = item.len();
+                                    for i in 0..len {
+                                        let id = ID::new(item.id.client, item.id.clock + i);
+                                        moved_from.insert(id, old_index + i);
+                                    }
+                                    old_index += len;
                                     last_op = match last_op.take() {
                                         Some(Change::Removed(i)) => Some(Change::Removed(i + len)),
                                         Some(op) => {
@@ -1169,6 +1563,7 @@ pub(crate) fn event_change_set(txn: &Transaction, start: Option<BlockPtr>) -> Ch
                                 }
                             };
                             last_op = Some(Change::Removed(removed + item.len()));
+                            old_index += item.len();
                             deleted.insert(item.id);
                         } // else nop
                     } else {
@@ -1186,6 +1581,13 @@ pub(crate) fn event_change_set(txn: &Transaction, start: Option<BlockPtr>) -> Ch
                             };
                             inserts.append(&mut item.content.get_content());
                             last_op = Some(Change::Added(inserts));
+                            if !txn.has_added(&item.id) {
+                                for i in 0..item.len() {
+                                    let id = ID::new(item.id.client, item.id.clock + i);
+                                    moved_to.push((id, new_index + i));
+                                }
+                            }
+                            new_index += item.len();
                             added.insert(item.id);
                         } else {
                             let retain = match last_op.take() {
@@ -1197,6 +1599,8 @@ pub(crate) fn event_change_set(txn: &Transaction, start: Option<BlockPtr>) -> Ch
                                 }
                             };
                             last_op = Some(Change::Retain(retain + item.len()));
+                            old_index += item.len();
+                            new_index += item.len();
                         }
                     }
                 } else {
@@ -1219,7 +1623,37 @@ pub(crate) fn event_change_set(txn: &Transaction, start: Option<BlockPtr>) -> Ch
         Some(change) => delta.push(change),
     }
 
-    ChangeSet::new(added, deleted, delta)
+    let mut moves: Vec<MoveChange> = Vec::new();
+    for (id, to) in moved_to {
+        if let Some(&from) = moved_from.get(&id) {
+            match moves.last_mut() {
+                Some(last) if last.from + last.len == from && last.to + last.len == to => {
+                    last.len += 1
+                }
+                _ => moves.push(MoveChange { from, to, len: 1 }),
+            }
+        }
+    }
+
+    ChangeSet::new(added, deleted, delta, moves)
+}
+
+/// Checks if a given `delta` inserts or removes any elements at position lower or equal to
+/// a given `index`.
+fn shifts_index(delta: &[Change], index: u32) -> bool {
+    let mut pos = 0;
+    for change in delta {
+        match change {
+            Change::Retain(len) => {
+                pos += len;
+                if pos > index {
+                    return false;
+                }
+            }
+            Change::Added(_) | Change::Removed(_) => return true,
+        }
+    }
+    false
 }
 
 pub struct Events(Vec<NonNull<Event>>);
@@ -1264,13 +1698,92 @@ impl<'a> ExactSizeIterator for EventsIter<'a> {
     }
 }
 
+/// Events emitted by all root-level types of a document and their nested types within the scope
+/// of a single transaction. Used by [Doc::observe_all_deep].
+pub struct DocEvents(Vec<(Rc<str>, NonNull<Event>)>);
+
+impl DocEvents {
+    pub(crate) fn new(mut events: Vec<(Rc<str>, &Event)>) -> Self {
+        events.sort_by_key(|(_, e)| e.path().len());
+        let inner = events
+            .into_iter()
+            .map(|(root, e)| {
+                let ptr = unsafe { NonNull::new_unchecked(e as *const Event as *mut Event) };
+                (root, ptr)
+            })
+            .collect();
+        DocEvents(inner)
+    }
+
+    pub fn iter(&self) -> DocEventsIter<'_> {
+        DocEventsIter(self.0.iter())
+    }
+}
+
+pub struct DocEventsIter<'a>(std::slice::Iter<'a, (Rc<str>, NonNull<Event>)>);
+
+impl<'a> Iterator for DocEventsIter<'a> {
+    type Item = DocEvent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (root, e) = self.0.next()?;
+        Some(DocEvent {
+            root,
+            event: unsafe { e.as_ref() },
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for DocEventsIter<'a> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// A single [Event] emitted as part of [DocEvents], together with a name of the root-level type
+/// it originated from.
+pub struct DocEvent<'a> {
+    root: &'a Rc<str>,
+    event: &'a Event,
+}
+
+impl<'a> DocEvent<'a> {
+    /// Returns a name of a root-level type, which contains a type that emitted current event.
+    pub fn root(&self) -> &'a Rc<str> {
+        self.root
+    }
+
+    /// Returns an underlying event. Its [Event::path] is relative to the root-level type.
+    pub fn event(&self) -> &'a Event {
+        self.event
+    }
+
+    /// Returns an absolute path to a type that emitted current event. First segment of that path
+    /// is always a [PathSegment::Key] with a name of the root-level type, which makes it usable
+    /// with methods like [Transaction::get_by_path].
+    pub fn path(&self) -> Path {
+        let mut path = self.event.path();
+        path.push_front(PathSegment::Key(self.root.clone()));
+        path
+    }
+}
+
 /// Generalized wrapper around events fired by specialized shared data types.
 pub enum Event {
     Text(TextEvent),
     Array(ArrayEvent),
     Map(MapEvent),
     XmlElement(XmlEvent),
+    XmlFragment(XmlFragmentEvent),
     XmlText(XmlTextEvent),
+    XmlHook(XmlHookEvent),
+    Weak(WeakEvent),
+    Counter(CounterEvent),
+    Tree(TreeEvent),
 }
 
 impl Event {
@@ -1280,7 +1793,12 @@ impl Event {
             Event::Array(e) => e.current_target = target,
             Event::Map(e) => e.current_target = target,
             Event::XmlElement(e) => e.current_target = target,
+            Event::XmlFragment(e) => e.current_target = target,
             Event::XmlText(e) => e.current_target = target,
+            Event::XmlHook(e) => e.current_target = target,
+            Event::Weak(e) => e.current_target = target,
+            Event::Counter(e) => e.current_target = target,
+            Event::Tree(e) => e.current_target = target,
         }
     }
 
@@ -1292,7 +1810,52 @@ impl Event {
             Event::Array(e) => e.path(),
             Event::Map(e) => e.path(),
             Event::XmlElement(e) => e.path(),
+            Event::XmlFragment(e) => e.path(),
             Event::XmlText(e) => e.path(),
+            Event::XmlHook(e) => e.path(),
+            Event::Weak(e) => e.path(),
+            Event::Counter(e) => e.path(),
+            Event::Tree(e) => e.path(),
+        }
+    }
+
+    /// Checks if current event could have changed a value found under a given `path` (or any of
+    /// its nested values). Both the `path` and [Event::path] are relative to the type on which a
+    /// deep observer was registered. This is the case when:
+    ///
+    /// - Current event was emitted by a type located under a given `path`.
+    /// - Current event was emitted by one of the ancestors of a type under a given `path` and it
+    ///   changed a map entry (or XML attribute) on that path, or it inserted/removed sequence
+    ///   elements before or at the index used by that path, thus shifting it.
+    pub fn affects_path(&self, txn: &Transaction, path: &Path) -> bool {
+        let event_path = self.path();
+        if !event_path.iter().zip(path.iter()).all(|(a, b)| a == b) {
+            false
+        } else if event_path.len() >= path.len() {
+            true
+        } else {
+            let keys = match self {
+                Event::Map(e) => Some(e.keys(txn)),
+                Event::XmlElement(e) => Some(e.keys(txn)),
+                Event::XmlText(e) => Some(e.keys(txn)),
+                Event::XmlHook(e) => Some(e.keys(txn)),
+                _ => None,
+            };
+            let delta = match self {
+                Event::Array(e) => Some(e.delta(txn)),
+                Event::XmlElement(e) => Some(e.delta(txn)),
+                Event::XmlFragment(e) => Some(e.delta(txn)),
+                _ => None,
+            };
+            match &path[event_path.len()] {
+                PathSegment::Key(key) => keys.map(|k| k.contains_key(key)).unwrap_or(false),
+                PathSegment::Index(index) => {
+                    let key_changed = keys
+                        .map(|k| k.contains_key(index.to_string().as_str()))
+                        .unwrap_or(false);
+                    key_changed || delta.map(|d| shifts_index(d, *index)).unwrap_or(false)
+                }
+            }
         }
     }
 
@@ -1303,7 +1866,12 @@ impl Event {
             Event::Array(e) => Value::YArray(e.target().clone()),
             Event::Map(e) => Value::YMap(e.target().clone()),
             Event::XmlElement(e) => Value::YXmlElement(e.target().clone()),
+            Event::XmlFragment(e) => Value::YXmlFragment(e.target().clone()),
             Event::XmlText(e) => Value::YXmlText(e.target().clone()),
+            Event::XmlHook(e) => Value::YXmlHook(e.target().clone()),
+            Event::Weak(e) => Value::YWeakLink(e.target().clone()),
+            Event::Counter(e) => Value::YCounter(e.target().clone()),
+            Event::Tree(e) => Value::YTree(e.target().clone()),
         }
     }
 }
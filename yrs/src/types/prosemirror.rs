@@ -0,0 +1,306 @@
+use crate::types::text::Diff;
+use crate::types::xml::{children, Xml, XmlElement, XmlFragment, XmlText};
+use crate::types::{Attrs, Branch, BranchPtr, Entries, Value};
+use crate::{Text, Transaction};
+use lib0::any::Any;
+use std::collections::HashMap;
+use std::fmt::Formatter;
+use std::rc::Rc;
+
+/// Error returned when a JSON passed to [XmlFragment::insert_prosemirror_json] doesn't follow
+/// the ProseMirror document model.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProseMirrorError {
+    /// Node is not an object with a string `type` field, its `content` is not an array or it's
+    /// a text node without a string `text` field.
+    InvalidNode(Any),
+    /// Mark is not an object with a string `type` field.
+    InvalidMark(Any),
+}
+
+impl std::fmt::Display for ProseMirrorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProseMirrorError::InvalidNode(node) => write!(f, "invalid ProseMirror node: {}", node),
+            ProseMirrorError::InvalidMark(mark) => write!(f, "invalid ProseMirror mark: {}", mark),
+        }
+    }
+}
+
+impl std::error::Error for ProseMirrorError {}
+
+/// A validated ProseMirror node, which is about to be inserted into a Yrs XML collection.
+enum Node {
+    Element {
+        tag: String,
+        attrs: Vec<(String, Any)>,
+        content: Vec<Node>,
+    },
+    Text(String, Attrs),
+}
+
+/// Converts XML nodes of a given fragment into a ProseMirror `doc` node.
+pub(crate) fn fragment_to_json(fragment: &XmlFragment) -> Any {
+    node_json(
+        "doc",
+        Vec::new(),
+        content_to_json(BranchPtr::from(fragment.as_ref())),
+    )
+}
+
+/// Converts a given XML element into a ProseMirror node, using its tag as a node type.
+pub(crate) fn element_to_json(elem: &XmlElement) -> Any {
+    let branch: &Branch = elem.as_ref();
+    let mut attrs = Vec::new();
+    for (key, item) in Entries::new(&branch.map) {
+        if let Some(value) = item.content.get_last() {
+            attrs.push((key.to_string(), value.to_json()));
+        }
+    }
+    node_json(elem.tag(), attrs, content_to_json(BranchPtr::from(branch)))
+}
+
+/// Converts a given XML text into a list of ProseMirror text nodes. Formatting attributes are
+/// mapped onto marks: attribute key is used as a mark type and its value as mark attributes.
+pub(crate) fn text_to_json(text: &XmlText) -> Vec<Any> {
+    let branch = BranchPtr::from(text.as_ref());
+    let mut txn = match branch.try_transact() {
+        Some(txn) => txn,
+        None => return Vec::new(),
+    };
+    let mut nodes = Vec::new();
+    for Diff::Insert(value, attrs) in Text::from(branch).diff(&mut txn) {
+        if let Value::Any(Any::String(s)) = value {
+            let mut node = HashMap::new();
+            node.insert("type".to_string(), Any::String("text".into()));
+            node.insert("text".to_string(), Any::String(s));
+            let marks = attrs
+                .map(|attrs| attrs_to_marks(*attrs))
+                .unwrap_or_default();
+            if !marks.is_empty() {
+                node.insert("marks".to_string(), Any::Array(marks.into_boxed_slice()));
+            }
+            nodes.push(Any::Map(Box::new(node)));
+        }
+    }
+    nodes
+}
+
+fn content_to_json(parent: BranchPtr) -> Vec<Any> {
+    let mut content = Vec::new();
+    for child in children(parent) {
+        match Xml::from(child) {
+            Xml::Element(elem) => content.push(element_to_json(&elem)),
+            Xml::Text(text) => content.extend(text_to_json(&text)),
+            // hooks have no counterpart in ProseMirror document model
+            Xml::Hook(_) => {}
+        }
+    }
+    content
+}
+
+fn node_json(node_type: &str, mut attrs: Vec<(String, Any)>, content: Vec<Any>) -> Any {
+    let mut node = HashMap::new();
+    node.insert("type".to_string(), Any::String(node_type.into()));
+    attrs.retain(|(_, value)| !matches!(value, Any::Null | Any::Undefined));
+    if !attrs.is_empty() {
+        node.insert(
+            "attrs".to_string(),
+            Any::Map(Box::new(attrs.into_iter().collect())),
+        );
+    }
+    if !content.is_empty() {
+        node.insert(
+            "content".to_string(),
+            Any::Array(content.into_boxed_slice()),
+        );
+    }
+    Any::Map(Box::new(node))
+}
+
+fn attrs_to_marks(attrs: Attrs) -> Vec<Any> {
+    let mut attrs: Vec<_> = attrs
+        .into_iter()
+        .filter(|(_, value)| !matches!(value, Any::Null | Any::Undefined))
+        .collect();
+    attrs.sort_by(|(a, _), (b, _)| a.cmp(b));
+    attrs
+        .into_iter()
+        .map(|(key, value)| {
+            // y-prosemirror suffixes keys of overlapping marks of the same type with `--<hash>`
+            let mark_type = key.split("--").next().unwrap_or_default();
+            let mut mark = HashMap::new();
+            mark.insert("type".to_string(), Any::String(mark_type.into()));
+            match value {
+                Any::Map(attrs) if !attrs.is_empty() => {
+                    mark.insert("attrs".to_string(), Any::Map(attrs));
+                }
+                _ => {}
+            }
+            Any::Map(Box::new(mark))
+        })
+        .collect()
+}
+
+/// Inserts child nodes (`content`) of a given ProseMirror `node` into a `parent` XML collection,
+/// starting at a given `index`. Returns a number of inserted XML nodes. Nothing is inserted if
+/// `node` is not a valid ProseMirror JSON.
+pub(crate) fn insert_content(
+    parent: &XmlFragment,
+    txn: &mut Transaction,
+    index: u32,
+    node: &Any,
+) -> Result<u32, ProseMirrorError> {
+    let content = parse_content(node)?;
+    Ok(insert_nodes(parent, txn, index, content))
+}
+
+fn parse_content(node: &Any) -> Result<Vec<Node>, ProseMirrorError> {
+    let map = match node {
+        Any::Map(map) => map,
+        _ => return Err(ProseMirrorError::InvalidNode(node.clone())),
+    };
+    match map.get("content") {
+        None => Ok(Vec::new()),
+        Some(Any::Array(content)) => content.iter().map(parse_node).collect(),
+        Some(_) => Err(ProseMirrorError::InvalidNode(node.clone())),
+    }
+}
+
+fn parse_node(node: &Any) -> Result<Node, ProseMirrorError> {
+    let invalid = || ProseMirrorError::InvalidNode(node.clone());
+    let map = match node {
+        Any::Map(map) => map,
+        _ => return Err(invalid()),
+    };
+    match map.get("type") {
+        Some(Any::String(t)) if t.as_ref() == "text" => {
+            let text = match map.get("text") {
+                Some(Any::String(text)) => text.to_string(),
+                _ => return Err(invalid()),
+            };
+            let mut attrs = Attrs::new();
+            match map.get("marks") {
+                None => {}
+                Some(Any::Array(marks)) => {
+                    for mark in marks.iter() {
+                        let (key, value) = parse_mark(mark)?;
+                        attrs.insert(key, value);
+                    }
+                }
+                Some(_) => return Err(invalid()),
+            }
+            Ok(Node::Text(text, attrs))
+        }
+        Some(Any::String(tag)) => {
+            let attrs = match map.get("attrs") {
+                None => Vec::new(),
+                Some(Any::Map(attrs)) => attrs
+                    .iter()
+                    .filter(|(_, value)| !matches!(value, Any::Null | Any::Undefined))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+                Some(_) => return Err(invalid()),
+            };
+            Ok(Node::Element {
+                tag: tag.to_string(),
+                attrs,
+                content: parse_content(node)?,
+            })
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_mark(mark: &Any) -> Result<(Rc<str>, Any), ProseMirrorError> {
+    if let Any::Map(map) = mark {
+        if let Some(Any::String(mark_type)) = map.get("type") {
+            let attrs = match map.get("attrs") {
+                Some(Any::Map(attrs)) => Any::Map(attrs.clone()),
+                _ => Any::Map(Box::default()),
+            };
+            return Ok((mark_type.as_ref().into(), attrs));
+        }
+    }
+    Err(ProseMirrorError::InvalidMark(mark.clone()))
+}
+
+fn insert_nodes(parent: &XmlFragment, txn: &mut Transaction, index: u32, nodes: Vec<Node>) -> u32 {
+    let mut i = index;
+    let mut text: Option<XmlText> = None;
+    for node in nodes {
+        match node {
+            Node::Text(chunk, attrs) => {
+                // consecutive text nodes are merged into a single XML text
+                let text = text.get_or_insert_with(|| {
+                    let text = parent.insert_text(txn, i);
+                    i += 1;
+                    text
+                });
+                let len = text.len();
+                text.insert_with_attributes(txn, len, &chunk, attrs);
+            }
+            Node::Element {
+                tag,
+                attrs,
+                content,
+            } => {
+                text = None;
+                let elem = parent.insert_elem(txn, i, tag);
+                for (key, value) in attrs {
+                    elem.insert_attribute_value(txn, key.into(), value);
+                }
+                insert_nodes(elem.as_fragment(), txn, 0, content);
+                i += 1;
+            }
+        }
+    }
+    i - index
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::exchange_updates;
+    use crate::types::prosemirror::ProseMirrorError;
+    use crate::types::xml::XmlElement;
+    use crate::Doc;
+    use lib0::any::Any;
+
+    #[test]
+    fn prosemirror_json_roundtrip() {
+        let json = Any::from_json(
+            r#"{"type":"doc","content":[
+                {"type":"heading","attrs":{"level":1},"content":[{"type":"text","text":"Title"}]},
+                {"type":"paragraph","content":[
+                    {"type":"text","text":"hello "},
+                    {"type":"text","text":"world","marks":[{"type":"bold"},{"type":"link","attrs":{"href":"https://yjs.dev"}}]},
+                    {"type":"text","text":"!"}
+                ]},
+                {"type":"horizontal_rule"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let d1 = Doc::with_client_id(1);
+        let f1 = d1.transact().get_xml_fragment("prosemirror");
+        {
+            let mut txn = d1.transact();
+            let inserted = f1.insert_prosemirror_json(&mut txn, 0, &json).unwrap();
+            assert_eq!(inserted, 3);
+
+            let invalid = Any::from_json(r#"{"type":"doc","content":[{"text":"x"}]}"#).unwrap();
+            assert!(matches!(
+                f1.insert_prosemirror_json(&mut txn, 0, &invalid),
+                Err(ProseMirrorError::InvalidNode(_))
+            ));
+        }
+        let paragraph = f1.get::<XmlElement>(1).unwrap();
+        assert_eq!(paragraph.len(), 1);
+        assert_eq!(f1.to_prosemirror_json(), json);
+
+        let d2 = Doc::with_client_id(2);
+        let f2 = d2.transact().get_xml_fragment("prosemirror");
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(f2.to_prosemirror_json(), json);
+    }
+}
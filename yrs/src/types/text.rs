@@ -1,13 +1,22 @@
 use crate::block::{Block, BlockPtr, Item, ItemContent, ItemPosition, Prelim};
 use crate::block_store::Snapshot;
 use crate::event::Subscription;
+use crate::grapheme_index::update_graphemes;
+use crate::line_index::{unit_len, update_lines};
+use crate::search_marker::update_markers;
 use crate::transaction::Transaction;
+#[cfg(feature = "markdown")]
+use crate::types::markdown;
+use crate::types::text_render::{HtmlRenderer, MarkdownRenderer};
+use crate::types::text_search::Pattern;
+use crate::types::weak::{LinkSource, WeakPrelim};
 use crate::types::{Attrs, Branch, BranchPtr, Delta, Observers, Path, Value, TYPE_REFS_TEXT};
 use crate::*;
 use lib0::any::Any;
 use std::cell::UnsafeCell;
 use std::collections::HashMap;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// A shared data type used for collaborative text editing. It enables multiple users to add and
 /// remove chunks of text in efficient manner. This type is internally represented as a mutable
@@ -44,7 +53,405 @@ impl Text {
 
     /// Returns a number of characters visible in a current text data structure.
     pub fn len(&self) -> u32 {
-        self.0.content_len
+        match self.0.store.as_ref() {
+            Some(store) if store.options.offset_kind == OffsetKind::Grapheme => {
+                let mut branch = self.0;
+                let (start, len) = (branch.start, branch.content_len);
+                branch.grapheme_index.len(start, len)
+            }
+            _ => self.0.content_len,
+        }
+    }
+
+    /// Converts an `offset` within current text contents from one offset kind to another,
+    /// eg. UTF-16 offset used by JavaScript clients into a number of Unicode code points.
+    /// Embeds are counted as a single element by all offset kinds.
+    ///
+    /// Offsets pointing inside of a character (or a grapheme cluster) are moved to its start,
+    /// while offsets exceeding text length are carried over past its end.
+    ///
+    /// Methods suffixed with `_with_offset_kind` accept and return positions expressed using
+    /// a given offset kind instead of the [Options::offset_kind](crate::Options::offset_kind)
+    /// configured for the document. Such positions are converted just like this method does.
+    pub fn convert_offset(&self, offset: u32, from: OffsetKind, to: OffsetKind) -> u32 {
+        match (self.offset_kind(), from, to) {
+            _ if from == to => offset,
+            (OffsetKind::Grapheme, OffsetKind::Utf16, OffsetKind::Grapheme) => {
+                self.to_graphemes(offset)
+            }
+            (OffsetKind::Grapheme, OffsetKind::Grapheme, OffsetKind::Utf16) => {
+                self.to_units(offset)
+            }
+            _ => convert_offset(&self.elements(), offset, from, to),
+        }
+    }
+
+    /// Returns a number of lines in current text. Lines are separated by new line characters,
+    /// therefore an empty text consists of a single empty line.
+    pub fn line_count(&self) -> u32 {
+        self.with_lines(|starts| starts.len() as u32 + 1)
+    }
+
+    /// Converts a given `offset` into a zero-based line number and a column within that line.
+    /// Both `offset` and a column are expressed using the offset kind configured for
+    /// the document. Returns `None` if `offset` exceeds the text length.
+    ///
+    /// Line positions are cached by current text and updated by local changes, while remote
+    /// changes cause them to be recomputed on the next lookup. In documents using
+    /// [OffsetKind::Grapheme], cached positions are mapped onto grapheme offsets using cached
+    /// grapheme clusters, without segmenting an entire text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use yrs::Doc;
+    ///
+    /// let doc = Doc::new();
+    /// let mut txn = doc.transact();
+    /// let text = txn.get_text("text");
+    /// text.insert(&mut txn, 0, "hello\nworld");
+    ///
+    /// assert_eq!(text.offset_to_line_column(8), Some((1, 2)));
+    /// assert_eq!(text.line_column_to_offset(1, 2), Some(8));
+    /// assert_eq!(text.line(1), Some("world".to_string()));
+    /// ```
+    pub fn offset_to_line_column(&self, offset: u32) -> Option<(u32, u32)> {
+        let unit_offset = self.to_units(offset);
+        if unit_offset > self.0.content_len {
+            return None;
+        }
+        let (line, line_start) = self.with_lines(|starts| {
+            let line = starts.partition_point(|&start| start <= unit_offset);
+            let line_start = if line == 0 { 0 } else { starts[line - 1] };
+            (line as u32, line_start)
+        });
+        Some((line, offset - self.to_graphemes(line_start)))
+    }
+
+    /// Converts a zero-based `line` number and a `column` within that line into an offset within
+    /// current text. Both `column` and returned offset are expressed using the offset kind
+    /// configured for the document. Returns `None` if there's no such line or `column` exceeds
+    /// the length of that line.
+    pub fn line_column_to_offset(&self, line: u32, column: u32) -> Option<u32> {
+        let (start, end) = self.line_range(line)?;
+        let (start, end) = (self.to_graphemes(start), self.to_graphemes(end));
+        if column <= end - start {
+            Some(start + column)
+        } else {
+            None
+        }
+    }
+
+    /// Returns contents of a given zero-based `line` without a trailing new line character or
+    /// `None` if there's no such line. Embedded values are skipped.
+    pub fn line(&self, line: u32) -> Option<String> {
+        let (start, end) = self.line_range(line)?;
+        let kind = self.offset_kind();
+        let (mut ptr, mut offset) = match self.0.search_markers.find(start, true) {
+            Some(marker) => (Some(marker.ptr), marker.index),
+            None => (self.0.start, 0),
+        };
+        let mut s = String::new();
+        while let Some(Block::Item(item)) = ptr.as_deref() {
+            if offset >= end {
+                break;
+            }
+            if !item.is_deleted() && item.is_countable() {
+                let len = item.content_len(kind);
+                if let ItemContent::String(chunk) = &item.content {
+                    if offset + len > start {
+                        let mut i = offset;
+                        for c in chunk.as_str().chars() {
+                            if i >= end {
+                                break;
+                            } else if i >= start {
+                                s.push(c);
+                            }
+                            i += unit_len(c, kind);
+                        }
+                    }
+                }
+                offset += len;
+            }
+            ptr = item.right;
+        }
+        Some(s)
+    }
+
+    /// Returns a range of offsets of a given `line` (excluding its trailing new line character),
+    /// expressed using the same units as a branch content length.
+    fn line_range(&self, line: u32) -> Option<(u32, u32)> {
+        let len = self.0.content_len;
+        self.with_lines(|starts| {
+            let line = line as usize;
+            let start = match line {
+                0 => 0,
+                n => *starts.get(n - 1)?,
+            };
+            let end = match starts.get(line) {
+                Some(next) => next - 1,
+                None if line <= starts.len() => len,
+                None => return None,
+            };
+            Some((start, end))
+        })
+    }
+
+    /// Calls a given function with cached offsets of line starts of current text, rebuilding
+    /// them first if necessary.
+    fn with_lines<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&[u32]) -> T,
+    {
+        let kind = self.offset_kind();
+        let mut branch = self.0;
+        let start = branch.start;
+        f(branch.line_index.starts(start, kind))
+    }
+
+    /// Maps an offset expressed using the offset kind configured for the document onto units used
+    /// by blocks of current text. Only texts using [OffsetKind::Grapheme] need to be mapped, which
+    /// is done using grapheme clusters cached alongside them.
+    fn to_units(&self, offset: u32) -> u32 {
+        if self.offset_kind() == OffsetKind::Grapheme {
+            let mut branch = self.0;
+            let start = branch.start;
+            branch.grapheme_index.to_units(start, offset)
+        } else {
+            offset
+        }
+    }
+
+    /// Reverse of [Text::to_units].
+    fn to_graphemes(&self, offset: u32) -> u32 {
+        if self.offset_kind() == OffsetKind::Grapheme {
+            let mut branch = self.0;
+            let start = branch.start;
+            branch.grapheme_index.to_graphemes(start, offset)
+        } else {
+            offset
+        }
+    }
+
+    fn offset_kind(&self) -> OffsetKind {
+        match self.0.store.as_ref() {
+            Some(store) => store.options.offset_kind,
+            None => OffsetKind::Bytes,
+        }
+    }
+
+    /// Returns ranges of all non-overlapping matches of a given `pattern` within current text,
+    /// expressed using the offset kind configured for the document. A `pattern` can be either
+    /// a string literal or - with the `regex` feature enabled - a `regex::Regex`.
+    ///
+    /// Embedded values are never matched: they split text content into fragments, which are
+    /// searched separately.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use yrs::Doc;
+    ///
+    /// let doc = Doc::new();
+    /// let mut txn = doc.transact();
+    /// let text = txn.get_text("text");
+    /// text.insert(&mut txn, 0, "to be or not to be");
+    ///
+    /// assert_eq!(text.find("be"), vec![3..5, 16..18]);
+    /// ```
+    pub fn find<P: Pattern + ?Sized>(&self, pattern: &P) -> Vec<Range<u32>> {
+        self.find_with_offset_kind(pattern, self.offset_kind())
+    }
+
+    /// Same as [Text::find], but returned ranges are expressed using a given offset `kind`.
+    /// See: [Text::convert_offset].
+    pub fn find_with_offset_kind<P: Pattern + ?Sized>(
+        &self,
+        pattern: &P,
+        kind: OffsetKind,
+    ) -> Vec<Range<u32>> {
+        let (elements, fragments) = self.fragments();
+        let matches: Vec<_> = fragments
+            .into_iter()
+            .flat_map(|fragment| {
+                let start = fragment.start;
+                let matches = pattern.find_matches(&elements[fragment]);
+                matches
+                    .into_iter()
+                    .map(move |m| start + m.start..start + m.end)
+            })
+            .collect();
+        let offsets = byte_offsets(
+            &elements,
+            matches.iter().flat_map(|m| [m.start, m.end]),
+            kind,
+        );
+        offsets.chunks(2).map(|range| range[0]..range[1]).collect()
+    }
+
+    /// Replaces all non-overlapping matches of a given `pattern` with a `replacement` string.
+    /// Regular expression patterns can refer to their capture groups within a `replacement`.
+    /// Returns a number of replaced matches.
+    ///
+    /// Only a part of each match, which differs from its replacement, is removed and inserted
+    /// again. Inserted text is formatted using the same attributes as the match it replaces.
+    /// Just like with [Text::find], embedded values are never matched nor replaced.
+    pub fn replace_all<P: Pattern + ?Sized>(
+        &self,
+        txn: &mut Transaction,
+        pattern: &P,
+        replacement: &str,
+    ) -> u32 {
+        let (elements, fragments) = self.fragments();
+        let replacements: Vec<_> = fragments
+            .into_iter()
+            .flat_map(|fragment| {
+                let start = fragment.start;
+                let replacements = pattern.replacements(&elements[fragment], replacement);
+                replacements
+                    .into_iter()
+                    .map(move |(m, r)| (start + m.start..start + m.end, r))
+            })
+            .collect();
+        if replacements.is_empty() {
+            return 0;
+        }
+
+        // formatting attributes of text chunks, indexed by the end offset of each chunk
+        let mut chunks = Vec::new();
+        let mut end = 0;
+        for Diff::Insert(value, attrs) in self.diff(txn) {
+            end += match &value {
+                Value::Any(Any::String(s)) => s.chars().count() as u32,
+                _ => 1,
+            };
+            chunks.push((end, attrs));
+        }
+        let attributes_at = |offset: u32| -> Attrs {
+            let i = chunks.partition_point(|(end, _)| *end <= offset);
+            match chunks.get(i) {
+                Some((_, Some(attrs))) => attrs.as_ref().clone(),
+                _ => Attrs::new(),
+            }
+        };
+
+        let offsets = byte_offsets(
+            &elements,
+            replacements.iter().flat_map(|(m, _)| [m.start, m.end]),
+            OffsetKind::Utf32,
+        );
+        let count = replacements.len() as u32;
+        // replace matches starting from the last one, so that offsets of preceding ones stay valid
+        for (i, (range, replacement)) in replacements.into_iter().enumerate().rev() {
+            let (start, end) = (offsets[2 * i], offsets[2 * i + 1]);
+            let found: Vec<char> = elements[range].chars().collect();
+            let replacement: Vec<char> = replacement.chars().collect();
+            let prefix = found
+                .iter()
+                .zip(replacement.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let suffix = found[prefix..]
+                .iter()
+                .rev()
+                .zip(replacement[prefix..].iter().rev())
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            let index = start + prefix as u32;
+            let removed = end - suffix as u32 - index;
+            let inserted: String = replacement[prefix..replacement.len() - suffix]
+                .iter()
+                .collect();
+            if removed > 0 {
+                self.remove_range_with_offset_kind(txn, index, removed, OffsetKind::Utf32);
+            }
+            if !inserted.is_empty() {
+                // use formatting of the character being replaced or the one preceding it
+                let at = if removed == 0 && index > start || index == end && index > 0 {
+                    index - 1
+                } else {
+                    index
+                };
+                let attrs = attributes_at(at);
+                self.insert_with_attributes_with_offset_kind(
+                    txn,
+                    index,
+                    &inserted,
+                    attrs,
+                    OffsetKind::Utf32,
+                );
+            }
+        }
+        count
+    }
+
+    /// Returns contents of current text, in which embeds are represented by a NUL character,
+    /// which is counted as a single element by all offset kinds.
+    fn elements(&self) -> String {
+        let mut start = self.0.start;
+        let mut s = String::new();
+        while let Some(Block::Item(item)) = start.as_deref() {
+            if !item.is_deleted() && item.is_countable() {
+                match &item.content {
+                    ItemContent::String(item_string) => s.push_str(item_string),
+                    _ => s.push('\0'),
+                }
+            }
+            start = item.right;
+        }
+        s
+    }
+
+    /// Returns contents of current text just like [Text::elements] does, together with byte ranges
+    /// of text fragments placed in between embeds.
+    fn fragments(&self) -> (String, Vec<Range<usize>>) {
+        let mut start = self.0.start;
+        let mut s = String::new();
+        let mut fragments = Vec::new();
+        let mut fragment_start = 0;
+        while let Some(Block::Item(item)) = start.as_deref() {
+            if !item.is_deleted() && item.is_countable() {
+                match &item.content {
+                    ItemContent::String(item_string) => s.push_str(item_string),
+                    _ => {
+                        fragments.push(fragment_start..s.len());
+                        s.push('\0');
+                        fragment_start = s.len();
+                    }
+                }
+            }
+            start = item.right;
+        }
+        fragments.push(fragment_start..s.len());
+        (s, fragments)
+    }
+
+    /// Maps a range of `len` characters starting at given `index`, both expressed using a given
+    /// offset `kind`, onto a range used by blocks of current text. Blocks of a text using
+    /// [OffsetKind::Grapheme] are measured using UTF-16 offsets of grapheme cluster boundaries.
+    pub(crate) fn map_range(
+        &self,
+        txn: &Transaction,
+        index: u32,
+        len: u32,
+        kind: OffsetKind,
+    ) -> (u32, u32) {
+        let block_kind = match txn.store().options.offset_kind {
+            OffsetKind::Grapheme => OffsetKind::Utf16,
+            other => other,
+        };
+        if kind == block_kind {
+            return (index, len);
+        } else if kind == OffsetKind::Grapheme {
+            let end = self.to_units(index + len);
+            let index = self.to_units(index);
+            return (index, end - index);
+        }
+        let elements = self.elements();
+        let start = convert_offset(&elements, index, kind, block_kind);
+        let end = convert_offset(&elements, index + len, kind, block_kind);
+        (start, end - start)
     }
 
     pub(crate) fn inner(&self) -> BranchPtr {
@@ -52,21 +459,32 @@ impl Text {
     }
 
     pub(crate) fn find_position(&self, txn: &mut Transaction, index: u32) -> Option<ItemPosition> {
-        let mut pos = {
+        let marker = self.0.search_markers.find(index, false);
+        let (mut pos, mut remaining) = if let Some(marker) = marker {
+            // start from the closest marker placed before the index
+            let pos = ItemPosition {
+                parent: self.0.into(),
+                left: marker.ptr.as_item().and_then(|item| item.left),
+                right: Some(marker.ptr),
+                index: marker.index,
+                current_attrs: marker.attrs.clone(),
+            };
+            (pos, index - marker.index)
+        } else {
             let inner = self.as_ref();
-            ItemPosition {
+            let pos = ItemPosition {
                 parent: self.0.into(),
                 left: None,
                 right: inner.start,
                 index: 0,
                 current_attrs: None,
-            }
+            };
+            (pos, index)
         };
 
         let mut format_ptrs = HashMap::new();
         let store = txn.store_mut();
         let encoding = store.options.offset_kind;
-        let mut remaining = index;
         while let Some(mut right_ptr) = pos.right {
             if remaining == 0 {
                 break;
@@ -78,6 +496,10 @@ impl Text {
                         ItemContent::Format(key, value) => {
                             if let Any::Null = value.as_ref() {
                                 format_ptrs.remove(key);
+                                if let Some(attrs) = pos.current_attrs.as_mut() {
+                                    // attributes inherited from a search marker
+                                    attrs.remove(key);
+                                }
                             } else {
                                 format_ptrs.insert(key.clone(), pos.right.clone());
                             }
@@ -128,7 +550,26 @@ impl Text {
                 }
             }
         }
-        
+        if let Some(attrs) = pos.current_attrs.as_ref() {
+            if attrs.is_empty() {
+                pos.current_attrs = None;
+            }
+        }
+
+        if remaining == 0 {
+            if let Some(Block::Item(left)) = pos.left.as_deref() {
+                // last visited block ends at the index, so there are no formatting blocks between
+                // them and current attributes are also valid at the beginning of that block
+                let left_index = index - left.content_len(encoding);
+                let attrs = pos.current_attrs.clone();
+                let mut branch = self.0;
+                let len = branch.content_len;
+                branch
+                    .search_markers
+                    .mark(pos.left.unwrap(), left_index, attrs, len);
+            }
+        }
+
         Some(pos)
     }
 
@@ -139,9 +580,23 @@ impl Text {
     ///
     /// This method will panic if provided `index` is greater than the length of a current text.
     pub fn insert(&self, txn: &mut Transaction, index: u32, chunk: &str) {
+        let kind = txn.store().options.offset_kind;
+        self.insert_with_offset_kind(txn, index, chunk, kind)
+    }
+
+    /// Same as [Text::insert], but `index` is expressed using a given offset `kind`.
+    /// See: [Text::convert_offset].
+    pub fn insert_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        chunk: &str,
+        kind: OffsetKind,
+    ) {
         if chunk.is_empty() {
             return;
         }
+        let (index, _) = self.map_range(txn, index, 0, kind);
         if let Some(mut pos) = self.find_position(txn, index) {
             let value = crate::block::PrelimString(chunk.into());
             while let Some(right) = pos.right.as_ref() {
@@ -152,7 +607,11 @@ impl Text {
                     break;
                 }
             }
-            txn.create_item(&pos, value, None);
+            update_graphemes(self.0, index, || {
+                update_markers(self.0, index, || {
+                    update_lines(self.0, index, chunk, || txn.create_item(&pos, value, None))
+                })
+            });
         } else {
             panic!("The type or the position doesn't exist!");
         }
@@ -167,19 +626,36 @@ impl Text {
     ///
     /// This method will panic if provided `index` is greater than the length of a current text.
     pub fn insert_with_attributes(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        chunk: &str,
+        attributes: Attrs,
+    ) {
+        let kind = txn.store().options.offset_kind;
+        self.insert_with_attributes_with_offset_kind(txn, index, chunk, attributes, kind)
+    }
+
+    /// Same as [Text::insert_with_attributes], but `index` is expressed using a given
+    /// offset `kind`. See: [Text::convert_offset].
+    pub fn insert_with_attributes_with_offset_kind(
         &self,
         txn: &mut Transaction,
         index: u32,
         chunk: &str,
         mut attributes: Attrs,
+        kind: OffsetKind,
     ) {
+        let (index, _) = self.map_range(txn, index, 0, kind);
         if let Some(mut pos) = self.find_position(txn, index) {
             pos.unset_missing(&mut attributes);
             Text::minimize_attr_changes(&mut pos, &attributes);
             let negated_attrs = self.insert_attributes(txn, &mut pos, attributes);
 
             let value = crate::block::PrelimString(chunk.into());
-            let item = txn.create_item(&pos, value, None);
+            let item = update_graphemes(self.0, index, || {
+                update_lines(self.0, index, chunk, || txn.create_item(&pos, value, None))
+            });
 
             pos.right = Some(item);
             pos.forward();
@@ -198,9 +674,27 @@ impl Text {
     ///
     /// This method will panic if provided `index` is greater than the length of a current text.
     pub fn insert_embed(&self, txn: &mut Transaction, index: u32, content: Any) {
+        let kind = txn.store().options.offset_kind;
+        self.insert_embed_with_offset_kind(txn, index, content, kind)
+    }
+
+    /// Same as [Text::insert_embed], but `index` is expressed using a given offset `kind`.
+    /// See: [Text::convert_offset].
+    pub fn insert_embed_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        content: Any,
+        kind: OffsetKind,
+    ) {
+        let (index, _) = self.map_range(txn, index, 0, kind);
         if let Some(pos) = self.find_position(txn, index) {
             let value = crate::block::PrelimEmbed(content);
-            txn.create_item(&pos, value, None);
+            update_graphemes(self.0, index, || {
+                update_markers(self.0, index, || {
+                    update_lines(self.0, index, "", || txn.create_item(&pos, value, None))
+                })
+            });
         } else {
             panic!("The type or the position doesn't exist!");
         }
@@ -215,19 +709,36 @@ impl Text {
     ///
     /// This method will panic if provided `index` is greater than the length of a current text.
     pub fn insert_embed_with_attributes(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        embed: Any,
+        attributes: Attrs,
+    ) {
+        let kind = txn.store().options.offset_kind;
+        self.insert_embed_with_attributes_with_offset_kind(txn, index, embed, attributes, kind)
+    }
+
+    /// Same as [Text::insert_embed_with_attributes], but `index` is expressed using a given
+    /// offset `kind`. See: [Text::convert_offset].
+    pub fn insert_embed_with_attributes_with_offset_kind(
         &self,
         txn: &mut Transaction,
         index: u32,
         embed: Any,
         mut attributes: Attrs,
+        kind: OffsetKind,
     ) {
+        let (index, _) = self.map_range(txn, index, 0, kind);
         if let Some(mut pos) = self.find_position(txn, index) {
             pos.unset_missing(&mut attributes);
             Text::minimize_attr_changes(&mut pos, &attributes);
             let negated_attrs = self.insert_attributes(txn, &mut pos, attributes);
 
             let value = crate::block::PrelimEmbed(embed);
-            let item = txn.create_item(&pos, value, None);
+            let item = update_graphemes(self.0, index, || {
+                update_lines(self.0, index, "", || txn.create_item(&pos, value, None))
+            });
 
             pos.right = Some(item);
             pos.forward();
@@ -248,14 +759,64 @@ impl Text {
     /// This method panics in case when not all expected characters were removed (due to
     /// insufficient number of characters to remove) or `index` is outside of the bounds of text.
     pub fn remove_range(&self, txn: &mut Transaction, index: u32, len: u32) {
+        let kind = txn.store().options.offset_kind;
+        self.remove_range_with_offset_kind(txn, index, len, kind)
+    }
+
+    /// Same as [Text::remove_range], but `index` and `len` are expressed using a given
+    /// offset `kind`. See: [Text::convert_offset].
+    pub fn remove_range_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        len: u32,
+        kind: OffsetKind,
+    ) {
+        let (index, len) = self.map_range(txn, index, len, kind);
         if let Some(pos) = self.find_position(txn, index) {
-            Self::remove(txn, pos, len)
+            let cleanups = update_graphemes(self.0, index, || {
+                update_markers(self.0, index, || {
+                    update_lines(self.0, index, "", || Self::remove(txn, pos, len))
+                })
+            });
+            if cleanups > 0 {
+                // formatting attributes cached by markers placed after removed range may have changed
+                let mut branch = self.0;
+                branch.search_markers.truncate(index);
+            }
         } else {
             panic!("The type or the position doesn't exist!");
         }
     }
 
-    fn remove(txn: &mut Transaction, mut pos: ItemPosition, len: u32) {
+    /// Returns a preliminary [WeakLink] quoting a range of `len` characters starting at a given
+    /// `index`. Once inserted into another collection, a link resolves to the current contents of
+    /// the quoted range, including characters inserted in between the quoted ones.
+    ///
+    /// Using a range which is empty or exceeds current text length results in panic.
+    ///
+    /// [WeakLink]: crate::types::weak::WeakLink
+    pub fn quote(&self, txn: &mut Transaction, index: u32, len: u32) -> WeakPrelim {
+        let kind = txn.store().options.offset_kind;
+        self.quote_with_offset_kind(txn, index, len, kind)
+    }
+
+    /// Same as [Text::quote], but `index` and `len` are expressed using a given offset `kind`.
+    /// See: [Text::convert_offset].
+    pub fn quote_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        len: u32,
+        kind: OffsetKind,
+    ) -> WeakPrelim {
+        let (index, len) = self.map_range(txn, index, len, kind);
+        WeakPrelim::new(LinkSource::quote(txn, self.0, index, len))
+    }
+
+    /// Removes `len` elements starting from a given position. Returns a number of formatting
+    /// blocks, which have been cleaned up as a result.
+    fn remove(txn: &mut Transaction, mut pos: ItemPosition, len: u32) -> u32 {
         let encoding = txn.store().options.offset_kind;
         let mut remaining = len;
         let start = pos.right.clone();
@@ -306,7 +867,9 @@ impl Text {
                 pos.right,
                 start_attrs.as_ref(),
                 end_attrs.as_mut(),
-            );
+            )
+        } else {
+            0
         }
     }
 
@@ -353,6 +916,21 @@ impl Text {
     /// Wraps an existing piece of text within a range described by `index`-`len` parameters with
     /// formatting blocks containing provided `attributes` metadata.
     pub fn format(&self, txn: &mut Transaction, index: u32, len: u32, attributes: Attrs) {
+        let kind = txn.store().options.offset_kind;
+        self.format_with_offset_kind(txn, index, len, attributes, kind)
+    }
+
+    /// Same as [Text::format], but `index` and `len` are expressed using a given offset `kind`.
+    /// See: [Text::convert_offset].
+    pub fn format_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        len: u32,
+        attributes: Attrs,
+        kind: OffsetKind,
+    ) {
+        let (index, len) = self.map_range(txn, index, len, kind);
         if let Some(pos) = self.find_position(txn, index) {
             self.insert_format(txn, pos, len, attributes)
         } else {
@@ -587,6 +1165,53 @@ impl Text {
         self.diff_range(txn, None, None)
     }
 
+    /// Renders formatted contents of this text as HTML using a provided `renderer`.
+    /// See: [HtmlRenderer].
+    pub fn to_html(&self, txn: &mut Transaction, renderer: &HtmlRenderer) -> String {
+        renderer.render(&self.diff(txn))
+    }
+
+    /// Parses a given CommonMark `markdown` document and inserts it as a formatted text at
+    /// a given `index`. Returns a length of inserted content. See [markdown] module for the
+    /// description of formatting attributes used. Requires the `markdown` feature.
+    ///
+    /// This method will panic if provided `index` is greater than the length of a current text.
+    #[cfg(feature = "markdown")]
+    pub fn insert_markdown(&self, txn: &mut Transaction, index: u32, markdown: &str) -> u32 {
+        // chunks are inserted using units of blocks, as grapheme clusters may span over them
+        let (start, _) = self.map_range(txn, index, 0, self.offset_kind());
+        let kind = match self.offset_kind() {
+            OffsetKind::Grapheme => OffsetKind::Utf16,
+            other => other,
+        };
+        let mut i = start;
+        for delta in markdown::parse(markdown) {
+            if let Delta::Inserted(value, attrs) = delta {
+                let attrs = attrs.map(|attrs| *attrs).unwrap_or_default();
+                match value {
+                    Value::Any(Any::String(s)) => {
+                        self.insert_with_attributes_with_offset_kind(txn, i, &s, attrs, kind);
+                        i += s.chars().map(|c| char_len(c, kind, true)).sum::<u32>();
+                    }
+                    Value::Any(embed) => {
+                        self.insert_embed_with_attributes_with_offset_kind(
+                            txn, i, embed, attrs, kind,
+                        );
+                        i += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.to_graphemes(i) - self.to_graphemes(start)
+    }
+
+    /// Renders formatted contents of this text as Markdown using a provided `renderer`.
+    /// See: [MarkdownRenderer].
+    pub fn to_markdown(&self, txn: &mut Transaction, renderer: &MarkdownRenderer) -> String {
+        renderer.render(&self.diff(txn))
+    }
+
     /// Returns the Delta representation of this YText type.
     pub fn diff_range(
         &self,
@@ -694,6 +1319,60 @@ impl Text {
     }
 }
 
+/// Converts an `offset` within a given `text` from one offset kind to another.
+/// See: [Text::convert_offset].
+fn char_len(c: char, kind: OffsetKind, cluster_start: bool) -> u32 {
+    match kind {
+        OffsetKind::Bytes => c.len_utf8() as u32,
+        OffsetKind::Utf16 => c.len_utf16() as u32,
+        OffsetKind::Utf32 => 1,
+        OffsetKind::Grapheme => cluster_start as u32,
+    }
+}
+
+fn convert_offset(text: &str, offset: u32, from: OffsetKind, to: OffsetKind) -> u32 {
+    let mut from_count = 0;
+    let mut to_count = 0;
+    for cluster in text.graphemes(true) {
+        for (i, c) in cluster.chars().enumerate() {
+            let from_len = char_len(c, from, i == 0);
+            if from_count + from_len > offset {
+                // offset points at or inside of a current character
+                return if to == OffsetKind::Grapheme && i != 0 {
+                    to_count - 1
+                } else {
+                    to_count
+                };
+            }
+            from_count += from_len;
+            to_count += char_len(c, to, i == 0);
+        }
+    }
+    to_count + (offset - from_count)
+}
+
+/// Converts ascending byte `offsets` within a given `text` into offsets expressed using a given
+/// offset `kind`. Offsets pointing inside of a grapheme cluster are moved to its start.
+fn byte_offsets<I>(text: &str, offsets: I, kind: OffsetKind) -> Vec<u32>
+where
+    I: IntoIterator<Item = usize>,
+{
+    let mut offsets = offsets.into_iter().peekable();
+    let mut result = Vec::new();
+    let mut count = 0;
+    for (start, cluster) in text.grapheme_indices(true) {
+        for (i, (j, c)) in cluster.char_indices().enumerate() {
+            while offsets.next_if(|&offset| offset <= start + j).is_some() {
+                let inside_cluster = kind == OffsetKind::Grapheme && i != 0;
+                result.push(if inside_cluster { count - 1 } else { count });
+            }
+            count += char_len(c, kind, i == 0);
+        }
+    }
+    result.extend(offsets.map(|_| count));
+    result
+}
+
 impl From<BranchPtr> for Text {
     fn from(inner: BranchPtr) -> Self {
         Text(inner)
@@ -971,6 +1650,35 @@ impl Prelim for PrelimText<'_> {
     }
 }
 
+/// An owned counterpart of [PrelimText]. It can be used to initialize a Text, when its initial
+/// content cannot be borrowed for the lifetime of a preliminary value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrelimTextBuf(pub String);
+
+impl From<String> for PrelimTextBuf {
+    fn from(value: String) -> Self {
+        PrelimTextBuf(value)
+    }
+}
+
+impl From<&str> for PrelimTextBuf {
+    fn from(value: &str) -> Self {
+        PrelimTextBuf(value.to_string())
+    }
+}
+
+impl Prelim for PrelimTextBuf {
+    fn into_content(self, _txn: &mut Transaction) -> (ItemContent, Option<Self>) {
+        let inner = Branch::new(TYPE_REFS_TEXT, None);
+        (ItemContent::Type(inner), Some(self))
+    }
+
+    fn integrate(self, txn: &mut Transaction, inner_ref: BranchPtr) {
+        let text = Text::from(inner_ref);
+        text.push(txn, &self.0);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::doc::{OffsetKind, Options};
@@ -1404,6 +2112,94 @@ mod test {
         assert_eq!(txt.to_string(), r#"“test”"#);
     }
 
+    #[test]
+    fn grapheme_encoding() {
+        let mut options = Options::with_client_id(1);
+        options.offset_kind = OffsetKind::Grapheme;
+        let doc = Doc::with_options(options);
+        let txt = doc.transact().get_text("content");
+
+        // family emoji is a ZWJ sequence of 7 code points, "é" uses a combining mark
+        txt.insert(&mut doc.transact(), 0, "a👨‍👩‍👧b");
+        txt.insert(&mut doc.transact(), 3, "e\u{301}");
+        assert_eq!(txt.len(), 4);
+
+        // combining mark appended in a separate block still forms a single character
+        txt.insert(&mut doc.transact(), 2, "c");
+        txt.insert(&mut doc.transact(), 3, "\u{308}");
+        assert_eq!(txt.to_string(), "a👨‍👩‍👧c\u{308}be\u{301}");
+        assert_eq!(txt.len(), 5);
+
+        let mut attrs = Attrs::new();
+        attrs.insert("bold".into(), Any::Bool(true));
+        txt.format(&mut doc.transact(), 1, 1, attrs.clone());
+        assert_eq!(
+            txt.diff(&mut doc.transact()),
+            vec![
+                Diff::Insert("a".into(), None),
+                Diff::Insert("👨‍👩‍👧".into(), Some(Box::new(attrs))),
+                Diff::Insert("c\u{308}be\u{301}".into(), None),
+            ]
+        );
+
+        txt.remove_range(&mut doc.transact(), 1, 2);
+        assert_eq!(txt.to_string(), "abe\u{301}");
+
+        txt.remove_range(&mut doc.transact(), 2, 1);
+        assert_eq!(txt.to_string(), "ab");
+        assert_eq!(txt.len(), 2);
+    }
+
+    #[test]
+    fn per_call_offset_kind() {
+        let mut options = Options::with_client_id(1);
+        options.offset_kind = OffsetKind::Utf16;
+        let doc = Doc::with_options(options);
+        let txt = doc.transact().get_text("content");
+        txt.insert(&mut doc.transact(), 0, "a😀é");
+
+        assert_eq!(
+            txt.convert_offset(2, OffsetKind::Utf32, OffsetKind::Utf16),
+            3
+        );
+        assert_eq!(
+            txt.convert_offset(3, OffsetKind::Utf16, OffsetKind::Bytes),
+            5
+        );
+        assert_eq!(
+            txt.convert_offset(7, OffsetKind::Bytes, OffsetKind::Grapheme),
+            3
+        );
+        // offsets inside of a surrogate pair are moved to the start of a character
+        assert_eq!(
+            txt.convert_offset(2, OffsetKind::Utf16, OffsetKind::Utf32),
+            1
+        );
+        // offsets past the end of a text are carried over
+        assert_eq!(
+            txt.convert_offset(4, OffsetKind::Utf32, OffsetKind::Utf16),
+            5
+        );
+
+        txt.insert_with_offset_kind(&mut doc.transact(), 5, "x", OffsetKind::Bytes);
+        assert_eq!(txt.to_string(), "a😀xé");
+
+        txt.insert_embed_with_offset_kind(
+            &mut doc.transact(),
+            4,
+            Any::Bool(true),
+            OffsetKind::Utf32,
+        );
+        assert_eq!(
+            txt.convert_offset(5, OffsetKind::Utf32, OffsetKind::Utf16),
+            6
+        );
+
+        txt.remove_range_with_offset_kind(&mut doc.transact(), 1, 2, OffsetKind::Utf32);
+        assert_eq!(txt.to_string(), "aé");
+        assert_eq!(txt.len(), 3);
+    }
+
     #[test]
     fn unicode_support() {
         let d1 = {
@@ -1970,4 +2766,109 @@ mod test {
 
         assert!(txt.diff(&mut txn).eq(&expect))
     }
+
+    #[test]
+    fn find_pattern() {
+        let doc = Doc::with_options(Options {
+            offset_kind: OffsetKind::Utf16,
+            ..Options::default()
+        });
+        let mut txn = doc.transact();
+        let txt = txn.get_text("test");
+        txt.insert(&mut txn, 0, "🐱 cat, cat");
+        txt.insert_embed(&mut txn, 3, Any::Bool(true));
+
+        assert_eq!(txt.find("cat"), vec![4..7, 9..12]);
+        assert_eq!(txt.find(""), vec![]);
+        assert_eq!(
+            txt.find_with_offset_kind("cat", OffsetKind::Bytes),
+            vec![6..9, 11..14]
+        );
+        // embeds are never matched
+        assert_eq!(txt.find("\0"), vec![]);
+        assert_eq!(txt.find("🐱 "), vec![0..3]);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn find_regex() {
+        let doc = Doc::new();
+        let mut txn = doc.transact();
+        let txt = txn.get_text("test");
+        txt.insert(&mut txn, 0, "cat cat, cat");
+        txt.insert_embed(&mut txn, 3, Any::Bool(true));
+
+        // embeds split text into separately searched fragments
+        let regex = regex::Regex::new(r"c\w+$").unwrap();
+        assert_eq!(txt.find(&regex), vec![0..3, 10..13]);
+        let regex = regex::Regex::new(r"t.").unwrap();
+        assert_eq!(txt.find(&regex), vec![7..9]);
+    }
+
+    #[test]
+    fn replace_all_preserves_formatting() {
+        let d1 = Doc::with_client_id(1);
+        let t1 = d1.transact().get_text("test");
+        let d2 = Doc::with_client_id(2);
+        let t2 = d2.transact().get_text("test");
+        let bold = Attrs::from([("bold".into(), Any::Bool(true))]);
+        {
+            let mut txn = d1.transact();
+            t1.insert(&mut txn, 0, "one cat, ");
+            t1.insert_with_attributes(&mut txn, 9, "two cats", bold.clone());
+        }
+        exchange_updates(&[&d1, &d2]);
+
+        {
+            let mut txn = d1.transact();
+            assert_eq!(t1.replace_all(&mut txn, "cat", "dog"), 2);
+            assert_eq!(t1.replace_all(&mut txn, " dog", " big dog"), 2);
+        }
+        {
+            // replacements are merged with concurrent edits
+            let mut txn = d2.transact();
+            t2.insert(&mut txn, 0, "my ");
+        }
+        exchange_updates(&[&d1, &d2]);
+
+        assert_eq!(t1.to_string(), t2.to_string());
+        assert_eq!(t1.to_string(), "my one big dog, two big dogs");
+        let mut txn = d1.transact();
+        let expect = vec![
+            Diff::Insert("my one big dog, ".into(), None),
+            Diff::Insert("two big dogs".into(), Some(Box::new(bold))),
+        ];
+        assert_eq!(t1.diff(&mut txn), expect);
+    }
+
+    #[test]
+    fn replace_all_skips_embeds() {
+        let doc = Doc::new();
+        let mut txn = doc.transact();
+        let txt = txn.get_text("test");
+        txt.insert(&mut txn, 0, "a b");
+        txt.insert_embed(&mut txn, 1, Any::Bool(true));
+
+        assert_eq!(txt.replace_all(&mut txn, "\0", ""), 0);
+        assert_eq!(txt.replace_all(&mut txn, " ", "-"), 1);
+        let expect = vec![
+            Diff::Insert("a".into(), None),
+            Diff::Insert(Any::Bool(true).into(), None),
+            Diff::Insert("-b".into(), None),
+        ];
+        assert_eq!(txt.diff(&mut txn), expect);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn replace_all_regex() {
+        let doc = Doc::new();
+        let mut txn = doc.transact();
+        let txt = txn.get_text("test");
+        txt.insert(&mut txn, 0, "one cat, two cats");
+
+        let regex = regex::Regex::new(r"(\w+) cat").unwrap();
+        assert_eq!(txt.replace_all(&mut txn, &regex, "${1} big dog"), 2);
+        assert_eq!(txt.to_string(), "one big dog, two big dogs");
+    }
 }
@@ -0,0 +1,581 @@
+//! Renderers producing HTML and Markdown from a formatted [Text](crate::Text) or
+//! [XmlText](crate::XmlText) content.
+//!
+//! Both renderers work on a list of [Diff] chunks returned by [Text::diff](crate::Text::diff).
+//! Formatting attributes of each chunk are mapped onto opening and closing markup using rules
+//! registered for specific attribute keys. Attributes without any registered rule are ignored.
+//! Rules are applied in the order of their registration: markup of rules registered earlier
+//! wraps markup of rules registered later. Markup of consecutive chunks sharing the same
+//! formatting is merged together, so that a produced output is always correctly nested.
+//!
+//! Only inline formatting is supported. Line breaks are rendered as they are.
+
+use crate::types::text::Diff;
+use crate::types::Value;
+use lib0::any::Any;
+use std::rc::Rc;
+
+type FormatFn = Box<dyn Fn(&Any) -> Option<(String, String)>>;
+type EmbedFn = Box<dyn Fn(&Value) -> String>;
+
+/// Rule used to render text formatted with a specific attribute.
+struct Format {
+    key: Rc<str>,
+    /// Produces an opening and closing markup for a given attribute value.
+    markup: FormatFn,
+    /// If true, text wrapped by this format is rendered as a Markdown code span, without escaping.
+    verbatim: bool,
+}
+
+/// Formatting markup, which is currently open.
+struct Open {
+    key: Rc<str>,
+    value: Any,
+    close: String,
+    verbatim: bool,
+    /// Position in the output, at which the content wrapped by this markup starts.
+    start: usize,
+}
+
+/// Set of rules shared by both HTML and Markdown renderers.
+#[derive(Default)]
+struct Rules {
+    formats: Vec<Format>,
+    embed: Option<EmbedFn>,
+}
+
+impl Rules {
+    fn push<K, F>(&mut self, key: K, verbatim: bool, markup: F)
+    where
+        K: Into<Rc<str>>,
+        F: Fn(&Any) -> Option<(String, String)> + 'static,
+    {
+        let key = key.into();
+        // registering a rule for the same key again replaces the previous one
+        self.formats.retain(|f| f.key != key);
+        self.formats.push(Format {
+            key,
+            markup: Box::new(markup),
+            verbatim,
+        });
+    }
+
+    fn render(&self, diff: &[Diff], escape: fn(&str, &mut String), markdown: bool) -> String {
+        let mut out = String::new();
+        let mut stack: Vec<Open> = Vec::new();
+        for Diff::Insert(value, attrs) in diff {
+            let text = match value {
+                Value::Any(Any::String(s)) => Some(s.as_ref()),
+                _ => None,
+            };
+            if let Some(s) = text {
+                if markdown && !stack.is_empty() && s.trim().is_empty() {
+                    // changing emphasis around whitespace produces invalid Markdown delimiters
+                    out.push_str(s);
+                    continue;
+                }
+            }
+
+            let mut wanted = Vec::new();
+            if let Some(attrs) = attrs {
+                for format in self.formats.iter() {
+                    match attrs.get(&format.key) {
+                        None | Some(Any::Null) | Some(Any::Undefined) | Some(Any::Bool(false)) => {}
+                        Some(value) => {
+                            if let Some((open, close)) = (format.markup)(value) {
+                                wanted.push((format, value, open, close));
+                            }
+                        }
+                    }
+                }
+            }
+
+            let common = stack
+                .iter()
+                .zip(wanted.iter())
+                .take_while(|(open, (format, value, _, _))| {
+                    open.key == format.key && &open.value == *value
+                })
+                .count();
+            close(&mut out, &mut stack, common, markdown);
+
+            let mut text = text;
+            if markdown && common < wanted.len() {
+                if let Some(s) = text {
+                    // leading whitespace must be placed before opening delimiters
+                    let trimmed = s.trim_start();
+                    out.push_str(&s[..s.len() - trimmed.len()]);
+                    text = Some(trimmed);
+                }
+            }
+            for (format, value, open, close) in wanted.into_iter().skip(common) {
+                out.push_str(&open);
+                stack.push(Open {
+                    key: format.key.clone(),
+                    value: value.clone(),
+                    close,
+                    verbatim: format.verbatim,
+                    start: out.len(),
+                });
+            }
+
+            match text {
+                Some(s) if stack.iter().any(|open| open.verbatim) => out.push_str(s),
+                Some(s) => escape(s, &mut out),
+                None => {
+                    if let Some(embed) = self.embed.as_ref() {
+                        out.push_str(&embed(value));
+                    }
+                }
+            }
+        }
+        close(&mut out, &mut stack, 0, markdown);
+        out
+    }
+}
+
+/// Closes all open markup above a given `depth` of the `stack`.
+fn close(out: &mut String, stack: &mut Vec<Open>, depth: usize, markdown: bool) {
+    if stack.len() <= depth {
+        return;
+    }
+    // trailing whitespace must be placed after closing delimiters
+    let whitespace = if markdown {
+        let len = out.trim_end().len();
+        out.split_off(len)
+    } else {
+        String::new()
+    };
+    while stack.len() > depth {
+        let open = stack.pop().unwrap();
+        if open.verbatim {
+            let content = out.split_off(open.start);
+            code_span(out, &content);
+        }
+        out.push_str(&open.close);
+    }
+    out.push_str(&whitespace);
+}
+
+/// Writes a given `content` as a Markdown code span. Its backtick fences are longer than any
+/// backtick run inside of the `content`, so that they cannot be closed prematurely.
+fn code_span(out: &mut String, content: &str) {
+    if content.is_empty() {
+        return;
+    }
+    let mut longest = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat(longest + 1);
+    // a single space is stripped from both ends of a code span, if it's present at both of them
+    let pad = content.starts_with('`')
+        || content.ends_with('`')
+        || (content.starts_with(' ') && content.ends_with(' ') && !content.trim().is_empty());
+    out.push_str(&fence);
+    if pad {
+        out.push(' ');
+    }
+    out.push_str(content);
+    if pad {
+        out.push(' ');
+    }
+    out.push_str(&fence);
+}
+
+fn attribute_string(value: &Any) -> String {
+    match value {
+        Any::String(s) => s.to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Renderer producing HTML out of a formatted text. Text content and attribute values are
+/// escaped. Embeds are not rendered unless [HtmlRenderer::embed] rule has been provided.
+///
+/// [HtmlRenderer::default] maps `bold`, `italic`, `underline`, `strike` and `code` attributes
+/// onto `<b>`, `<i>`, `<u>`, `<s>` and `<code>` tags respectively, while `link` attribute is
+/// rendered as `<a>` tag with an attribute value used as its `href`.
+///
+/// # Example
+///
+/// ```rust
+/// use yrs::Doc;
+/// use yrs::types::text_render::HtmlRenderer;
+/// use yrs::types::Attrs;
+/// use lib0::any::Any;
+///
+/// let doc = Doc::new();
+/// let mut txn = doc.transact();
+/// let text = txn.get_text("text");
+/// text.insert(&mut txn, 0, "hello world");
+/// let bold = Attrs::from([("bold".into(), Any::Bool(true))]);
+/// text.format(&mut txn, 6, 5, bold);
+///
+/// let renderer = HtmlRenderer::default().tag("bold", "strong");
+/// assert_eq!(text.to_html(&mut txn, &renderer), "hello <strong>world</strong>");
+/// ```
+pub struct HtmlRenderer(Rules);
+
+impl HtmlRenderer {
+    /// Creates a new renderer without any formatting rules. All formatting attributes will be
+    /// ignored.
+    pub fn new() -> Self {
+        HtmlRenderer(Rules::default())
+    }
+
+    /// Renders text formatted with a given `attr` using a provided HTML `tag`.
+    pub fn tag<K: Into<Rc<str>>>(mut self, attr: K, tag: &str) -> Self {
+        let open = format!("<{}>", tag);
+        let close = format!("</{}>", tag);
+        self.0
+            .push(attr, false, move |_| Some((open.clone(), close.clone())));
+        self
+    }
+
+    /// Renders text formatted with a given `attr` using a provided HTML `tag`. Value of
+    /// formatting attribute is used as a value of `html_attr` attribute of that tag, eg.
+    /// `link` attribute can be rendered as `<a href="...">` tag.
+    pub fn tag_with_attribute<K: Into<Rc<str>>>(
+        mut self,
+        attr: K,
+        tag: &str,
+        html_attr: &str,
+    ) -> Self {
+        let tag = tag.to_string();
+        let html_attr = html_attr.to_string();
+        self.0.push(attr, false, move |value| {
+            let mut open = format!("<{} {}=\"", tag, html_attr);
+            escape_html(&attribute_string(value), &mut open);
+            open.push_str("\">");
+            Some((open, format!("</{}>", tag)))
+        });
+        self
+    }
+
+    /// Renders text formatted with a given `attr` using an opening and closing markup returned
+    /// by a provided function. Returned markup is not escaped. If `None` is returned, formatting
+    /// is ignored.
+    pub fn format<K, F>(mut self, attr: K, f: F) -> Self
+    where
+        K: Into<Rc<str>>,
+        F: Fn(&Any) -> Option<(String, String)> + 'static,
+    {
+        self.0.push(attr, false, f);
+        self
+    }
+
+    /// Renders embedded values using a provided function. Returned markup is not escaped.
+    pub fn embed<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Value) -> String + 'static,
+    {
+        self.0.embed = Some(Box::new(f));
+        self
+    }
+
+    /// Renders a formatted text `diff` as HTML.
+    pub fn render(&self, diff: &[Diff]) -> String {
+        self.0.render(diff, escape_html, false)
+    }
+}
+
+impl Default for HtmlRenderer {
+    fn default() -> Self {
+        HtmlRenderer::new()
+            .tag_with_attribute("link", "a", "href")
+            .tag("bold", "b")
+            .tag("italic", "i")
+            .tag("underline", "u")
+            .tag("strike", "s")
+            .tag("code", "code")
+    }
+}
+
+/// Renderer producing Markdown out of a formatted text. Characters which have special meaning in
+/// Markdown are escaped with a backslash, unless they're part of a code span. Embeds are not
+/// rendered unless [MarkdownRenderer::embed] rule has been provided.
+///
+/// [MarkdownRenderer::default] maps `bold`, `italic` and `strike` attributes onto `**`, `_`
+/// and `~~` delimiters respectively, `code` attribute is rendered as a code span, while `link`
+/// attribute is rendered as an inline link with an attribute value used as its destination.
+/// Markdown has no notion of underline, therefore `underline` attribute is ignored.
+///
+/// # Example
+///
+/// ```rust
+/// use yrs::Doc;
+/// use yrs::types::text_render::MarkdownRenderer;
+/// use yrs::types::Attrs;
+/// use lib0::any::Any;
+///
+/// let doc = Doc::new();
+/// let mut txn = doc.transact();
+/// let text = txn.get_text("text");
+/// text.insert(&mut txn, 0, "1*2 = 2 ");
+/// let bold = Attrs::from([("bold".into(), Any::Bool(true))]);
+/// text.insert_with_attributes(&mut txn, 8, "indeed", bold);
+///
+/// let renderer = MarkdownRenderer::default();
+/// assert_eq!(text.to_markdown(&mut txn, &renderer), "1\\*2 = 2 **indeed**");
+/// ```
+pub struct MarkdownRenderer(Rules);
+
+impl MarkdownRenderer {
+    /// Creates a new renderer without any formatting rules. All formatting attributes will be
+    /// ignored.
+    pub fn new() -> Self {
+        MarkdownRenderer(Rules::default())
+    }
+
+    /// Renders text formatted with a given `attr` by surrounding it with a provided `delimiter`,
+    /// eg. `**` for bold text.
+    pub fn delimiter<K: Into<Rc<str>>>(mut self, attr: K, delimiter: &str) -> Self {
+        let delimiter = delimiter.to_string();
+        self.0.push(attr, false, move |_| {
+            Some((delimiter.clone(), delimiter.clone()))
+        });
+        self
+    }
+
+    /// Renders text formatted with a given `attr` as a code span. Text inside of a code span is
+    /// not escaped, instead it's surrounded with a backtick fence longer than any sequence of
+    /// backticks it contains.
+    pub fn code<K: Into<Rc<str>>>(mut self, attr: K) -> Self {
+        // fences are chosen once the whole content of a code span is known
+        self.0
+            .push(attr, true, |_| Some((String::new(), String::new())));
+        self
+    }
+
+    /// Renders text formatted with a given `attr` as an inline link. Value of formatting
+    /// attribute is used as a link destination.
+    pub fn link<K: Into<Rc<str>>>(mut self, attr: K) -> Self {
+        self.0.push(attr, false, |value| {
+            let href = attribute_string(value);
+            let mut close = String::from("](<");
+            for c in href.chars() {
+                if matches!(c, '<' | '>' | '\\') {
+                    close.push('\\');
+                }
+                close.push(c);
+            }
+            close.push_str(">)");
+            Some(("[".to_string(), close))
+        });
+        self
+    }
+
+    /// Renders text formatted with a given `attr` using an opening and closing markup returned
+    /// by a provided function. Returned markup is not escaped. If `None` is returned, formatting
+    /// is ignored.
+    pub fn format<K, F>(mut self, attr: K, f: F) -> Self
+    where
+        K: Into<Rc<str>>,
+        F: Fn(&Any) -> Option<(String, String)> + 'static,
+    {
+        self.0.push(attr, false, f);
+        self
+    }
+
+    /// Renders embedded values using a provided function. Returned markup is not escaped.
+    pub fn embed<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Value) -> String + 'static,
+    {
+        self.0.embed = Some(Box::new(f));
+        self
+    }
+
+    /// Renders a formatted text `diff` as Markdown.
+    pub fn render(&self, diff: &[Diff]) -> String {
+        self.0.render(diff, escape_markdown, true)
+    }
+}
+
+impl Default for MarkdownRenderer {
+    fn default() -> Self {
+        MarkdownRenderer::new()
+            .link("link")
+            .delimiter("bold", "**")
+            .delimiter("italic", "_")
+            .delimiter("strike", "~~")
+            .code("code")
+    }
+}
+
+/// Escapes characters which have special meaning in HTML text and attribute values.
+pub fn escape_html(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Escapes characters which have special meaning in Markdown inline content. Characters which
+/// could start a block (eg. a list item or a heading) are escaped when they're placed at the
+/// beginning of a line, with the line start determined using content already written to `out`.
+pub fn escape_markdown(s: &str, out: &mut String) {
+    let line = &out[out.rfind('\n').map(|i| i + 1).unwrap_or(0)..];
+    let mut line_start = line.chars().all(|c| c == ' ');
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '~' | '#' | '|' | '!'
+        ) || (line_start && matches!(c, '-' | '+' | '='))
+        {
+            out.push('\\');
+        } else if line_start && c.is_ascii_digit() {
+            // ordered list item marker, eg. `1.` or `1)`
+            out.push(c);
+            while let Some(&digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                out.push(digit);
+                chars.next();
+            }
+            if let Some(&marker) = chars.peek().filter(|c| matches!(c, '.' | ')')) {
+                out.push('\\');
+                out.push(marker);
+                chars.next();
+            }
+            line_start = false;
+            continue;
+        }
+        out.push(c);
+        line_start = c == '\n' || (line_start && c == ' ');
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::text_render::{HtmlRenderer, MarkdownRenderer};
+    use crate::types::{Attrs, Value};
+    use crate::Doc;
+    use lib0::any::Any;
+
+    fn attrs(json: &str) -> Attrs {
+        match Any::from_json(json).unwrap() {
+            Any::Map(map) => map.into_iter().map(|(k, v)| (k.into(), v)).collect(),
+            other => panic!("expected object, got {}", other),
+        }
+    }
+
+    #[test]
+    fn render_html() {
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let text = txn.get_xml_text("text");
+        text.insert(&mut txn, 0, "a < b & ");
+        text.insert_with_attributes(&mut txn, 8, "bold ", attrs(r#"{"bold":true}"#));
+        text.insert_with_attributes(
+            &mut txn,
+            13,
+            "both",
+            attrs(r#"{"bold":true,"italic":true}"#),
+        );
+        text.insert_with_attributes(
+            &mut txn,
+            17,
+            " link",
+            attrs(r#"{"link":"https://x.io/?a=1&b=\"2\"","custom":1}"#),
+        );
+        text.insert_embed(
+            &mut txn,
+            22,
+            Any::from_json(r#"{"image":"a.png"}"#).unwrap(),
+        );
+
+        let html = text.to_html(&mut txn, &HtmlRenderer::default());
+        assert_eq!(
+            html,
+            "a &lt; b &amp; <b>bold <i>both</i></b><a href=\"https://x.io/?a=1&amp;b=&quot;2&quot;\"> link</a>"
+        );
+
+        let renderer = HtmlRenderer::new()
+            .tag("italic", "em")
+            .tag("bold", "strong")
+            .format("custom", |value| {
+                Some((
+                    format!("<span data-custom=\"{}\">", value),
+                    "</span>".into(),
+                ))
+            })
+            .embed(|value| match value {
+                Value::Any(Any::Map(map)) => match map.get("image") {
+                    Some(Any::String(src)) => format!("<img src=\"{}\">", src),
+                    _ => String::new(),
+                },
+                _ => String::new(),
+            });
+        let html = text.to_html(&mut txn, &renderer);
+        assert_eq!(
+            html,
+            "a &lt; b &amp; <strong>bold </strong><em><strong>both</strong></em><span data-custom=\"1\"> link<img src=\"a.png\"></span>"
+        );
+    }
+
+    #[test]
+    fn render_markdown() {
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let text = txn.get_text("text");
+        text.insert(&mut txn, 0, "see *this* ");
+        text.insert_with_attributes(&mut txn, 11, "bold ", attrs(r#"{"bold":true}"#));
+        text.insert_with_attributes(
+            &mut txn,
+            16,
+            "and italic ",
+            attrs(r#"{"bold":true,"italic":true}"#),
+        );
+        text.insert_with_attributes(&mut txn, 27, "a_b*c", attrs(r#"{"code":true}"#));
+        text.insert_with_attributes(&mut txn, 32, " or ", Attrs::new());
+        text.insert_with_attributes(
+            &mut txn,
+            36,
+            "[docs]",
+            attrs(r#"{"link":"https://x.io/a b","underline":true}"#),
+        );
+
+        let md = text.to_markdown(&mut txn, &MarkdownRenderer::default());
+        assert_eq!(
+            md,
+            "see \\*this\\* **bold _and italic_** `a_b*c` or [\\[docs\\]](<https://x.io/a b>)"
+        );
+    }
+
+    #[test]
+    fn render_markdown_code_with_backticks() {
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let text = txn.get_text("text");
+        text.insert(&mut txn, 0, "run ");
+        text.insert_with_attributes(&mut txn, 4, "a ``b`` c", attrs(r#"{"code":true}"#));
+        text.insert_with_attributes(&mut txn, 13, " or ", Attrs::new());
+        text.insert_with_attributes(&mut txn, 17, "`x`", attrs(r#"{"code":true}"#));
+
+        let md = text.to_markdown(&mut txn, &MarkdownRenderer::default());
+        assert_eq!(md, "run ```a ``b`` c``` or `` `x` ``");
+    }
+
+    #[test]
+    fn render_markdown_block_markers() {
+        let doc = Doc::with_client_id(1);
+        let mut txn = doc.transact();
+        let text = txn.get_text("text");
+        text.insert(&mut txn, 0, "- a\n+ b\n1. c\n  12) d\n# e\n===\nf - 1. g");
+
+        let md = text.to_markdown(&mut txn, &MarkdownRenderer::default());
+        assert_eq!(
+            md,
+            "\\- a\n\\+ b\n1\\. c\n  12\\) d\n\\# e\n\\===\nf - 1. g"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! Patterns used to search for and replace fragments of [Text](crate::Text) content.
+//! See [Text::find](crate::Text::find) and [Text::replace_all](crate::Text::replace_all).
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+use std::ops::Range;
+
+/// A pattern, which can be searched for within a text content. It's implemented for string
+/// literals and - with the `regex` feature enabled - for regular expressions.
+pub trait Pattern {
+    /// Returns byte ranges of all non-overlapping matches of this pattern within a given
+    /// `haystack`, ordered by their position.
+    fn find_matches(&self, haystack: &str) -> Vec<Range<usize>>;
+
+    /// Returns byte ranges of all non-overlapping matches of this pattern within a given
+    /// `haystack` together with a text that should replace them. By default a `replacement`
+    /// is used as it is.
+    fn replacements(&self, haystack: &str, replacement: &str) -> Vec<(Range<usize>, String)> {
+        self.find_matches(haystack)
+            .into_iter()
+            .map(|range| (range, replacement.to_string()))
+            .collect()
+    }
+}
+
+/// Literal patterns match exact occurrences of a string. Empty string doesn't match anything.
+impl Pattern for str {
+    fn find_matches(&self, haystack: &str) -> Vec<Range<usize>> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        haystack
+            .match_indices(self)
+            .map(|(start, m)| start..start + m.len())
+            .collect()
+    }
+}
+
+impl Pattern for String {
+    fn find_matches(&self, haystack: &str) -> Vec<Range<usize>> {
+        self.as_str().find_matches(haystack)
+    }
+}
+
+/// Regular expression patterns support capture group references (eg. `$1` or `${name}`) in
+/// replacement strings. See [regex::Captures::expand].
+#[cfg(feature = "regex")]
+impl Pattern for Regex {
+    fn find_matches(&self, haystack: &str) -> Vec<Range<usize>> {
+        self.find_iter(haystack).map(|m| m.range()).collect()
+    }
+
+    fn replacements(&self, haystack: &str, replacement: &str) -> Vec<(Range<usize>, String)> {
+        self.captures_iter(haystack)
+            .map(|captures| {
+                let mut expanded = String::new();
+                captures.expand(replacement, &mut expanded);
+                (captures.get(0).unwrap().range(), expanded)
+            })
+            .collect()
+    }
+}
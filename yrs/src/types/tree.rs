@@ -0,0 +1,697 @@
+use crate::block::{Block, ItemContent, Prelim};
+use crate::event::Subscription;
+use crate::types::{Branch, BranchPtr, Observers, Path, Value, TYPE_REFS_TREE};
+use crate::{Array, Map, PrelimMap, SubscriptionId, Transaction, ID};
+use lib0::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Formatter;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+/// Tree is a shared type used to store hierarchies of nodes (eg. outliners or file trees), which
+/// can be concurrently rearranged by different peers. Every node is identified by an [ID] and has
+/// a [Map] attached to it, which can be used to store node's data.
+///
+/// All nodes are stored in a single sequence, while each node keeps a reference to its parent.
+/// Node's position among its siblings is determined by its position in that sequence, so that
+/// moving nodes makes use of the same move operations that are used by [Array::move_to]. This way
+/// nodes concurrently moved by different peers are never duplicated nor lost.
+///
+/// Conflicts are resolved as follows:
+///
+/// - When the same node was concurrently moved under different parents, the last-write-wins
+///   principle is used to pick one of them.
+/// - When nodes were concurrently moved under each other, forming a cycle, the node which has
+///   been moved most recently is placed at the root level of a tree. Since peer clocks are not
+///   comparable with each other, every move is stamped with a Lamport timestamp greater than
+///   timestamps of all moves known to the moving peer. This way a move made after observing
+///   another one always wins, while ties between concurrent moves are resolved by picking
+///   the one made by a peer with a higher client ID.
+/// - Nodes created or moved concurrently under a node, which has been deleted, are deleted
+///   together with it.
+#[repr(transparent)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Tree(BranchPtr);
+
+impl Tree {
+    /// Returns a number of nodes stored within current tree.
+    pub fn len(&self) -> u32 {
+        self.layout().parents.len() as u32
+    }
+
+    /// Checks if current tree has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.layout().parents.is_empty()
+    }
+
+    /// Checks if a node identified by a given `node` ID exists in current tree.
+    pub fn contains(&self, node: &ID) -> bool {
+        self.layout().parents.contains_key(node)
+    }
+
+    /// Returns an ID of a parent of a given `node`. Returns `None` if node is placed at the root
+    /// level of current tree or if it doesn't exist.
+    pub fn parent(&self, node: &ID) -> Option<ID> {
+        self.layout().parents.get(node).cloned().flatten()
+    }
+
+    /// Returns IDs of all children of a given `parent` node in their order. If `parent` is `None`,
+    /// nodes placed at the root level of current tree are returned.
+    pub fn children(&self, parent: Option<&ID>) -> Vec<ID> {
+        let layout = self.layout();
+        layout.children(parent).map(|(_, id)| id).collect()
+    }
+
+    /// Returns a [Map] used to store the data of a given `node`, or `None` if node doesn't exist.
+    pub fn get(&self, node: &ID) -> Option<Map> {
+        let layout = self.layout();
+        let index = layout.index_of(node)?;
+        Some(layout.nodes[index as usize].1.clone())
+    }
+
+    /// Creates a new node and inserts it under a given `parent` at provided `index` among its
+    /// children. If `parent` is `None`, node is inserted at the root level of current tree.
+    /// Returns an ID of a newly created node.
+    ///
+    /// This method panics if `index` is greater than a number of `parent` node's children.
+    pub fn create_node(
+        &self,
+        txn: &mut Transaction,
+        parent: Option<&ID>,
+        index: u32,
+    ) -> Result<ID, TreeError> {
+        let layout = self.layout();
+        if let Some(parent) = parent {
+            if layout.index_of(parent).is_none() {
+                return Err(TreeError::NotFound(*parent));
+            }
+        }
+        let index = layout.insert_index(parent, index, None);
+        let array = Array::from(self.0);
+        array.insert(txn, index, PrelimMap::<Any>::new());
+        let node = match array.get(index) {
+            Some(Value::YMap(map)) => *map.as_ref().item.unwrap().id(),
+            _ => panic!("Defect: created tree node not found"),
+        };
+        self.set_parent(txn, &node, parent, layout.clock + 1);
+        Ok(node)
+    }
+
+    /// Moves a given `node` together with all of its descendants under a `new_parent` node at
+    /// provided `index` among its children. If `new_parent` is `None`, node is moved to the root
+    /// level of current tree.
+    ///
+    /// Returns an error if any of the nodes doesn't exist or if `new_parent` is the moved node
+    /// itself or any of its descendants. This method panics if `index` is greater than a number
+    /// of `new_parent` node's children.
+    pub fn move_node(
+        &self,
+        txn: &mut Transaction,
+        node: &ID,
+        new_parent: Option<&ID>,
+        index: u32,
+    ) -> Result<(), TreeError> {
+        let layout = self.layout();
+        let source = layout.index_of(node).ok_or(TreeError::NotFound(*node))?;
+        if let Some(parent) = new_parent {
+            if layout.index_of(parent).is_none() {
+                return Err(TreeError::NotFound(*parent));
+            } else if parent == node || layout.is_ancestor(node, parent) {
+                return Err(TreeError::Cycle(*parent));
+            }
+        }
+        let target = layout.insert_index(new_parent, index, Some(node));
+        Array::from(self.0).move_to(txn, source, target);
+        self.set_parent(txn, node, new_parent, layout.clock + 1);
+        Ok(())
+    }
+
+    /// Deletes a given `node` together with all of its descendants. Returns `false` if node
+    /// didn't exist.
+    pub fn delete_node(&self, txn: &mut Transaction, node: &ID) -> bool {
+        let layout = self.layout();
+        if layout.index_of(node).is_none() {
+            return false;
+        }
+        let array = Array::from(self.0);
+        let map = Map::from(self.0);
+        // remove from the back, so that indexes of remaining nodes are not affected
+        for (index, (id, _)) in layout.nodes.iter().enumerate().rev() {
+            if id == node || layout.is_ancestor(node, id) {
+                array.remove(txn, index as u32);
+                map.remove(txn, &node_key(id));
+            }
+        }
+        true
+    }
+
+    /// Converts current tree into JSON-like array of its root level nodes. Each node is
+    /// represented as an object with its `id`, `data` and an array of its `children`.
+    pub fn to_json(&self) -> Any {
+        fn to_json(layout: &Layout, parent: Option<&ID>) -> Any {
+            let mut result = Vec::new();
+            for (index, id) in layout.children(parent) {
+                let data = &layout.nodes[index as usize].1;
+                let mut node = HashMap::new();
+                node.insert("id".to_string(), Any::String(node_key(&id).into()));
+                node.insert("data".to_string(), data.to_json());
+                node.insert("children".to_string(), to_json(layout, Some(&id)));
+                result.push(Any::Map(Box::new(node)));
+            }
+            Any::Array(result.into_boxed_slice())
+        }
+
+        to_json(&self.layout(), None)
+    }
+
+    /// Subscribes a given callback to be triggered whenever nodes of current tree are created,
+    /// moved or deleted. A callback is triggered whenever a transaction gets committed. This
+    /// function does not trigger if changes have been made to the data of tree nodes.
+    ///
+    /// All tree changes can be tracked by using [TreeEvent::changes] method.
+    ///
+    /// Returns an [Observer] which, when dropped, will unsubscribe current callback.
+    pub fn observe<F>(&mut self, f: F) -> Subscription<TreeEvent>
+    where
+        F: Fn(&Transaction, &TreeEvent) + 'static,
+    {
+        if let Observers::Tree(eh) = self.0.observers.get_or_insert_with(Observers::tree) {
+            eh.subscribe(f)
+        } else {
+            panic!("Observed collection is of different type") //TODO: this should be Result::Err
+        }
+    }
+
+    /// Unsubscribes a previously subscribed event callback identified by given `subscription_id`.
+    pub fn unobserve(&mut self, subscription_id: SubscriptionId) {
+        if let Some(Observers::Tree(eh)) = self.0.observers.as_mut() {
+            eh.unsubscribe(subscription_id);
+        }
+    }
+
+    /// Assigns a `parent` to a given `node`. Assignment is stamped with a given Lamport `clock`,
+    /// used to resolve cycles formed by concurrent moves.
+    fn set_parent(&self, txn: &mut Transaction, node: &ID, parent: Option<&ID>, clock: u64) {
+        let parent = match parent {
+            Some(parent) => Any::String(node_key(parent).into()),
+            None => Any::Null,
+        };
+        let value = Any::Array(vec![parent, Any::BigInt(clock as i64)].into_boxed_slice());
+        Map::from(self.0).insert(txn, node_key(node), value);
+    }
+
+    /// Returns all nodes stored in current tree together with their resolved parents. Layout is
+    /// cached by a tree until any of its nodes or their parents change.
+    fn layout(&self) -> Rc<Layout> {
+        let mut branch = self.0;
+        if let Some(layout) = branch.tree_layout.as_ref() {
+            return layout.clone();
+        }
+        let layout = Rc::new(self.resolve_layout());
+        branch.tree_layout = Some(layout.clone());
+        layout
+    }
+
+    fn resolve_layout(&self) -> Layout {
+        let mut nodes = Vec::new();
+        let mut parents = HashMap::new();
+        let mut stamps = HashMap::new();
+        let mut clock = 0;
+        for value in Array::from(self.0).iter() {
+            if let Value::YMap(map) = value {
+                if let Some(ptr) = map.as_ref().item {
+                    let id = *ptr.id();
+                    let key = node_key(&id);
+                    let (parent, stamp) = match self.0.get(&key) {
+                        Some(Value::Any(any)) => parse_assignment(&any),
+                        _ => (None, 0),
+                    };
+                    let client = self
+                        .0
+                        .map
+                        .get(key.as_str())
+                        .map_or(0, |ptr| ptr.id().client);
+                    clock = clock.max(stamp);
+                    stamps.insert(id, (stamp, client));
+                    parents.insert(id, parent);
+                    nodes.push((id, map));
+                }
+            }
+        }
+
+        let mut visible: HashMap<ID, bool> = HashMap::with_capacity(nodes.len());
+        for (id, _) in nodes.iter() {
+            let mut path = Vec::new();
+            let mut current = *id;
+            let is_visible = loop {
+                if let Some(&v) = visible.get(&current) {
+                    break v;
+                } else if let Some(pos) = path.iter().position(|n| n == &current) {
+                    // nodes have been concurrently moved under each other: the one, which parent
+                    // has been assigned most recently, is moved to the root level
+                    let latest = path[pos..]
+                        .iter()
+                        .max_by_key(|n| stamps[*n])
+                        .cloned()
+                        .unwrap();
+                    parents.insert(latest, None);
+                    path.clear();
+                    current = *id;
+                    continue;
+                }
+                match parents.get(&current) {
+                    // parent of a previous node has been deleted
+                    None => break false,
+                    Some(None) => {
+                        path.push(current);
+                        break true;
+                    }
+                    Some(Some(parent)) => {
+                        path.push(current);
+                        current = *parent;
+                    }
+                }
+            };
+            for node in path {
+                visible.insert(node, is_visible);
+            }
+        }
+        parents.retain(|id, _| visible.get(id) == Some(&true));
+
+        let mut indexes = HashMap::with_capacity(parents.len());
+        let mut children: HashMap<Option<ID>, Vec<u32>> = HashMap::new();
+        for (index, (id, _)) in nodes.iter().enumerate() {
+            if let Some(parent) = parents.get(id) {
+                indexes.insert(*id, index as u32);
+                children.entry(*parent).or_default().push(index as u32);
+            }
+        }
+
+        Layout {
+            nodes,
+            parents,
+            indexes,
+            children,
+            clock,
+        }
+    }
+}
+
+impl AsRef<Branch> for Tree {
+    fn as_ref(&self) -> &Branch {
+        self.0.deref()
+    }
+}
+
+impl AsMut<Branch> for Tree {
+    fn as_mut(&mut self) -> &mut Branch {
+        self.0.deref_mut()
+    }
+}
+
+impl From<BranchPtr> for Tree {
+    fn from(inner: BranchPtr) -> Self {
+        Tree(inner)
+    }
+}
+
+/// Nodes of a [Tree] in order of their underlying sequence, together with their resolved parents.
+/// Nodes, which are not reachable from the root of a tree, have no parents assigned.
+pub(crate) struct Layout {
+    nodes: Vec<(ID, Map)>,
+    parents: HashMap<ID, Option<ID>>,
+    /// Indexes of reachable nodes in a sequence of tree nodes.
+    indexes: HashMap<ID, u32>,
+    /// Indexes of reachable nodes in a sequence of tree nodes, grouped by their parents.
+    children: HashMap<Option<ID>, Vec<u32>>,
+    /// The highest Lamport timestamp of parent assignments made so far.
+    clock: u64,
+}
+
+impl Layout {
+    /// Returns an index of a given `node` in a sequence of tree nodes, or `None` if node is not
+    /// reachable from the root of a tree.
+    fn index_of(&self, node: &ID) -> Option<u32> {
+        self.indexes.get(node).cloned()
+    }
+
+    /// Returns children of a given `parent` together with their indexes in a sequence of tree
+    /// nodes.
+    fn children<'a>(&'a self, parent: Option<&ID>) -> impl Iterator<Item = (u32, ID)> + 'a {
+        let indexes = self.children.get(&parent.cloned());
+        indexes
+            .into_iter()
+            .flatten()
+            .map(move |&index| (index, self.nodes[index as usize].0))
+    }
+
+    /// Checks if `ancestor` node can be found on a path from the root down to a given `node`.
+    fn is_ancestor(&self, ancestor: &ID, node: &ID) -> bool {
+        let mut current = self.parents.get(node).cloned().flatten();
+        while let Some(parent) = current {
+            if &parent == ancestor {
+                return true;
+            }
+            current = self.parents.get(&parent).cloned().flatten();
+        }
+        false
+    }
+
+    /// Returns an index in a sequence of tree nodes, at which a node should be placed in order to
+    /// become a child of a given `parent` at provided `index`. `exclude` node is not counted as
+    /// one of the children.
+    fn insert_index(&self, parent: Option<&ID>, index: u32, exclude: Option<&ID>) -> u32 {
+        let siblings: Vec<u32> = self
+            .children(parent)
+            .filter(|(_, id)| Some(id) != exclude)
+            .map(|(i, _)| i)
+            .collect();
+        let i = index as usize;
+        if i < siblings.len() {
+            siblings[i]
+        } else if i == siblings.len() {
+            match (siblings.last(), parent) {
+                (Some(last), _) => last + 1,
+                (None, Some(parent)) => self.index_of(parent).unwrap() + 1,
+                (None, None) => self.nodes.len() as u32,
+            }
+        } else {
+            panic!(
+                "Index {} is outside of the range of tree node children",
+                index
+            )
+        }
+    }
+}
+
+fn node_key(id: &ID) -> String {
+    format!("{}#{}", id.client, id.clock)
+}
+
+fn parse_node_key(key: &str) -> Option<ID> {
+    let (client, clock) = key.split_once('#')?;
+    Some(ID::new(client.parse().ok()?, clock.parse().ok()?))
+}
+
+/// Parses a parent assignment of a node into its parent ID and a Lamport timestamp.
+fn parse_assignment(value: &Any) -> (Option<ID>, u64) {
+    match value {
+        Any::Array(values) => match values.as_ref() {
+            [Any::String(key), Any::BigInt(clock)] => (parse_node_key(key), *clock as u64),
+            [_, Any::BigInt(clock)] => (None, *clock as u64),
+            _ => (None, 0),
+        },
+        _ => (None, 0),
+    }
+}
+
+/// A preliminary tree. It can be used to insert an empty [Tree] into another Yrs collection,
+/// such as [Array] or [Map].
+pub struct PrelimTree;
+
+impl Prelim for PrelimTree {
+    fn into_content(self, _txn: &mut Transaction) -> (ItemContent, Option<Self>) {
+        let inner = Branch::new(TYPE_REFS_TREE, None);
+        (ItemContent::Type(inner), None)
+    }
+
+    fn integrate(self, _txn: &mut Transaction, _inner_ref: BranchPtr) {}
+}
+
+/// Error returned by [Tree] operations.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreeError {
+    /// Node with a given ID doesn't exist or it has been deleted.
+    NotFound(ID),
+    /// Node cannot be moved under a given node, as it's the moved node itself or one of its
+    /// descendants.
+    Cycle(ID),
+}
+
+impl std::fmt::Display for TreeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TreeError::NotFound(id) => write!(f, "tree node {} not found", id),
+            TreeError::Cycle(id) => write!(f, "tree node cannot be moved under {}", id),
+        }
+    }
+}
+
+impl std::error::Error for TreeError {}
+
+/// Change of a single [Tree] node, reported by [TreeEvent::changes].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreeChange {
+    /// Node has been created.
+    Created(ID),
+    /// Node has been moved under another parent or to another position among its siblings.
+    Moved(ID),
+    /// Node has been deleted.
+    Deleted(ID),
+}
+
+/// Event generated by [Tree::observe] method. Emitted during transaction commit phase.
+pub struct TreeEvent {
+    pub(crate) current_target: BranchPtr,
+    target: Tree,
+    keys: HashSet<Option<Rc<str>>>,
+}
+
+impl TreeEvent {
+    pub(crate) fn new(branch_ref: BranchPtr, key_changes: HashSet<Option<Rc<str>>>) -> Self {
+        TreeEvent {
+            target: Tree::from(branch_ref),
+            current_target: branch_ref,
+            keys: key_changes,
+        }
+    }
+
+    /// Returns a [Tree] instance which emitted this event.
+    pub fn target(&self) -> &Tree {
+        &self.target
+    }
+
+    /// Returns a path from root type down to [Tree] instance which emitted this event.
+    pub fn path(&self) -> Path {
+        Branch::path(self.current_target, self.target.0)
+    }
+
+    /// Returns a list of nodes created, moved or deleted within a transaction, which emitted this
+    /// event, ordered by node IDs.
+    pub fn changes(&self, txn: &Transaction) -> Vec<TreeChange> {
+        let mut changes = HashMap::new();
+        for key in self.keys.iter().flatten() {
+            if let Some(node) = parse_node_key(key) {
+                let change = match txn.store().blocks.get_block(&node).as_deref() {
+                    Some(Block::Item(item)) if item.is_deleted() => {
+                        if txn.has_added(&node) {
+                            continue;
+                        }
+                        TreeChange::Deleted(node)
+                    }
+                    Some(Block::Item(_)) if txn.has_added(&node) => TreeChange::Created(node),
+                    Some(Block::Item(_)) => TreeChange::Moved(node),
+                    _ => continue,
+                };
+                changes.insert(node, change);
+            }
+        }
+
+        // nodes reordered among their siblings
+        let mut ptr = self.target.0.start;
+        while let Some(Block::Item(item)) = ptr.as_deref() {
+            if let (ItemContent::Type(_), Some(moved)) = (&item.content, item.moved) {
+                if !item.is_deleted() && txn.has_added(moved.id()) {
+                    changes.entry(item.id).or_insert(TreeChange::Moved(item.id));
+                }
+            }
+            ptr = item.right;
+        }
+
+        let mut changes: Vec<_> = changes.into_iter().collect();
+        changes.sort_by_key(|(id, _)| *id);
+        changes.into_iter().map(|(_, change)| change).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::exchange_updates;
+    use crate::types::tree::{TreeChange, TreeError};
+    use crate::Doc;
+    use lib0::any::Any;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn create_move_delete() {
+        let doc = Doc::with_client_id(1);
+        let tree = doc.transact().get_tree("tree");
+        let mut txn = doc.transact();
+        let a = tree.create_node(&mut txn, None, 0).unwrap();
+        let b = tree.create_node(&mut txn, None, 1).unwrap();
+        let a1 = tree.create_node(&mut txn, Some(&a), 0).unwrap();
+        let a2 = tree.create_node(&mut txn, Some(&a), 1).unwrap();
+        let a0 = tree.create_node(&mut txn, Some(&a), 0).unwrap();
+        tree.get(&a1)
+            .unwrap()
+            .insert(&mut txn, "name", "a1".to_string());
+
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree.children(None), vec![a, b]);
+        assert_eq!(tree.children(Some(&a)), vec![a0, a1, a2]);
+        assert_eq!(tree.parent(&a1), Some(a));
+
+        // reorder among siblings
+        tree.move_node(&mut txn, &a2, Some(&a), 0).unwrap();
+        assert_eq!(tree.children(Some(&a)), vec![a2, a0, a1]);
+
+        // move to another parent
+        tree.move_node(&mut txn, &a1, Some(&b), 0).unwrap();
+        assert_eq!(tree.children(Some(&a)), vec![a2, a0]);
+        assert_eq!(tree.children(Some(&b)), vec![a1]);
+        assert_eq!(
+            tree.get(&a1).unwrap().get("name"),
+            Some(Any::String("a1".into()).into())
+        );
+
+        // node cannot be moved under its own descendant
+        assert_eq!(
+            tree.move_node(&mut txn, &b, Some(&a1), 0),
+            Err(TreeError::Cycle(a1))
+        );
+
+        assert!(tree.delete_node(&mut txn, &a));
+        assert!(!tree.contains(&a2));
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.children(None), vec![b]);
+        assert_eq!(
+            tree.move_node(&mut txn, &a0, None, 0),
+            Err(TreeError::NotFound(a0))
+        );
+    }
+
+    #[test]
+    fn concurrent_cycle() {
+        let d1 = Doc::with_client_id(1);
+        let t1 = d1.transact().get_tree("tree");
+        let d2 = Doc::with_client_id(2);
+        let t2 = d2.transact().get_tree("tree");
+
+        let (a, b) = {
+            let mut txn = d1.transact();
+            let a = t1.create_node(&mut txn, None, 0).unwrap();
+            let b = t1.create_node(&mut txn, None, 1).unwrap();
+            (a, b)
+        };
+        exchange_updates(&[&d1, &d2]);
+
+        t1.move_node(&mut d1.transact(), &a, Some(&b), 0).unwrap();
+        t2.move_node(&mut d2.transact(), &b, Some(&a), 0).unwrap();
+        exchange_updates(&[&d1, &d2]);
+
+        assert_eq!(t1.len(), 2);
+        assert_eq!(t1.to_json(), t2.to_json());
+        assert_eq!(t1.children(None).len(), 1);
+        assert_eq!(t2.children(None), t1.children(None));
+    }
+
+    #[test]
+    fn concurrent_cycle_lamport_order() {
+        let d1 = Doc::with_client_id(1);
+        let t1 = d1.transact().get_tree("tree");
+        let d2 = Doc::with_client_id(2);
+        let t2 = d2.transact().get_tree("tree");
+
+        let (a, b, c) = {
+            let mut txn = d1.transact();
+            let a = t1.create_node(&mut txn, None, 0).unwrap();
+            let b = t1.create_node(&mut txn, None, 1).unwrap();
+            let c = t1.create_node(&mut txn, None, 2).unwrap();
+            (a, b, c)
+        };
+        exchange_updates(&[&d1, &d2]);
+
+        // peer 2 advances its clock without moving any nodes
+        {
+            let mut txn = d2.transact();
+            let data = t2.get(&c).unwrap();
+            for i in 0..50 {
+                data.insert(&mut txn, i.to_string(), i);
+            }
+        }
+        exchange_updates(&[&d1, &d2]);
+
+        // peer 1 moves nodes more times, so its concurrent move is stamped with a higher timestamp
+        t1.move_node(&mut d1.transact(), &c, Some(&a), 0).unwrap();
+        t1.move_node(&mut d1.transact(), &c, None, 2).unwrap();
+        t1.move_node(&mut d1.transact(), &a, Some(&b), 0).unwrap();
+        t2.move_node(&mut d2.transact(), &b, Some(&a), 0).unwrap();
+        exchange_updates(&[&d1, &d2]);
+
+        assert_eq!(t1.to_json(), t2.to_json());
+        assert_eq!(t1.parent(&a), None);
+        assert_eq!(t1.parent(&b), Some(a));
+        assert_eq!(t2.children(Some(&a)), vec![b]);
+    }
+
+    #[test]
+    fn concurrent_reparent() {
+        let d1 = Doc::with_client_id(1);
+        let t1 = d1.transact().get_tree("tree");
+        let d2 = Doc::with_client_id(2);
+        let t2 = d2.transact().get_tree("tree");
+
+        let (a, b, c) = {
+            let mut txn = d1.transact();
+            let a = t1.create_node(&mut txn, None, 0).unwrap();
+            let b = t1.create_node(&mut txn, None, 1).unwrap();
+            let c = t1.create_node(&mut txn, None, 2).unwrap();
+            (a, b, c)
+        };
+        exchange_updates(&[&d1, &d2]);
+
+        t1.move_node(&mut d1.transact(), &c, Some(&a), 0).unwrap();
+        t2.move_node(&mut d2.transact(), &c, Some(&b), 0).unwrap();
+        exchange_updates(&[&d1, &d2]);
+
+        assert_eq!(t1.len(), 3);
+        assert_eq!(t1.to_json(), t2.to_json());
+        let in_a = t1.children(Some(&a)).len();
+        let in_b = t1.children(Some(&b)).len();
+        assert_eq!(in_a + in_b, 1);
+    }
+
+    #[test]
+    fn observe_changes() {
+        let d1 = Doc::with_client_id(1);
+        let t1 = d1.transact().get_tree("tree");
+        let d2 = Doc::with_client_id(2);
+        let mut t2 = d2.transact().get_tree("tree");
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_c = changes.clone();
+        let _sub = t2.observe(move |txn, e| *changes_c.borrow_mut() = e.changes(txn));
+
+        let (a, b) = {
+            let mut txn = d1.transact();
+            let a = t1.create_node(&mut txn, None, 0).unwrap();
+            let b = t1.create_node(&mut txn, None, 1).unwrap();
+            (a, b)
+        };
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(
+            changes.take(),
+            vec![TreeChange::Created(a), TreeChange::Created(b)]
+        );
+
+        t1.move_node(&mut d1.transact(), &b, None, 0).unwrap();
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(changes.take(), vec![TreeChange::Moved(b)]);
+        assert_eq!(t2.children(None), vec![b, a]);
+
+        t1.delete_node(&mut d1.transact(), &a);
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(changes.take(), vec![TreeChange::Deleted(a)]);
+    }
+}
@@ -0,0 +1,669 @@
+use crate::block::{Block, BlockPtr, Item, ItemContent, Prelim};
+use crate::block_iter::BlockIter;
+use crate::event::Subscription;
+use crate::moving::RelativePosition;
+use crate::types::{
+    Branch, BranchPtr, Observers, Path, TypePtr, Value, TYPE_REFS_TEXT, TYPE_REFS_WEAK,
+    TYPE_REFS_XML_TEXT,
+};
+use crate::updates::decoder::{Decode, Decoder};
+use crate::updates::encoder::{Encode, Encoder};
+use crate::{SubscriptionId, Transaction, ID};
+use lib0::any::Any;
+use lib0::error::Error;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// Weak link is a shared type, which doesn't store any content on its own. Instead it references
+/// (quotes) either a single entry of a [Map] or a range of elements of an [Array] or a [Text]
+/// stored somewhere else in the same document.
+///
+/// Linked content is referenced by identifiers of its blocks, which means that:
+///
+/// - Reading a weak link always returns the current state of the linked content.
+/// - Quoted elements are followed when they are moved to another position, while elements
+///   concurrently inserted in between quoted ones become part of the quote.
+/// - Deleted elements are no longer returned. Link to a map entry follows the latest value
+///   assigned under its key.
+///
+/// Weak links can be created with [Map::link], [Array::quote] or [Text::quote] and inserted into
+/// other shared collections just like any other preliminary value. Observers of a weak link are
+/// notified whenever the linked content changes.
+///
+/// [Map::link]: crate::Map::link
+/// [Array::quote]: crate::Array::quote
+/// [Text::quote]: crate::Text::quote
+#[repr(transparent)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WeakLink(BranchPtr);
+
+impl WeakLink {
+    /// Returns a source of the content referenced by this weak link.
+    pub fn source(&self) -> &LinkSource {
+        self.0
+            .link_source
+            .as_deref()
+            .expect("Defect: weak link has no source")
+    }
+
+    /// Returns a value referenced by this weak link. For links to [Map] entries it's the current
+    /// value stored under the linked key, while for quoted ranges it's their first element.
+    /// Returns `None` if linked content has been deleted.
+    pub fn try_deref_value(&self) -> Option<Value> {
+        let store = self.0.store.as_ref()?;
+        let ptr = store.blocks.get_block(&self.source().quote_start.id)?;
+        if let Block::Item(item) = ptr.deref() {
+            if let (TypePtr::Branch(parent), Some(key)) = (&item.parent, &item.parent_sub) {
+                return parent.get(key);
+            }
+        }
+        self.unquote().next()
+    }
+
+    /// Returns an iterator over all non-deleted elements of a quoted range. Characters of quoted
+    /// [Text] are returned as separate string values.
+    pub fn unquote(&self) -> Unquote<'_> {
+        Unquote::new(self)
+    }
+
+    /// Returns a string built from a quoted range of a [Text]. Non-string elements (like embeds)
+    /// are skipped.
+    ///
+    /// [Text]: crate::Text
+    pub fn get_string(&self) -> String {
+        let mut s = String::new();
+        for value in self.unquote() {
+            if let Value::Any(Any::String(chunk)) = value {
+                s.push_str(&chunk);
+            }
+        }
+        s
+    }
+
+    /// Converts linked content into JSON-like representation: links to [Map] entries return their
+    /// current value, quoted [Text] ranges return a string, while other quoted ranges return an
+    /// array of their elements.
+    ///
+    /// [Map]: crate::Map
+    /// [Text]: crate::Text
+    pub fn to_json(&self) -> Any {
+        let source = self.source_item();
+        if let Some((parent, parent_sub)) = source {
+            if parent_sub {
+                return match self.try_deref_value() {
+                    Some(value) => value.to_json(),
+                    None => Any::Undefined,
+                };
+            } else if let TYPE_REFS_TEXT | TYPE_REFS_XML_TEXT = parent.type_ref() {
+                return Any::String(self.get_string().into_boxed_str());
+            }
+        }
+        let values: Vec<Any> = self.unquote().map(Value::to_json).collect();
+        Any::Array(values.into_boxed_slice())
+    }
+
+    /// Returns a collection containing linked content, or `None` if it cannot be found.
+    pub(crate) fn quoted_branch(&self) -> Option<BranchPtr> {
+        self.source_item().map(|(parent, _)| parent)
+    }
+
+    /// Returns a parent collection of linked content together with a flag informing if this link
+    /// references a map entry.
+    fn source_item(&self) -> Option<(BranchPtr, bool)> {
+        let store = self.0.store.as_ref()?;
+        let ptr = store.blocks.get_block(&self.source().quote_start.id)?;
+        if let Block::Item(item) = ptr.deref() {
+            let parent = *item.parent.as_branch()?;
+            Some((parent, item.parent_sub.is_some()))
+        } else {
+            None
+        }
+    }
+
+    /// Subscribes a given callback to be triggered whenever content referenced by current weak
+    /// link is changed. Callbacks are triggered during transaction commit.
+    ///
+    /// Returns a [Subscription] which, when dropped, will unsubscribe current callback.
+    pub fn observe<F>(&mut self, f: F) -> Subscription<WeakEvent>
+    where
+        F: Fn(&Transaction, &WeakEvent) + 'static,
+    {
+        if let Observers::Weak(eh) = self.0.observers.get_or_insert_with(Observers::weak) {
+            eh.subscribe(f)
+        } else {
+            panic!("Observed collection is of different type") //TODO: this should be Result::Err
+        }
+    }
+
+    /// Unsubscribes a previously subscribed event callback identified by given `subscription_id`.
+    pub fn unobserve(&mut self, subscription_id: SubscriptionId) {
+        if let Some(Observers::Weak(eh)) = self.0.observers.as_mut() {
+            eh.unsubscribe(subscription_id);
+        }
+    }
+
+    /// Checks if any of the blocks referenced by current weak link have been inserted or deleted
+    /// within a given transaction.
+    pub(crate) fn is_changed(&self, txn: &Transaction) -> bool {
+        let source = self.source();
+        let ptr = match txn.store().blocks.get_block(&source.quote_start.id) {
+            Some(ptr) => ptr,
+            None => return false,
+        };
+        if let Block::Item(item) = ptr.deref() {
+            let parent = match item.parent.as_branch() {
+                Some(&parent) => TypePtr::Branch(parent),
+                None => return false,
+            };
+            if let Some(key) = &item.parent_sub {
+                return match txn.changed.get(&parent) {
+                    Some(keys) => keys.contains(&Some(key.clone())),
+                    None => false,
+                };
+            } else if !txn.changed.contains_key(&parent) {
+                return false;
+            }
+        }
+        let mut ptr = Some(ptr);
+        while let Some(Block::Item(item)) = ptr.as_deref() {
+            let is_last = source.ends_within(item);
+            let end = &source.quote_end;
+            let is_quoted = !(is_last && end.assoc && end.id.clock == item.id.clock);
+            if is_quoted && (txn.has_added(&item.id) || txn.has_deleted(&item.id)) {
+                return true;
+            } else if is_last {
+                break;
+            }
+            ptr = item.right;
+        }
+        false
+    }
+}
+
+impl AsRef<Branch> for WeakLink {
+    fn as_ref(&self) -> &Branch {
+        self.0.deref()
+    }
+}
+
+impl AsMut<Branch> for WeakLink {
+    fn as_mut(&mut self) -> &mut Branch {
+        self.0.deref_mut()
+    }
+}
+
+impl From<BranchPtr> for WeakLink {
+    fn from(inner: BranchPtr) -> Self {
+        WeakLink(inner)
+    }
+}
+
+/// Identifiers of the first and the last element referenced by a [WeakLink].
+///
+/// Link sources are encoded as a flags byte followed by IDs of the first and - unless both are
+/// the same - the last quoted element, each written as a pair of variable length client and
+/// clock numbers. Flags have following bits set:
+///
+/// - `0b001` if start and end IDs differ,
+/// - `0b010` if a quote starts at the element its start ID points to, rather than right after it,
+/// - `0b100` if a quote ends right before the element its end ID points to, rather than right
+///   after it.
+///
+/// This encoding is specific to Yrs: released versions of Yjs don't define weak links, therefore
+/// updates containing them cannot be decoded by Yjs clients.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LinkSource {
+    pub(crate) quote_start: RelativePosition,
+    pub(crate) quote_end: RelativePosition,
+}
+
+impl LinkSource {
+    pub(crate) fn new(quote_start: RelativePosition, quote_end: RelativePosition) -> Self {
+        LinkSource {
+            quote_start,
+            quote_end,
+        }
+    }
+
+    /// Creates a link source for a range of `len` elements starting at a given `index` of
+    /// a `branch` sequence.
+    pub(crate) fn quote(txn: &mut Transaction, branch: BranchPtr, index: u32, len: u32) -> Self {
+        if len == 0 || index + len > branch.content_len() {
+            panic!(
+                "Range {}..{} is outside of the range of a sequence",
+                index,
+                index + len
+            );
+        }
+        let quote_start = match Self::element_id(txn, branch, index) {
+            Some((_, id)) => RelativePosition::create(id, true),
+            None => panic!("Index {} is outside of the range of a sequence", index),
+        };
+        let quote_end = match Self::element_id(txn, branch, index + len) {
+            Some((_, id)) => RelativePosition::create(id, true),
+            None => {
+                // quote reaches the end of a sequence, there's no element to associate with
+                match Self::element_id(txn, branch, index + len - 1) {
+                    Some((ptr, _)) => RelativePosition::create(ptr.last_id(), false),
+                    None => panic!(
+                        "Index {} is outside of the range of a sequence",
+                        index + len
+                    ),
+                }
+            }
+        };
+        LinkSource::new(quote_start, quote_end)
+    }
+
+    /// Returns a block and an identifier of an element found at a given `index` of a `branch`
+    /// sequence.
+    fn element_id(txn: &mut Transaction, branch: BranchPtr, index: u32) -> Option<(BlockPtr, ID)> {
+        let mut walker = BlockIter::new(branch);
+        if !walker.try_forward(txn, index) || walker.finished() {
+            return None;
+        }
+        let ptr = walker.next_item()?;
+        let mut id = *ptr.id();
+        let rel = walker.rel();
+        id.clock += match ptr.deref() {
+            Block::Item(item) => match &item.content {
+                ItemContent::String(s) => s.block_offset(rel, txn.store().options.offset_kind),
+                _ => rel,
+            },
+            Block::GC(_) => rel,
+        };
+        Some((ptr, id))
+    }
+
+    /// Checks if this link source is a single map entry or a range of one element.
+    pub fn is_single(&self) -> bool {
+        self.quote_start.id == self.quote_end.id
+    }
+
+    /// Checks if the last quoted element is a part of a given `item`.
+    fn ends_within(&self, item: &Item) -> bool {
+        item.contains(&self.quote_end.id)
+    }
+}
+
+impl std::fmt::Display for LinkSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.quote_start, self.quote_end)
+    }
+}
+
+impl Encode for LinkSource {
+    fn encode<E: Encoder>(&self, encoder: &mut E) {
+        let is_single = self.is_single();
+        let flags = {
+            let mut b = 0;
+            if !is_single {
+                b |= 0b0000_0001
+            }
+            if self.quote_start.assoc {
+                b |= 0b0000_0010
+            }
+            if self.quote_end.assoc {
+                b |= 0b0000_0100
+            }
+            b
+        };
+        encoder.write_var(flags);
+        encoder.write_var(self.quote_start.id.client);
+        encoder.write_var(self.quote_start.id.clock);
+        if !is_single {
+            encoder.write_var(self.quote_end.id.client);
+            encoder.write_var(self.quote_end.id.clock);
+        }
+    }
+}
+
+impl Decode for LinkSource {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, Error> {
+        let flags: u8 = decoder.read_var()?;
+        let is_single = flags & 0b0000_0001 == 0;
+        let start_assoc = flags & 0b0000_0010 != 0;
+        let end_assoc = flags & 0b0000_0100 != 0;
+        let start_id = ID::new(decoder.read_var()?, decoder.read_var()?);
+        let end_id = if is_single {
+            start_id
+        } else {
+            ID::new(decoder.read_var()?, decoder.read_var()?)
+        };
+        Ok(LinkSource::new(
+            RelativePosition::create(start_id, start_assoc),
+            RelativePosition::create(end_id, end_assoc),
+        ))
+    }
+}
+
+/// Iterator over elements referenced by a [WeakLink]. Created by [WeakLink::unquote].
+pub struct Unquote<'a> {
+    source: Option<LinkSource>,
+    current: Option<BlockPtr>,
+    offset: u32,
+    buf: std::vec::IntoIter<Value>,
+    _marker: PhantomData<&'a WeakLink>,
+}
+
+impl<'a> Unquote<'a> {
+    fn new(link: &'a WeakLink) -> Self {
+        let source = link.source().clone();
+        let start = &source.quote_start;
+        let mut current = None;
+        let mut offset = 0;
+        if let Some(store) = link.0.store.as_ref() {
+            if let Some(ptr) = store.blocks.get_block(&start.id) {
+                current = Some(ptr);
+                offset = start.id.clock - ptr.id().clock;
+                if !start.assoc {
+                    // quote starts right after an associated element
+                    offset += 1;
+                }
+            }
+        }
+        Unquote {
+            source: Some(source),
+            current,
+            offset,
+            buf: Vec::new().into_iter(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns values of a current block within quoted range and moves to the next one.
+    fn next_block(&mut self) -> Option<Vec<Value>> {
+        let source = self.source.as_ref()?;
+        let ptr = self.current.take()?;
+        if let Block::Item(item) = ptr.deref() {
+            let from = self.offset;
+            let mut to = item.len();
+            if source.ends_within(item) {
+                let end = &source.quote_end;
+                to = end.id.clock - item.id.clock + if end.assoc { 0 } else { 1 };
+                self.source = None;
+            } else {
+                self.current = item.right;
+                self.offset = 0;
+            }
+            if from < to && !item.is_deleted() && item.is_countable() {
+                Some(slice_content(&item.content, from, to))
+            } else {
+                Some(Vec::new())
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Iterator for Unquote<'a> {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.buf.next() {
+                return Some(value);
+            }
+            self.buf = self.next_block()?.into_iter();
+        }
+    }
+}
+
+/// Returns values of elements stored in a given `content` between `from` (inclusive) and `to`
+/// (exclusive) offsets counted in block clock units.
+fn slice_content(content: &ItemContent, from: u32, to: u32) -> Vec<Value> {
+    match content {
+        ItemContent::String(s) => {
+            let mut values = Vec::new();
+            let mut clock = 0;
+            for c in s.as_str().chars() {
+                if clock >= from && clock < to {
+                    values.push(Value::Any(Any::String(c.to_string().into_boxed_str())));
+                }
+                clock += c.len_utf16() as u32;
+            }
+            values
+        }
+        other => {
+            let mut values = other.get_content();
+            values.truncate(to as usize);
+            values.drain(0..(from as usize).min(values.len()));
+            values
+        }
+    }
+}
+
+/// A preliminary weak link. It can be inserted into another shared collection to create
+/// a [WeakLink] there.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WeakPrelim {
+    source: LinkSource,
+}
+
+impl WeakPrelim {
+    pub(crate) fn new(source: LinkSource) -> Self {
+        WeakPrelim { source }
+    }
+
+    /// Returns a source of the content referenced by this preliminary weak link.
+    pub fn source(&self) -> &LinkSource {
+        &self.source
+    }
+}
+
+impl Prelim for WeakPrelim {
+    fn into_content(self, _txn: &mut Transaction) -> (ItemContent, Option<Self>) {
+        let mut inner = Branch::new(TYPE_REFS_WEAK, None);
+        inner.link_source = Some(Box::new(self.source));
+        (ItemContent::Type(inner), None)
+    }
+
+    fn integrate(self, _txn: &mut Transaction, _inner_ref: BranchPtr) {}
+}
+
+/// Event generated by [WeakLink::observe] method. Emitted during transaction commit phase, when
+/// content referenced by a weak link has been changed.
+pub struct WeakEvent {
+    pub(crate) current_target: BranchPtr,
+    target: WeakLink,
+}
+
+impl WeakEvent {
+    pub(crate) fn new(branch_ref: BranchPtr) -> Self {
+        WeakEvent {
+            target: WeakLink::from(branch_ref),
+            current_target: branch_ref,
+        }
+    }
+
+    /// Returns a [WeakLink] instance which emitted this event.
+    pub fn target(&self) -> &WeakLink {
+        &self.target
+    }
+
+    /// Returns a path from root type down to [WeakLink] instance which emitted this event.
+    pub fn path(&self) -> Path {
+        Branch::path(self.current_target, self.target.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::exchange_updates;
+    use crate::types::{Branch, BranchPtr, Value};
+    use crate::{Doc, WeakLink};
+    use lib0::any::Any;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn link_at(doc: &Doc, index: u32) -> WeakLink {
+        let links = doc.transact().get_array("links");
+        links.get(index).and_then(Value::to_yweak).unwrap()
+    }
+
+    #[test]
+    fn map_link() {
+        let d1 = Doc::with_client_id(1);
+        let m1 = d1.transact().get_map("map");
+        let l1 = d1.transact().get_array("links");
+        let d2 = Doc::with_client_id(2);
+        let m2 = d2.transact().get_map("map");
+
+        m1.insert(&mut d1.transact(), "a", "value");
+        assert!(m1.link("b").is_none());
+        let prelim = m1.link("a").unwrap();
+        l1.insert(&mut d1.transact(), 0, prelim);
+
+        let link = link_at(&d1, 0);
+        assert_eq!(link.try_deref_value(), Some(Value::from("value")));
+
+        exchange_updates(&[&d1, &d2]);
+        let link2 = link_at(&d2, 0);
+        assert_eq!(link2.try_deref_value(), Some(Value::from("value")));
+
+        // link follows the latest value under linked key
+        m2.insert(&mut d2.transact(), "a", "value2");
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(link.try_deref_value(), Some(Value::from("value2")));
+        assert_eq!(link.to_json(), Any::String("value2".into()));
+
+        m1.remove(&mut d1.transact(), "a");
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(link.try_deref_value(), None);
+        assert_eq!(link2.try_deref_value(), None);
+    }
+
+    #[test]
+    fn array_quote() {
+        let d1 = Doc::with_client_id(1);
+        let a1 = d1.transact().get_array("array");
+        let l1 = d1.transact().get_array("links");
+        let d2 = Doc::with_client_id(2);
+        let a2 = d2.transact().get_array("array");
+
+        a1.insert_range(&mut d1.transact(), 0, [1, 2, 3, 4, 5]);
+        {
+            let mut txn = d1.transact();
+            let prelim = a1.quote(&mut txn, 1, 3);
+            l1.insert(&mut txn, 0, prelim);
+        }
+        let link = link_at(&d1, 0);
+        let values: Vec<_> = link.unquote().collect();
+        assert_eq!(values, vec![2.into(), 3.into(), 4.into()]);
+
+        exchange_updates(&[&d1, &d2]);
+        let link2 = link_at(&d2, 0);
+        assert_eq!(link2.to_json(), vec![2, 3, 4].into());
+
+        // elements inserted in between quoted ones become part of the quote, while elements
+        // inserted outside of it don't
+        a2.insert(&mut d2.transact(), 2, 10);
+        a2.insert(&mut d2.transact(), 0, 11);
+        a2.push_back(&mut d2.transact(), 12);
+        // deleted elements are no longer quoted
+        a1.remove(&mut d1.transact(), 3);
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(a1.to_json(), a2.to_json());
+        assert_eq!(link.to_json(), vec![2, 10, 3].into());
+        assert_eq!(link2.to_json(), vec![2, 10, 3].into());
+
+        // quoted elements are followed when moved
+        a1.move_to(&mut d1.transact(), 2, 0);
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(a2.to_json(), vec![2, 11, 1, 10, 3, 5, 12].into());
+        assert_eq!(link2.to_json(), vec![2, 10, 3].into());
+    }
+
+    #[test]
+    fn text_quote() {
+        let d1 = Doc::with_client_id(1);
+        let t1 = d1.transact().get_text("text");
+        let l1 = d1.transact().get_array("links");
+        let d2 = Doc::with_client_id(2);
+        let t2 = d2.transact().get_text("text");
+
+        t1.insert(&mut d1.transact(), 0, "hello 🌍 world");
+        {
+            let mut txn = d1.transact();
+            let prelim = t1.quote(&mut txn, 6, 6);
+            l1.insert(&mut txn, 0, prelim);
+        }
+        let link = link_at(&d1, 0);
+        assert_eq!(link.get_string(), "🌍 w");
+
+        exchange_updates(&[&d1, &d2]);
+        t2.insert(&mut d2.transact(), 11, "big ");
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(t1.to_string(), "hello 🌍 big world");
+        assert_eq!(link.get_string(), "🌍 big w");
+        assert_eq!(link.to_json(), Any::String("🌍 big w".into()));
+
+        // quote reaching the end of a text
+        {
+            let mut txn = d1.transact();
+            let prelim = t1.quote(&mut txn, 16, 4);
+            l1.insert(&mut txn, 1, prelim);
+        }
+        assert_eq!(link_at(&d1, 1).get_string(), "orld");
+    }
+
+    #[test]
+    fn observe_linked_changes() {
+        let doc = Doc::with_client_id(1);
+        let array = doc.transact().get_array("array");
+        let links = doc.transact().get_array("links");
+
+        array.insert_range(&mut doc.transact(), 0, [1, 2, 3, 4]);
+        {
+            let mut txn = doc.transact();
+            let prelim = array.quote(&mut txn, 1, 2);
+            links.insert(&mut txn, 0, prelim);
+        }
+        let mut link = link_at(&doc, 0);
+        let calls = Rc::new(Cell::new(0));
+        let c = calls.clone();
+        let _sub = link.observe(move |_, _| {
+            c.set(c.get() + 1);
+        });
+
+        // changes outside of quoted range are not reported
+        array.push_back(&mut doc.transact(), 5);
+        array.remove(&mut doc.transact(), 0);
+        assert_eq!(calls.get(), 0);
+
+        array.insert(&mut doc.transact(), 1, 10);
+        assert_eq!(calls.get(), 1);
+        array.remove(&mut doc.transact(), 0);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(link.to_json(), vec![10, 3].into());
+    }
+
+    #[test]
+    fn links_indexed_by_quoted_collection() {
+        let doc = Doc::with_client_id(1);
+        let array = doc.transact().get_array("array");
+        let map = doc.transact().get_map("map");
+        let links = doc.transact().get_array("links");
+
+        array.insert_range(&mut doc.transact(), 0, [1, 2, 3]);
+        map.insert(&mut doc.transact(), "a", 1);
+        {
+            let mut txn = doc.transact();
+            let prelim = array.quote(&mut txn, 0, 2);
+            links.insert(&mut txn, 0, prelim);
+            links.insert(&mut txn, 1, map.link("a").unwrap());
+        }
+        let quoting = |branch: &Branch| {
+            let txn = doc.transact();
+            let links = txn.store().weak_links.get(&BranchPtr::from(branch));
+            links.map_or(0, |links| links.len())
+        };
+        assert_eq!(quoting(array.as_ref()), 1);
+        assert_eq!(quoting(map.as_ref()), 1);
+
+        links.remove(&mut doc.transact(), 0);
+        assert_eq!(quoting(array.as_ref()), 0);
+        assert_eq!(quoting(map.as_ref()), 1);
+    }
+}
@@ -1,46 +1,68 @@
-use crate::block::{Block, Item, ItemContent, ItemPosition, Prelim};
+use crate::block::{Block, ItemContent, ItemPosition, Prelim};
+use crate::block_iter::BlockIter;
 use crate::event::Subscription;
+use crate::moving::{move_range, visible_parent};
+use crate::types::prosemirror::{self, ProseMirrorError};
 use crate::types::text::TextEvent;
+use crate::types::text_render::{HtmlRenderer, MarkdownRenderer};
+use crate::types::text_search::Pattern;
+use crate::types::xml_diff::{self, XmlDiff, XmlDiffError};
+use crate::types::xml_parser::{self, XmlNode, XmlParseError};
+use crate::types::xml_selector::{QuerySelectorAll, SelectorError, XmlSelector};
 use crate::types::{
     event_change_set, event_keys, Attrs, Branch, BranchPtr, Change, ChangeSet, Delta, Entries,
-    EntryChange, Map, Observers, Path, Text, TypePtr, Value, TYPE_REFS_XML_ELEMENT,
-    TYPE_REFS_XML_FRAGMENT, TYPE_REFS_XML_TEXT,
+    EntryChange, Map, MoveChange, Observers, Path, Text, TypePtr, Value, TYPE_REFS_XML_ELEMENT,
+    TYPE_REFS_XML_FRAGMENT, TYPE_REFS_XML_HOOK, TYPE_REFS_XML_TEXT,
 };
-use crate::{SubscriptionId, Transaction, ID};
+use crate::{OffsetKind, SubscriptionId, Transaction, ID};
 use lib0::any::Any;
 use std::cell::UnsafeCell;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::fmt::Write;
-use std::ops::{Deref, DerefMut};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, Range};
 use std::rc::Rc;
 
 /// An return type from XML elements retrieval methods. It's an enum of all supported values, that
-/// can be nested inside of [XmlElement]. These are other [XmlElement]s or [XmlText] values.
+/// can be nested inside of [XmlElement]. These are other [XmlElement]s, [XmlText] or [XmlHook]
+/// values.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Xml {
     Element(XmlElement),
     Text(XmlText),
+    Hook(XmlHook),
 }
 
 impl TryInto<XmlElement> for Xml {
-    type Error = XmlText;
+    type Error = Xml;
 
     fn try_into(self) -> Result<XmlElement, Self::Error> {
         match self {
             Xml::Element(xml) => Ok(xml),
-            Xml::Text(xml) => Err(xml),
+            other => Err(other),
         }
     }
 }
 
 impl TryInto<XmlText> for Xml {
-    type Error = XmlElement;
+    type Error = Xml;
 
     fn try_into(self) -> Result<XmlText, Self::Error> {
         match self {
             Xml::Text(xml) => Ok(xml),
-            Xml::Element(xml) => Err(xml),
+            other => Err(other),
+        }
+    }
+}
+
+impl TryInto<XmlHook> for Xml {
+    type Error = Xml;
+
+    fn try_into(self) -> Result<XmlHook, Self::Error> {
+        match self {
+            Xml::Hook(xml) => Ok(xml),
+            other => Err(other),
         }
     }
 }
@@ -51,6 +73,7 @@ impl From<BranchPtr> for Xml {
         match type_ref {
             TYPE_REFS_XML_ELEMENT => Xml::Element(XmlElement::from(inner)),
             TYPE_REFS_XML_TEXT => Xml::Text(XmlText::from(inner)),
+            TYPE_REFS_XML_HOOK => Xml::Hook(XmlHook::from(inner)),
             other => panic!("Unsupported type: {}", other),
         }
     }
@@ -78,6 +101,10 @@ impl XmlElement {
         self.0.inner()
     }
 
+    pub(crate) fn as_fragment(&self) -> &XmlFragment {
+        &self.0
+    }
+
     /// Converts current XML node into a textual representation. This representation if flat, it
     /// doesn't include any indentation.
     pub fn to_string(&self) -> String {
@@ -94,10 +121,8 @@ impl XmlElement {
             write!(&mut s, " \"{}\"=\"{}\"", k, v).unwrap();
         }
         write!(&mut s, ">").unwrap();
-        for i in inner.iter() {
-            for content in i.content.get_content() {
-                write!(&mut s, "{}", content.to_string()).unwrap();
-            }
+        for child in children(inner) {
+            write!(&mut s, "{}", node_to_string(child)).unwrap();
         }
         write!(&mut s, "</{}>", tag).unwrap();
         s
@@ -123,6 +148,17 @@ impl XmlElement {
     ) {
         let key = attr_name.into();
         let value = crate::block::PrelimString(attr_value.as_ref().into());
+        self.insert_attribute_value(txn, key, value)
+    }
+
+    /// Inserts an attribute entry into current XML element without converting its value into
+    /// a string first.
+    pub(crate) fn insert_attribute_value<V: Prelim>(
+        &self,
+        txn: &mut Transaction,
+        key: Rc<str>,
+        value: V,
+    ) {
         let pos = {
             let inner = self.inner();
             let left = inner.map.get(&key);
@@ -209,7 +245,8 @@ impl XmlElement {
     /// for node in html.successors() {
     ///     match node {
     ///         Xml::Element(elem) => println!("- {}", elem.tag()),
-    ///         Xml::Text(txt) => println!("- {}", txt.to_string())
+    ///         Xml::Text(txt) => println!("- {}", txt.to_string()),
+    ///         Xml::Hook(hook) => println!("- {}", hook.hook_name()),
     ///     }
     /// }
     /// /* will print:
@@ -225,6 +262,41 @@ impl XmlElement {
         self.0.iter()
     }
 
+    /// Returns an iterator over successors of a current XML element, which are matched by
+    /// a given CSS-like `selector`, eg. `"p > b[data-id]"`. See: [XmlFragment::query_selector_all].
+    pub fn query_selector_all(
+        &self,
+        selector: &str,
+    ) -> Result<QuerySelectorAll<'_>, SelectorError> {
+        self.0.query_selector_all(selector)
+    }
+
+    /// Returns a first successor of a current XML element, which is matched by a given CSS-like
+    /// `selector`. See: [XmlFragment::query_selector_all].
+    pub fn query_selector(&self, selector: &str) -> Result<Option<Xml>, SelectorError> {
+        self.0.query_selector(selector)
+    }
+
+    /// Checks if current XML element and `other` one are deeply equal, ie. they have the same
+    /// tag, attributes and their child nodes are deeply equal as well. Compared elements may
+    /// belong to different documents.
+    pub fn deep_eq(&self, other: &XmlElement) -> bool {
+        self.inner().name == other.inner().name && self.0.deep_eq(&other.0)
+    }
+
+    /// Computes a structural difference between current XML element and a `target` one, which
+    /// may belong to a different document. Tags of compared elements are not taken into account.
+    /// See: [XmlFragment::diff].
+    pub fn diff(&self, target: &XmlElement) -> XmlDiff {
+        self.0.diff(&target.0)
+    }
+
+    /// Applies a structural `diff` (computed from current XML element) onto current element.
+    /// See: [XmlFragment::apply_diff].
+    pub fn apply_diff(&self, txn: &mut Transaction, diff: &XmlDiff) -> Result<(), XmlDiffError> {
+        self.0.apply_diff(txn, diff)
+    }
+
     /// Inserts another [XmlElement] with a given tag `name` into a current one at the given `index`
     /// and returns it. If `index` is equal to `0`, new element will be inserted as a first child.
     /// If `index` is equal to length of current XML element, new element will be inserted as a last
@@ -248,6 +320,64 @@ impl XmlElement {
         self.0.insert_text(txn, index)
     }
 
+    /// Inserts a [XmlHook] with a given `name` into a current XML element at the given `index`
+    /// and returns it. This method will panic if `index` is greater than the length of current
+    /// XML element.
+    pub fn insert_hook<S: Into<Rc<str>>>(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        name: S,
+    ) -> XmlHook {
+        self.0.insert_hook(txn, index, name)
+    }
+
+    /// Parses a well-formed XML string and inserts its top-level nodes as children of current
+    /// element, starting at a given `index`. See: [XmlFragment::insert_xml_str].
+    pub fn insert_xml_str(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        xml: &str,
+    ) -> Result<u32, XmlParseError> {
+        self.0.insert_xml_str(txn, index, xml)
+    }
+
+    /// Parses an HTML string using lenient parsing rules and inserts its top-level nodes as
+    /// children of current element, starting at a given `index`.
+    /// See: [XmlFragment::insert_html_str].
+    pub fn insert_html_str(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        html: &str,
+    ) -> Result<u32, XmlParseError> {
+        self.0.insert_html_str(txn, index, html)
+    }
+
+    /// Converts current XML element into a ProseMirror node JSON, using element tag as a node
+    /// type and its attributes as node attributes. See: [XmlFragment::to_prosemirror_json].
+    pub fn to_prosemirror_json(&self) -> Any {
+        prosemirror::element_to_json(self)
+    }
+
+    /// Inserts child nodes of a given ProseMirror `node` JSON as children of current element,
+    /// starting at a given `index`. See: [XmlFragment::insert_prosemirror_json].
+    pub fn insert_prosemirror_json(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        node: &Any,
+    ) -> Result<u32, ProseMirrorError> {
+        self.0.insert_prosemirror_json(txn, index, node)
+    }
+
+    /// Replaces a child node at a given `index` with a new `value` or appends it, when `index` is
+    /// equal to the length of a current element. Returns `false` if `index` is out of bounds.
+    pub(crate) fn set_child<V: Prelim>(&self, txn: &mut Transaction, index: u32, value: V) -> bool {
+        self.0.set_child(txn, index, value)
+    }
+
     /// Removes a range (defined by `len`) of XML nodes from the current XML element, starting at
     /// the given `index`. Returns the result which may contain an error if a number of elements
     /// removed is lesser than the expected one provided in `len` parameter.
@@ -255,6 +385,73 @@ impl XmlElement {
         self.0.remove(txn, index, len)
     }
 
+    /// Moves a child XML node found at `source` index into `target` index position of a current
+    /// XML element. Moved node keeps its identity, so that concurrent changes made to it and its
+    /// successors are preserved.
+    ///
+    /// Example:
+    /// ```
+    /// use yrs::Doc;
+    /// let doc = Doc::new();
+    /// let mut txn = doc.transact();
+    /// let div = txn.get_xml_element("div");
+    /// div.push_elem_back(&mut txn, "p");
+    /// div.push_elem_back(&mut txn, "img");
+    /// div.push_elem_back(&mut txn, "span");
+    /// // move <span> before <p>
+    /// div.move_to(&mut txn, 2, 0);
+    /// assert_eq!(div.to_string(), "<UNDEFINED><span></span><p></p><img></img></UNDEFINED>");
+    /// ```
+    pub fn move_to(&self, txn: &mut Transaction, source: u32, target: u32) {
+        self.0.move_to(txn, source, target)
+    }
+
+    /// Moves all child XML nodes found within `start`..`end` indexes range (both side inclusive)
+    /// into new position pointed by `target` index. All nodes inserted concurrently by other peers
+    /// inside of moved range will be moved as well after synchronization.
+    ///
+    /// `assoc_start`/`assoc_end` flags are used to mark if ranges should include nodes that
+    /// might have been inserted concurrently at the edges of the range definition.
+    pub fn move_range_to(
+        &self,
+        txn: &mut Transaction,
+        start: u32,
+        assoc_start: bool,
+        end: u32,
+        assoc_end: bool,
+        target: u32,
+    ) {
+        self.0
+            .move_range_to(txn, start, assoc_start, end, assoc_end, target)
+    }
+
+    /// Moves a child XML node found at `source` index of a current XML element into another
+    /// `target` element at given `index` position. Unlike removing a node and inserting its copy,
+    /// moved node keeps its identity, so that concurrent changes made to it are preserved. See
+    /// [XmlElement::move_range_into] for compatibility notes.
+    pub fn move_into(&self, txn: &mut Transaction, source: u32, target: &XmlElement, index: u32) {
+        self.0.move_into(txn, source, &target.0, index)
+    }
+
+    /// Moves all child XML nodes found within `start`..`end` indexes range (both side inclusive)
+    /// of a current XML element into another `target` element at given `index` position.
+    /// Nodes inserted concurrently right before `start` or right after `end` are not part of
+    /// the moved range.
+    ///
+    /// Moves between different sequences are an extension of the Yjs move protocol: updates
+    /// produced by this method can only be interpreted by other Yrs peers, while Yjs clients
+    /// will keep the moved elements in their original sequence.
+    pub fn move_range_into(
+        &self,
+        txn: &mut Transaction,
+        start: u32,
+        end: u32,
+        target: &XmlElement,
+        index: u32,
+    ) {
+        self.0.move_range_into(txn, start, end, &target.0, index)
+    }
+
     /// Pushes a new [XmlElement] with a given tag `name` as the last child of a current one and
     /// returns it.
     pub fn push_elem_back<S: Into<Rc<str>>>(&self, txn: &mut Transaction, name: S) -> XmlElement {
@@ -295,12 +492,19 @@ impl XmlElement {
     where
         F: Fn(&Transaction, &XmlEvent) -> () + 'static,
     {
-        self.0.observe(f)
+        let mut inner = self.inner();
+        if let Observers::Xml(eh) = inner.observers.get_or_insert_with(Observers::xml) {
+            eh.subscribe(f)
+        } else {
+            panic!("Observed collection is of different type") //TODO: this should be Result::Err
+        }
     }
 
     /// Unsubscribes a previously subscribed event callback identified by given `subscription_id`.
     pub fn unobserve(&mut self, subscription_id: SubscriptionId) {
-        self.0.unobserve(subscription_id);
+        if let Some(Observers::Xml(eh)) = self.inner().observers.as_mut() {
+            eh.unsubscribe(subscription_id);
+        }
     }
 }
 
@@ -346,6 +550,10 @@ impl Into<XmlElement> for XmlFragment {
     }
 }
 
+/// XML fragment is a shared type representing a list of XML nodes, which - unlike [XmlElement] -
+/// has neither a tag name nor attributes of its own. It's used as a root-level container of
+/// rich text documents by editor bindings like y-prosemirror, which store their content in a
+/// `Y.XmlFragment`.
 #[repr(transparent)]
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct XmlFragment(BranchPtr);
@@ -360,15 +568,8 @@ impl XmlFragment {
     }
 
     pub fn first_child(&self) -> Option<Xml> {
-        let inner = self.inner();
-        let first = inner.first()?;
-        match &first.content {
-            ItemContent::Type(c) => {
-                let value = Xml::from(BranchPtr::from(c));
-                Some(value)
-            }
-            _ => None,
-        }
+        let first = child_at(self.inner(), 0)?;
+        Some(Xml::from(first))
     }
 
     pub fn parent(&self) -> Option<XmlElement> {
@@ -383,13 +584,61 @@ impl XmlFragment {
         TreeWalker::new(&self.0)
     }
 
+    /// Returns an iterator over successors of a current fragment (visited in depth-first order),
+    /// which are [XmlElement]s matched by a given CSS-like `selector`. Supported syntax includes
+    /// tag names, `*`, attribute filters (eg. `[data-id]` or `[data-id="1"]`), descendant
+    /// (`div b`) and child (`p > b`) combinators and comma-separated selector lists.
+    /// See [XmlSelector] for details.
+    ///
+    /// Returns an error if `selector` couldn't be parsed.
+    pub fn query_selector_all(
+        &self,
+        selector: &str,
+    ) -> Result<QuerySelectorAll<'_>, SelectorError> {
+        let selector = XmlSelector::parse(selector)?;
+        Ok(QuerySelectorAll::new(self.iter(), selector))
+    }
+
+    /// Returns a first successor of a current fragment, which is matched by a given CSS-like
+    /// `selector`. See: [XmlFragment::query_selector_all].
+    pub fn query_selector(&self, selector: &str) -> Result<Option<Xml>, SelectorError> {
+        Ok(self.query_selector_all(selector)?.next())
+    }
+
+    /// Checks if attributes and child nodes of current fragment and `other` one are deeply equal.
+    /// Compared fragments may belong to different documents.
+    pub fn deep_eq(&self, other: &XmlFragment) -> bool {
+        xml_diff::attributes(self.0) == xml_diff::attributes(other.0)
+            && xml_diff::snapshot_children(self.0) == xml_diff::snapshot_children(other.0)
+    }
+
+    /// Computes a structural difference between current fragment and a `target` one, which may
+    /// belong to a different document. Returned [XmlDiff] describes the minimal set of edits -
+    /// removed, inserted and changed child nodes together with their attribute and text changes -
+    /// necessary to make current fragment deeply equal to a `target`.
+    ///
+    /// Child nodes which are deeply equal in both fragments are matched first. Remaining nodes
+    /// are matched when they are [XmlText]s or [XmlElement]s with the same tag and compared
+    /// recursively. Text content is compared using individual characters and their formatting.
+    pub fn diff(&self, target: &XmlFragment) -> XmlDiff {
+        xml_diff::diff(self.0, target.0)
+    }
+
+    /// Applies a `diff` computed by [XmlFragment::diff] as a sequence of edits over current
+    /// fragment. Nodes which haven't been changed are left untouched, so that changes made
+    /// concurrently over them are preserved once peers synchronize.
+    ///
+    /// `diff` must be applied onto the same state of current fragment it was computed from.
+    /// Returns an error without changing current fragment if its structure doesn't match
+    /// the `diff`.
+    pub fn apply_diff(&self, txn: &mut Transaction, diff: &XmlDiff) -> Result<(), XmlDiffError> {
+        xml_diff::apply(self, txn, diff)
+    }
+
     pub fn to_string(&self) -> String {
         let mut s = String::new();
-        let inner = self.inner();
-        for i in inner.iter() {
-            for content in i.content.get_content() {
-                write!(&mut s, "{}", content.to_string()).unwrap();
-            }
+        for child in children(self.inner()) {
+            write!(&mut s, "{}", node_to_string(child)).unwrap();
         }
         s
     }
@@ -419,6 +668,117 @@ impl XmlFragment {
         }
     }
 
+    /// Inserts a [XmlHook] with a given `name` into a current fragment at the given `index` and
+    /// returns it. This method will panic if `index` is greater than the length of current
+    /// fragment.
+    pub fn insert_hook<S: Into<Rc<str>>>(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        name: S,
+    ) -> XmlHook {
+        let ptr = self.0.insert_at(txn, index, PrelimXml::Hook(name.into()));
+        let item = ptr.as_item().unwrap();
+        if let ItemContent::Type(inner) = &item.content {
+            XmlHook::from(BranchPtr::from(inner))
+        } else {
+            panic!("Defect: inserted XML hook returned primitive value block")
+        }
+    }
+
+    /// Parses a well-formed XML string and inserts its top-level nodes as children of current
+    /// fragment, starting at a given `index`. Parsed elements become nested [XmlElement]s (with
+    /// their attributes) and text content becomes [XmlText] nodes. Comments, processing
+    /// instructions and whitespace used only to indent the tags are skipped. Whitespace is kept
+    /// between inline elements and inside of `<pre>` and `<textarea>` elements.
+    ///
+    /// Returns a number of inserted top-level nodes. If `xml` couldn't be parsed, an error is
+    /// returned and current fragment is left unchanged.
+    pub fn insert_xml_str(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        xml: &str,
+    ) -> Result<u32, XmlParseError> {
+        let nodes = xml_parser::parse(xml, false)?;
+        Ok(self.insert_nodes(txn, index, nodes))
+    }
+
+    /// Works like [XmlFragment::insert_xml_str], but uses lenient HTML parsing rules: tag names
+    /// are case-insensitive, void elements (like `<br>`) don't need to be closed, attributes
+    /// don't need to be quoted or have values, HTML named entities are recognized and
+    /// unclosed elements are closed automatically.
+    pub fn insert_html_str(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        html: &str,
+    ) -> Result<u32, XmlParseError> {
+        let nodes = xml_parser::parse(html, true)?;
+        Ok(self.insert_nodes(txn, index, nodes))
+    }
+
+    /// Converts current XML fragment into a ProseMirror document JSON, using the same conventions
+    /// as y-prosemirror bindings: fragment becomes a `doc` node, [XmlElement]s become nodes with
+    /// a type equal to their tag and attributes as node `attrs`, while [XmlText]s become text
+    /// nodes with formatting attributes mapped onto `marks`.
+    pub fn to_prosemirror_json(&self) -> Any {
+        prosemirror::fragment_to_json(self)
+    }
+
+    /// Inserts child nodes (`content`) of a given ProseMirror `node` JSON - usually a `doc` -
+    /// into current fragment, starting at a given `index`. Consecutive text nodes are merged into
+    /// a single [XmlText], with their marks stored as formatting attributes.
+    ///
+    /// Returns a number of inserted top-level XML nodes. If `node` doesn't follow ProseMirror
+    /// document model, an error is returned and current fragment is left unchanged.
+    pub fn insert_prosemirror_json(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        node: &Any,
+    ) -> Result<u32, ProseMirrorError> {
+        prosemirror::insert_content(self, txn, index, node)
+    }
+
+    fn insert_nodes(&self, txn: &mut Transaction, index: u32, nodes: Vec<XmlNode>) -> u32 {
+        let mut i = index;
+        for node in nodes {
+            match node {
+                XmlNode::Element {
+                    tag,
+                    attributes,
+                    children,
+                } => {
+                    let elem = self.insert_elem(txn, i, tag);
+                    for (name, value) in attributes {
+                        elem.insert_attribute(txn, name, value);
+                    }
+                    elem.0.insert_nodes(txn, 0, children);
+                }
+                XmlNode::Text(content) => {
+                    let text = self.insert_text(txn, i);
+                    text.push(txn, &content);
+                }
+            }
+            i += 1;
+        }
+        i - index
+    }
+
+    /// Replaces a child node at a given `index` with a new `value` or appends it, when `index` is
+    /// equal to the length of a current fragment. Returns `false` if `index` is out of bounds.
+    pub(crate) fn set_child<V: Prelim>(&self, txn: &mut Transaction, index: u32, value: V) -> bool {
+        let len = self.len();
+        if index > len {
+            return false;
+        } else if index < len {
+            self.remove(txn, index, 1);
+        }
+        self.0.insert_at(txn, index, value);
+        true
+    }
+
     pub fn remove(&self, txn: &mut Transaction, index: u32, len: u32) {
         let removed = self.0.remove_at(txn, index, len);
         if removed != len {
@@ -426,6 +786,59 @@ impl XmlFragment {
         }
     }
 
+    pub fn move_to(&self, txn: &mut Transaction, source: u32, target: u32) {
+        if source == target || source + 1 == target {
+            // It doesn't make sense to move a node into the same position (it's basically a no-op).
+            return;
+        }
+        self.move_range_to(txn, source, true, source, false, target)
+    }
+
+    pub fn move_range_to(
+        &self,
+        txn: &mut Transaction,
+        start: u32,
+        assoc_start: bool,
+        end: u32,
+        assoc_end: bool,
+        target: u32,
+    ) {
+        if start <= target && target <= end {
+            // It doesn't make sense to move a range into the same range (it's basically a no-op).
+            return;
+        }
+        let assoc = (assoc_start, assoc_end);
+        move_range(txn, self.0, start..=end, assoc, self.0, target);
+    }
+
+    /// Moves a child XML node found at `source` index of a current XML fragment into another
+    /// `target` fragment at given `index` position. See [XmlFragment::move_range_into] for
+    /// compatibility notes.
+    pub fn move_into(&self, txn: &mut Transaction, source: u32, target: &XmlFragment, index: u32) {
+        self.move_range_into(txn, source, source, target, index)
+    }
+
+    /// Moves all child XML nodes found within `start`..`end` indexes range (both side inclusive)
+    /// of a current XML fragment into another `target` fragment at given `index` position.
+    ///
+    /// Moves between different sequences are an extension of the Yjs move protocol: updates
+    /// produced by this method can only be interpreted by other Yrs peers, while Yjs clients
+    /// will keep the moved elements in their original sequence.
+    pub fn move_range_into(
+        &self,
+        txn: &mut Transaction,
+        start: u32,
+        end: u32,
+        target: &XmlFragment,
+        index: u32,
+    ) {
+        if self.0 == target.0 {
+            self.move_range_to(txn, start, true, end, false, index)
+        } else {
+            move_range(txn, self.0, start..=end, (true, false), target.0, index);
+        }
+    }
+
     pub fn push_elem_back<S: Into<Rc<str>>>(&self, txn: &mut Transaction, name: S) -> XmlElement {
         let len = self.len();
         self.insert_elem(txn, len, name)
@@ -445,34 +858,44 @@ impl XmlFragment {
     }
 
     pub fn get<T: From<BranchPtr>>(&self, index: u32) -> Option<T> {
-        let inner = self.inner();
-        let (content, _) = inner.get_at(index)?;
-        if let ItemContent::Type(inner) = content {
-            let branch_ref: BranchPtr = inner.into();
-            Some(T::from(branch_ref))
-        } else {
-            None
-        }
+        let child = child_at(self.inner(), index)?;
+        Some(T::from(child))
     }
 
-    pub fn observe<F>(&mut self, f: F) -> Subscription<XmlEvent>
+    /// Subscribes a given callback to be triggered whenever child nodes of current XML fragment
+    /// are changed. A callback is triggered whenever a transaction gets committed. This function
+    /// does not trigger if changes have been observed by nested shared collections.
+    ///
+    /// Children node changes can be tracked by using [XmlFragmentEvent::delta] method.
+    ///
+    /// Returns an [Observer] which, when dropped, will unsubscribe current callback.
+    pub fn observe<F>(&mut self, f: F) -> Subscription<XmlFragmentEvent>
     where
-        F: Fn(&Transaction, &XmlEvent) -> () + 'static,
+        F: Fn(&Transaction, &XmlFragmentEvent) + 'static,
     {
-        if let Observers::Xml(eh) = self.0.observers.get_or_insert_with(Observers::xml) {
+        if let Observers::XmlFragment(eh) =
+            self.0.observers.get_or_insert_with(Observers::xml_fragment)
+        {
             eh.subscribe(f)
         } else {
             panic!("Observed collection is of different type") //TODO: this should be Result::Err
         }
     }
 
-    pub fn unobserve(&mut self, subscription_id: u32) {
-        if let Some(Observers::Xml(eh)) = self.0.observers.as_mut() {
+    /// Unsubscribes a previously subscribed event callback identified by given `subscription_id`.
+    pub fn unobserve(&mut self, subscription_id: SubscriptionId) {
+        if let Some(Observers::XmlFragment(eh)) = self.0.observers.as_mut() {
             eh.unsubscribe(subscription_id);
         }
     }
 }
 
+impl From<BranchPtr> for XmlFragment {
+    fn from(inner: BranchPtr) -> Self {
+        XmlFragment(inner)
+    }
+}
+
 impl AsRef<Branch> for XmlFragment {
     fn as_ref(&self) -> &Branch {
         self.0.deref()
@@ -487,23 +910,16 @@ impl AsMut<Branch> for XmlFragment {
 
 /// An iterator over [XmlElement] successors, working in a recursive depth-first manner.
 pub struct TreeWalker<'a> {
-    current: Option<&'a Item>,
-    root: TypePtr,
-    first_call: bool,
+    /// Remaining children of every visited level of the XML tree, with the deepest one on top.
+    stack: Vec<std::vec::IntoIter<BranchPtr>>,
+    _marker: PhantomData<&'a Branch>,
 }
 
 impl<'a> TreeWalker<'a> {
     fn new(root: &'a BranchPtr) -> Self {
-        let current = if let Some(Block::Item(item)) = root.start.as_deref() {
-            Some(item)
-        } else {
-            None
-        };
-
         TreeWalker {
-            current,
-            root: TypePtr::Branch(*root),
-            first_call: true,
+            stack: vec![children(*root).into_iter()],
+            _marker: PhantomData,
         }
     }
 }
@@ -513,59 +929,29 @@ impl<'a> Iterator for TreeWalker<'a> {
 
     /// Tree walker used depth-first search to move over the xml tree.
     fn next(&mut self) -> Option<Self::Item> {
-        let mut result = None;
-        let mut n = self.current.take();
-        if let Some(current) = n {
-            if !self.first_call || current.is_deleted() {
-                while {
-                    if let ItemContent::Type(t) = &current.content {
-                        let inner = t.as_ref();
-                        let type_ref = inner.type_ref();
-                        if !current.is_deleted()
-                            && (type_ref == TYPE_REFS_XML_ELEMENT
-                                || type_ref == TYPE_REFS_XML_FRAGMENT)
-                            && inner.start.is_some()
-                        {
-                            // walk down in the tree
-                            n = inner.start.as_ref().and_then(|ptr| ptr.as_item());
-                        } else {
-                            // walk right or up in the tree
-                            while let Some(current) = n {
-                                if let Some(right) = current.right.as_ref() {
-                                    n = right.as_item();
-                                    break;
-                                } else if current.parent == self.root {
-                                    n = None;
-                                } else {
-                                    let ptr = current.parent.as_branch().unwrap();
-                                    n = if let Some(Block::Item(item)) = ptr.item.as_deref() {
-                                        Some(item)
-                                    } else {
-                                        None
-                                    };
-                                }
-                            }
-                        }
-                    }
-                    if let Some(current) = n {
-                        current.is_deleted()
-                    } else {
-                        false
-                    }
-                } {}
-            }
-            self.first_call = false;
-            self.current = n;
-        }
-        if let Some(current) = self.current {
-            if let ItemContent::Type(t) = &current.content {
-                result = Some(Xml::from(BranchPtr::from(t)));
+        loop {
+            let level = self.stack.last_mut()?;
+            if let Some(node) = level.next() {
+                let type_ref = node.type_ref();
+                if type_ref == TYPE_REFS_XML_ELEMENT || type_ref == TYPE_REFS_XML_FRAGMENT {
+                    // walk down in the tree
+                    self.stack.push(children(node).into_iter());
+                }
+                return Some(Xml::from(node));
+            } else {
+                // walk up in the tree
+                self.stack.pop();
             }
         }
-        result
     }
 }
 
+/// XML hook is a map-like shared type, which can be nested inside of [XmlElement]s and
+/// [XmlFragment]s next to other XML nodes. It's a counterpart of Yjs `Y.XmlHook`: a named
+/// placeholder used by the applications to render custom content from the data stored inside
+/// of it. Hooks are not a part of textual XML representation.
+///
+/// In terms of conflict resolution, [XmlHook] follows the same rules as [Map].
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct XmlHook(Map);
 
@@ -574,6 +960,32 @@ impl XmlHook {
         XmlHook(map)
     }
 
+    fn inner(&self) -> BranchPtr {
+        BranchPtr::from(self.0.as_ref())
+    }
+
+    /// A name of a current hook, which is used by the application to recognize how its content
+    /// should be rendered.
+    pub fn hook_name(&self) -> &str {
+        let branch: &Branch = self.0.as_ref();
+        branch.name.as_deref().unwrap_or_default()
+    }
+
+    /// Returns a next sibling of a current XML hook, if any exists.
+    pub fn next_sibling(&self) -> Option<Xml> {
+        next_sibling(self.inner())
+    }
+
+    /// Returns a previous sibling of a current XML hook, if any exists.
+    pub fn prev_sibling(&self) -> Option<Xml> {
+        prev_sibling(self.inner())
+    }
+
+    /// Returns a parent XML element, current node can be found within.
+    pub fn parent(&self) -> Option<XmlElement> {
+        parent(self.inner())
+    }
+
     pub fn len(&self) -> u32 {
         self.0.len()
     }
@@ -613,6 +1025,38 @@ impl XmlHook {
     pub fn clear(&self, txn: &mut Transaction) {
         self.0.clear(txn)
     }
+
+    /// Subscribes a given callback to be triggered whenever entries of a current XML hook are
+    /// changed. A callback is triggered whenever a transaction gets committed.
+    ///
+    /// Returns an [Observer] which, when dropped, will unsubscribe current callback.
+    pub fn observe<F>(&mut self, f: F) -> Subscription<XmlHookEvent>
+    where
+        F: Fn(&Transaction, &XmlHookEvent) + 'static,
+    {
+        if let Observers::XmlHook(eh) = self
+            .inner()
+            .observers
+            .get_or_insert_with(Observers::xml_hook)
+        {
+            eh.subscribe(f)
+        } else {
+            panic!("Observed collection is of different type") //TODO: this should be Result::Err
+        }
+    }
+
+    /// Unsubscribes a previously subscribed event callback identified by given `subscription_id`.
+    pub fn unobserve(&mut self, subscription_id: SubscriptionId) {
+        if let Some(Observers::XmlHook(eh)) = self.inner().observers.as_mut() {
+            eh.unsubscribe(subscription_id);
+        }
+    }
+}
+
+impl AsRef<Branch> for XmlHook {
+    fn as_ref(&self) -> &Branch {
+        self.0.as_ref()
+    }
 }
 
 impl From<BranchPtr> for XmlHook {
@@ -621,6 +1065,12 @@ impl From<BranchPtr> for XmlHook {
     }
 }
 
+impl From<XmlHook> for Map {
+    fn from(hook: XmlHook) -> Self {
+        hook.0
+    }
+}
+
 impl Into<XmlHook> for Map {
     fn into(self) -> XmlHook {
         XmlHook(self)
@@ -657,6 +1107,25 @@ impl XmlText {
         self.0.to_string()
     }
 
+    /// Renders formatted contents of this XML text as HTML using a provided `renderer`.
+    /// See: [HtmlRenderer].
+    pub fn to_html(&self, txn: &mut Transaction, renderer: &HtmlRenderer) -> String {
+        self.0.to_html(txn, renderer)
+    }
+
+    /// Parses a given CommonMark `markdown` document and inserts it as a formatted text at
+    /// a given `index`. Returns a length of inserted content. See: [Text::insert_markdown].
+    #[cfg(feature = "markdown")]
+    pub fn insert_markdown(&self, txn: &mut Transaction, index: u32, markdown: &str) -> u32 {
+        self.0.insert_markdown(txn, index, markdown)
+    }
+
+    /// Renders formatted contents of this XML text as Markdown using a provided `renderer`.
+    /// See: [MarkdownRenderer].
+    pub fn to_markdown(&self, txn: &mut Transaction, renderer: &MarkdownRenderer) -> String {
+        self.0.to_markdown(txn, renderer)
+    }
+
     pub fn remove_attribute(&self, txn: &mut Transaction, attr_name: &str) {
         self.inner().remove(txn, attr_name);
     }
@@ -669,6 +1138,17 @@ impl XmlText {
     ) {
         let key = attr_name.into();
         let value = crate::block::PrelimString(attr_value.as_ref().into());
+        self.insert_attribute_value(txn, key, value)
+    }
+
+    /// Inserts an attribute entry into current XML text without converting its value into
+    /// a string first.
+    pub(crate) fn insert_attribute_value<V: Prelim>(
+        &self,
+        txn: &mut Transaction,
+        key: Rc<str>,
+        value: V,
+    ) {
         let pos = {
             let inner = self.inner();
             let left = inner.map.get(&key);
@@ -716,6 +1196,70 @@ impl XmlText {
         self.0.len()
     }
 
+    /// Converts an `offset` within current text contents from one offset kind to another.
+    /// See: [Text::convert_offset](crate::Text::convert_offset).
+    pub fn convert_offset(&self, offset: u32, from: OffsetKind, to: OffsetKind) -> u32 {
+        self.0.convert_offset(offset, from, to)
+    }
+
+    /// Returns a number of lines in current XML text. See: [Text::line_count].
+    pub fn line_count(&self) -> u32 {
+        self.0.line_count()
+    }
+
+    /// Converts a given `offset` into a zero-based line number and a column within that line.
+    /// See: [Text::offset_to_line_column].
+    pub fn offset_to_line_column(&self, offset: u32) -> Option<(u32, u32)> {
+        self.0.offset_to_line_column(offset)
+    }
+
+    /// Converts a zero-based `line` number and a `column` within that line into an offset within
+    /// current XML text. See: [Text::line_column_to_offset].
+    pub fn line_column_to_offset(&self, line: u32, column: u32) -> Option<u32> {
+        self.0.line_column_to_offset(line, column)
+    }
+
+    /// Returns contents of a given zero-based `line` without a trailing new line character.
+    /// See: [Text::line].
+    pub fn line(&self, line: u32) -> Option<String> {
+        self.0.line(line)
+    }
+
+    /// Returns ranges of all non-overlapping matches of a given `pattern` within current XML
+    /// text, expressed using the offset kind configured for the document. See: [Text::find].
+    pub fn find<P: Pattern + ?Sized>(&self, pattern: &P) -> Vec<Range<u32>> {
+        self.0.find(pattern)
+    }
+
+    /// Same as [XmlText::find], but returned ranges are expressed using a given offset `kind`.
+    /// See: [Text::convert_offset](crate::Text::convert_offset).
+    pub fn find_with_offset_kind<P: Pattern + ?Sized>(
+        &self,
+        pattern: &P,
+        kind: OffsetKind,
+    ) -> Vec<Range<u32>> {
+        self.0.find_with_offset_kind(pattern, kind)
+    }
+
+    /// Replaces all non-overlapping matches of a given `pattern` with a `replacement` string,
+    /// preserving their formatting. Returns a number of replaced matches.
+    /// See: [Text::replace_all].
+    pub fn replace_all<P: Pattern + ?Sized>(
+        &self,
+        txn: &mut Transaction,
+        pattern: &P,
+        replacement: &str,
+    ) -> u32 {
+        self.0.replace_all(txn, pattern, replacement)
+    }
+
+    /// Converts current XML text into a JSON array of ProseMirror text nodes. Formatting
+    /// attributes are mapped onto marks: attribute key becomes a mark type, while attribute value
+    /// (if it's an object) becomes mark `attrs`.
+    pub fn to_prosemirror_json(&self) -> Any {
+        Any::Array(prosemirror::text_to_json(self).into_boxed_slice())
+    }
+
     /// Inserts a `chunk` of text at a given `index`.
     /// If `index` is `0`, this `chunk` will be inserted at the beginning of a current text.
     /// If `index` is equal to current data structure length, this `chunk` will be appended at
@@ -723,6 +1267,20 @@ impl XmlText {
     ///
     /// This method will panic if provided `index` is greater than the length of a current text.
     pub fn insert(&self, txn: &mut Transaction, index: u32, content: &str) {
+        let kind = txn.store().options.offset_kind;
+        self.insert_with_offset_kind(txn, index, content, kind)
+    }
+
+    /// Same as [XmlText::insert], but `index` is expressed using a given offset `kind`.
+    /// See: [Text::convert_offset](crate::Text::convert_offset).
+    pub fn insert_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        content: &str,
+        kind: OffsetKind,
+    ) {
+        let (index, _) = self.0.map_range(txn, index, 0, kind);
         if let Some(mut pos) = self.0.find_position(txn, index) {
             pos.parent = TypePtr::Branch(self.inner());
             txn.create_item(&pos, crate::block::PrelimString(content.into()), None);
@@ -749,12 +1307,39 @@ impl XmlText {
         self.0.insert_with_attributes(txn, index, content, attrs);
     }
 
+    /// Same as [XmlText::insert_with_attributes], but `index` is expressed using a given
+    /// offset `kind`. See: [Text::convert_offset](crate::Text::convert_offset).
+    pub fn insert_with_attributes_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        content: &str,
+        attrs: Attrs,
+        kind: OffsetKind,
+    ) {
+        self.0
+            .insert_with_attributes_with_offset_kind(txn, index, content, attrs, kind);
+    }
+
     /// Wraps an existing piece of text within a range described by `index`-`len` parameters with
     /// formatting blocks containing provided `attributes` metadata.
     pub fn format(&self, txn: &mut Transaction, index: u32, len: u32, attrs: Attrs) {
         self.0.format(txn, index, len, attrs);
     }
 
+    /// Same as [XmlText::format], but `index` and `len` are expressed using a given offset `kind`.
+    /// See: [Text::convert_offset](crate::Text::convert_offset).
+    pub fn format_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        len: u32,
+        attrs: Attrs,
+        kind: OffsetKind,
+    ) {
+        self.0.format_with_offset_kind(txn, index, len, attrs, kind);
+    }
+
     /// Inserts an embed `content` at a given `index`.
     ///
     /// If `index` is `0`, this `content` will be inserted at the beginning of a current text.
@@ -766,6 +1351,19 @@ impl XmlText {
         self.0.insert_embed(txn, index, content)
     }
 
+    /// Same as [XmlText::insert_embed], but `index` is expressed using a given offset `kind`.
+    /// See: [Text::convert_offset](crate::Text::convert_offset).
+    pub fn insert_embed_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        content: Any,
+        kind: OffsetKind,
+    ) {
+        self.0
+            .insert_embed_with_offset_kind(txn, index, content, kind)
+    }
+
     /// Inserts an embed `content` of text at a given `index`.
     /// If `index` is `0`, this `content` will be inserted at the beginning of a current text.
     /// If `index` is equal to current data structure length, this `chunk` will be appended at
@@ -785,6 +1383,20 @@ impl XmlText {
             .insert_embed_with_attributes(txn, index, content, attributes)
     }
 
+    /// Same as [XmlText::insert_embed_with_attributes], but `index` is expressed using a given
+    /// offset `kind`. See: [Text::convert_offset](crate::Text::convert_offset).
+    pub fn insert_embed_with_attributes_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        content: Any,
+        attributes: Attrs,
+        kind: OffsetKind,
+    ) {
+        self.0
+            .insert_embed_with_attributes_with_offset_kind(txn, index, content, attributes, kind)
+    }
+
     /// Appends a new string `content` at the end of this XML text structure.
     pub fn push(&self, txn: &mut Transaction, content: &str) {
         let len = self.len();
@@ -798,6 +1410,18 @@ impl XmlText {
         self.0.remove_range(txn, index, len)
     }
 
+    /// Same as [XmlText::remove_range], but `index` and `len` are expressed using a given
+    /// offset `kind`. See: [Text::convert_offset](crate::Text::convert_offset).
+    pub fn remove_range_with_offset_kind(
+        &self,
+        txn: &mut Transaction,
+        index: u32,
+        len: u32,
+        kind: OffsetKind,
+    ) {
+        self.0.remove_range_with_offset_kind(txn, index, len, kind)
+    }
+
     /// Subscribes a given callback to be triggered whenever current XML text is changed.
     /// A callback is triggered whenever a transaction gets committed. This function does not
     /// trigger if changes have been observed by nested shared collections.
@@ -915,9 +1539,57 @@ impl XmlTextEvent {
     }
 }
 
-enum PrelimXml {
+/// Event generated by [XmlHook::observe] method. Emitted during transaction commit phase.
+pub struct XmlHookEvent {
+    pub(crate) current_target: BranchPtr,
+    target: XmlHook,
+    #[allow(clippy::type_complexity)]
+    keys: UnsafeCell<Result<HashMap<Rc<str>, EntryChange>, HashSet<Option<Rc<str>>>>>,
+}
+
+impl XmlHookEvent {
+    pub(crate) fn new(branch_ref: BranchPtr, key_changes: HashSet<Option<Rc<str>>>) -> Self {
+        XmlHookEvent {
+            target: XmlHook::from(branch_ref),
+            current_target: branch_ref,
+            keys: UnsafeCell::new(Err(key_changes)),
+        }
+    }
+
+    /// Returns a [XmlHook] instance which emitted this event.
+    pub fn target(&self) -> &XmlHook {
+        &self.target
+    }
+
+    /// Returns a path from root type down to [XmlHook] instance which emitted this event.
+    pub fn path(&self) -> Path {
+        Branch::path(self.current_target, self.target.inner())
+    }
+
+    /// Returns a summary of key-value changes made over corresponding [XmlHook] within bounds of
+    /// current transaction.
+    pub fn keys(&self, txn: &Transaction) -> &HashMap<Rc<str>, EntryChange> {
+        let keys = unsafe { self.keys.get().as_mut().unwrap() };
+        if let Err(subs) = keys {
+            *keys = Ok(event_keys(txn, self.target.inner(), subs));
+        }
+        match keys {
+            Ok(keys) => keys,
+            Err(_) => panic!("Defect: should not happen"),
+        }
+    }
+}
+
+/// Preliminary XML node, which can be inserted as a child of an [XmlElement] or [XmlFragment]
+/// using generic APIs accepting [Prelim] values, like [Transaction::set_by_path].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrelimXml {
+    /// An empty [XmlElement] with a given tag name.
     Elem(Rc<str>),
+    /// An empty [XmlText].
     Text,
+    /// An empty [XmlHook] with a given name.
+    Hook(Rc<str>),
 }
 
 impl Prelim for PrelimXml {
@@ -925,6 +1597,7 @@ impl Prelim for PrelimXml {
         let mut inner = match self {
             PrelimXml::Elem(node_name) => Branch::new(TYPE_REFS_XML_ELEMENT, Some(node_name)),
             PrelimXml::Text => Branch::new(TYPE_REFS_XML_TEXT, None),
+            PrelimXml::Hook(hook_name) => Branch::new(TYPE_REFS_XML_HOOK, Some(hook_name)),
         };
         inner.as_mut().store = Some(txn.store.clone());
         (ItemContent::Type(inner), None)
@@ -934,42 +1607,81 @@ impl Prelim for PrelimXml {
 }
 
 fn next_sibling(inner: BranchPtr) -> Option<Xml> {
-    let mut current = inner.item;
-    while let Some(Block::Item(item)) = current.as_deref() {
-        current = item.right;
-        if let Some(Block::Item(right)) = current.as_deref() {
-            if !right.is_deleted() {
-                if let ItemContent::Type(inner) = &right.content {
-                    return Some(Xml::from(BranchPtr::from(inner)));
-                }
-            }
-        }
-    }
-
-    None
+    sibling(inner, true)
 }
 
 fn prev_sibling(inner: BranchPtr) -> Option<Xml> {
-    let mut current = inner.item;
+    sibling(inner, false)
+}
+
+/// Returns a closest XML node visible on the right (if `next` is set) or left side of a given one.
+fn sibling(inner: BranchPtr, next: bool) -> Option<Xml> {
+    let ptr = inner.item?;
+    let mut current = Some(ptr);
     while let Some(Block::Item(item)) = current.as_deref() {
-        current = item.left;
-        if let Some(Block::Item(left)) = current.as_deref() {
-            if !left.is_deleted() {
-                if let ItemContent::Type(inner) = &left.content {
-                    return Some(Xml::from(BranchPtr::from(inner)));
-                }
+        if item.moved.is_some()
+            || (!item.is_deleted() && matches!(item.content, ItemContent::Move(_)))
+        {
+            // moved nodes are visible in a different order than the one of the block list
+            let siblings = children(visible_parent(ptr)?);
+            let i = siblings.iter().position(|node| node.item == Some(ptr))?;
+            let i = if next { i + 1 } else { i.checked_sub(1)? };
+            return siblings.get(i).map(|&node| Xml::from(node));
+        }
+        if current != Some(ptr) && !item.is_deleted() {
+            if let ItemContent::Type(inner) = &item.content {
+                return Some(Xml::from(BranchPtr::from(inner)));
             }
         }
+        current = if next { item.right } else { item.left };
     }
 
     None
 }
 
 fn parent(inner: BranchPtr) -> Option<XmlElement> {
-    let block = inner.item?;
-    let item = block.as_item()?;
-    let parent = item.parent.as_branch()?;
-    Some(XmlElement::from(*parent))
+    let parent = visible_parent(inner.item?)?;
+    Some(XmlElement::from(parent))
+}
+
+/// Returns all XML nodes visible as direct children of a given `parent`, in their order.
+/// Unlike iterating over a block list, this takes moved nodes into account.
+pub(crate) fn children(parent: BranchPtr) -> Vec<BranchPtr> {
+    if let Some(mut txn) = parent.try_transact() {
+        let mut walker = BlockIter::new(parent);
+        walker.values(&mut txn).filter_map(as_node).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Returns an XML node visible at a given `index` of a `parent` children.
+fn child_at(parent: BranchPtr, index: u32) -> Option<BranchPtr> {
+    let mut txn = parent.try_transact()?;
+    let mut walker = BlockIter::new(parent);
+    if walker.try_forward(&mut txn, index) {
+        walker.read_value(&mut txn).and_then(as_node)
+    } else {
+        None
+    }
+}
+
+fn as_node(value: Value) -> Option<BranchPtr> {
+    match value {
+        Value::YXmlElement(elem) => Some(elem.inner()),
+        Value::YXmlText(text) => Some(text.inner()),
+        Value::YXmlHook(hook) => Some(hook.inner()),
+        _ => None,
+    }
+}
+
+fn node_to_string(node: BranchPtr) -> String {
+    match Xml::from(node) {
+        Xml::Element(elem) => elem.to_string(),
+        Xml::Text(text) => text.to_string(),
+        // hooks are meant to be rendered by the application, they have no XML representation
+        Xml::Hook(_) => String::new(),
+    }
 }
 
 /// Event generated by [XmlElement::observe] method. Emitted during transaction commit phase.
@@ -1015,6 +1727,12 @@ impl XmlEvent {
         self.changes(txn).delta.as_slice()
     }
 
+    /// Returns ranges of XML child nodes, which have been moved within a bounds of current
+    /// transaction. Each move is also a part of a [Self::delta] as a removal and insertion.
+    pub fn moves(&self, txn: &Transaction) -> &[MoveChange] {
+        self.changes(txn).moves.as_slice()
+    }
+
     /// Returns a collection of block identifiers that have been added within a bounds of
     /// current transaction.
     pub fn added(&self, txn: &Transaction) -> &HashSet<ID> {
@@ -1052,10 +1770,67 @@ impl XmlEvent {
     }
 }
 
+/// Event generated by [XmlFragment::observe] method. Emitted during transaction commit phase.
+pub struct XmlFragmentEvent {
+    pub(crate) current_target: BranchPtr,
+    target: XmlFragment,
+    change_set: UnsafeCell<Option<Box<ChangeSet<Change>>>>,
+}
+
+impl XmlFragmentEvent {
+    pub(crate) fn new(branch_ref: BranchPtr) -> Self {
+        XmlFragmentEvent {
+            target: XmlFragment::from(branch_ref),
+            current_target: branch_ref,
+            change_set: UnsafeCell::new(None),
+        }
+    }
+
+    /// Returns a [XmlFragment] instance which emitted this event.
+    pub fn target(&self) -> &XmlFragment {
+        &self.target
+    }
+
+    /// Returns a path from root type down to [XmlFragment] instance which emitted this event.
+    pub fn path(&self) -> Path {
+        Branch::path(self.current_target, self.target.inner())
+    }
+
+    /// Returns a summary of XML child nodes changed within corresponding [XmlFragment] collection
+    /// within bounds of current transaction.
+    pub fn delta(&self, txn: &Transaction) -> &[Change] {
+        self.changes(txn).delta.as_slice()
+    }
+
+    /// Returns ranges of XML child nodes, which have been moved within a bounds of current
+    /// transaction. Each move is also a part of a [Self::delta] as a removal and insertion.
+    pub fn moves(&self, txn: &Transaction) -> &[MoveChange] {
+        self.changes(txn).moves.as_slice()
+    }
+
+    /// Returns a collection of block identifiers that have been added within a bounds of
+    /// current transaction.
+    pub fn added(&self, txn: &Transaction) -> &HashSet<ID> {
+        &self.changes(txn).added
+    }
+
+    /// Returns a collection of block identifiers that have been removed within a bounds of
+    /// current transaction.
+    pub fn deleted(&self, txn: &Transaction) -> &HashSet<ID> {
+        &self.changes(txn).deleted
+    }
+
+    fn changes(&self, txn: &Transaction) -> &ChangeSet<Change> {
+        let change_set = unsafe { self.change_set.get().as_mut().unwrap() };
+        change_set.get_or_insert_with(|| Box::new(event_change_set(txn, self.target.inner().start)))
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::types::xml::Xml;
-    use crate::types::{Change, EntryChange, Value};
+    use crate::test_utils::exchange_updates;
+    use crate::types::xml::{Xml, XmlElement};
+    use crate::types::{parse_path, Change, EntryChange, Value};
     use crate::updates::decoder::Decode;
     use crate::updates::encoder::{Encoder, EncoderV1};
     use crate::{Doc, StateVector, Update};
@@ -1157,6 +1932,74 @@ mod test {
         );
     }
 
+    #[test]
+    fn move_children() {
+        let d1 = Doc::with_client_id(1);
+        let root1 = d1.transact().get_xml_element("root");
+        let other1 = d1.transact().get_xml_element("other");
+        let d2 = Doc::with_client_id(2);
+        let root2 = d2.transact().get_xml_element("root");
+        let other2 = d2.transact().get_xml_element("other");
+        {
+            let mut txn = d1.transact();
+            root1.push_elem_back(&mut txn, "p");
+            root1.push_elem_back(&mut txn, "img");
+            root1.push_elem_back(&mut txn, "span");
+            other1.push_elem_back(&mut txn, "div");
+        }
+        exchange_updates(&[&d1, &d2]);
+
+        // move <span> before <p> within the same element
+        root1.move_to(&mut d1.transact(), 2, 0);
+        assert_eq!(
+            root1.to_string(),
+            "<UNDEFINED><span></span><p></p><img></img></UNDEFINED>"
+        );
+        let span = match root1.first_child() {
+            Some(Xml::Element(e)) => e,
+            other => panic!("expected <span> element, found {:?}", other),
+        };
+        assert_eq!(span.tag(), "span");
+        assert_eq!(span.parent().as_ref(), Some(&root1));
+        assert_eq!(span.prev_sibling(), None);
+        let tags: Vec<_> = root1
+            .successors()
+            .filter_map(|n| match n {
+                Xml::Element(e) => Some(e.tag().to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(tags, vec!["span", "p", "img"]);
+
+        // concurrently move <img> into another element and update it
+        root1.move_into(&mut d1.transact(), 2, &other1, 1);
+        if let Some(Xml::Element(img)) = root2.get(1) {
+            img.insert_attribute(&mut d2.transact(), "src", "logo.png");
+        } else {
+            panic!("expected <img> element");
+        }
+        exchange_updates(&[&d1, &d2]);
+
+        for (root, other) in [(&root1, &other1), (&root2, &other2)] {
+            assert_eq!(
+                root.to_string(),
+                "<UNDEFINED><span></span><p></p></UNDEFINED>"
+            );
+            assert_eq!(
+                other.to_string(),
+                "<UNDEFINED><div></div><img \"src\"=\"logo.png\"></img></UNDEFINED>"
+            );
+            assert_eq!(root.len(), 2);
+            assert_eq!(other.len(), 2);
+            let img = match other.get(1) {
+                Some(Xml::Element(e)) => e,
+                other => panic!("expected <img> element, found {:?}", other),
+            };
+            assert_eq!(img.parent().as_ref(), Some(other));
+            assert_eq!(img.next_sibling(), None);
+        }
+    }
+
     #[test]
     fn serialization() {
         let d1 = Doc::with_client_id(1);
@@ -1342,4 +2185,168 @@ mod test {
             )]))
         );
     }
+
+    #[test]
+    fn xml_fragment_root() {
+        let d1 = Doc::with_client_id(1);
+        let mut f1 = d1.transact().get_xml_fragment("prosemirror");
+        let nodes = Rc::new(RefCell::new(None));
+        let nodes_c = nodes.clone();
+        let _sub = f1.observe(move |txn, e| {
+            *nodes_c.borrow_mut() = Some(e.delta(txn).to_vec());
+        });
+
+        let (p, heading) = {
+            let mut txn = d1.transact();
+            let p = f1.insert_elem(&mut txn, 0, "paragraph");
+            p.push_text_back(&mut txn).push(&mut txn, "hello");
+            let heading = f1.insert_elem(&mut txn, 1, "heading");
+            (p, heading)
+        };
+        assert_eq!(
+            nodes.borrow_mut().take(),
+            Some(vec![Change::Added(vec![
+                Value::YXmlElement(p),
+                Value::YXmlElement(heading)
+            ])])
+        );
+        assert_eq!(
+            f1.to_string(),
+            "<paragraph>hello</paragraph><heading></heading>"
+        );
+
+        let d2 = Doc::with_client_id(2);
+        let f2 = d2.transact().get_xml_fragment("prosemirror");
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(f2.to_string(), f1.to_string());
+
+        let mut txn = d2.transact();
+        let first = txn.get_by_path(&parse_path("prosemirror/0")).unwrap();
+        assert_eq!(first.to_yxml_elem().unwrap().tag(), "paragraph");
+        txn.remove_by_path(&parse_path("prosemirror/1"));
+        assert_eq!(f2.to_string(), "<paragraph>hello</paragraph>");
+    }
+
+    #[test]
+    fn insert_xml_str() {
+        let d1 = Doc::with_client_id(1);
+        let f1 = d1.transact().get_xml_fragment("fragment");
+        {
+            let mut txn = d1.transact();
+            let inserted = f1
+                .insert_xml_str(&mut txn, 0, "<p>hello <b>world</b></p><img src=\"a.png\"/>")
+                .unwrap();
+            assert_eq!(inserted, 2);
+            assert!(f1.insert_xml_str(&mut txn, 0, "<p><b>oops</p>").is_err());
+            f1.insert_html_str(&mut txn, 1, "<UL><li>one<li>two</ul>")
+                .unwrap();
+        }
+        let p = f1.get::<XmlElement>(0).unwrap();
+        assert_eq!(p.tag(), "p");
+        assert_eq!(p.len(), 2);
+        let img = f1.get::<XmlElement>(2).unwrap();
+        assert_eq!(img.get_attribute("src"), Some("a.png".to_string()));
+        assert_eq!(
+            f1.to_string(),
+            "<p>hello <b>world</b></p><ul><li>one</li><li>two</li></ul><img \"src\"=\"a.png\"></img>"
+        );
+
+        let d2 = Doc::with_client_id(2);
+        let f2 = d2.transact().get_xml_fragment("fragment");
+        exchange_updates(&[&d1, &d2]);
+        assert_eq!(f2.to_string(), f1.to_string());
+    }
+
+    #[test]
+    fn xml_hook() {
+        let d1 = Doc::with_client_id(1);
+        let f1 = d1.transact().get_xml_fragment("fragment");
+        let mut hook = {
+            let mut txn = d1.transact();
+            let p = f1.insert_elem(&mut txn, 0, "p");
+            p.push_text_back(&mut txn).push(&mut txn, "hello");
+            let hook = p.insert_hook(&mut txn, 1, "mention");
+            hook.insert(&mut txn, "user".to_string(), "alice");
+            hook
+        };
+        assert_eq!(hook.hook_name(), "mention");
+        assert_eq!(f1.to_string(), "<p>hello</p>");
+
+        let keys = Rc::new(RefCell::new(None));
+        let keys_c = keys.clone();
+        let _sub = hook.observe(move |txn, e| {
+            *keys_c.borrow_mut() = Some(e.keys(txn).clone());
+        });
+        {
+            let mut txn = d1.transact();
+            hook.insert(&mut txn, "user".to_string(), "bob");
+        }
+        assert_eq!(
+            keys.borrow_mut().take(),
+            Some(HashMap::from([(
+                "user".into(),
+                EntryChange::Updated(
+                    Value::Any(Any::String("alice".into())),
+                    Value::Any(Any::String("bob".into()))
+                )
+            )]))
+        );
+
+        let d2 = Doc::with_client_id(2);
+        let f2 = d2.transact().get_xml_fragment("fragment");
+        exchange_updates(&[&d1, &d2]);
+        let p: XmlElement = f2.get(0).unwrap();
+        let remote = match p.get(1) {
+            Some(Xml::Hook(hook)) => hook,
+            other => panic!("expected XML hook, got {:?}", other),
+        };
+        assert_eq!(remote.hook_name(), "mention");
+        assert_eq!(
+            remote.get("user"),
+            Some(Value::Any(Any::String("bob".into())))
+        );
+
+        let txn = d2.transact();
+        let value = txn.get_by_path(&parse_path("fragment/0/1/user")).unwrap();
+        assert_eq!(value, Value::Any(Any::String("bob".into())));
+    }
+
+    #[test]
+    fn xml_hook_yjs_compatibility() {
+        /* Generated with Yjs v1 encoding:
+            const doc = new Y.Doc()
+            doc.clientID = 1
+            const hook = new Y.XmlHook('custom')
+            doc.getXmlFragment('xml').insert(0, [hook])
+            hook.set('a', 1)
+        */
+        let update = &[
+            1, 2, 1, 0, 7, 1, 3, 120, 109, 108, 5, 6, 99, 117, 115, 116, 111, 109, 40, 0, 1, 0, 1,
+            97, 1, 125, 1, 0,
+        ];
+        let doc = Doc::with_client_id(2);
+        let xml = doc.transact().get_xml_fragment("xml");
+        doc.transact()
+            .apply_update(Update::decode_v1(update).unwrap());
+        let hook = match xml.get::<Xml>(0) {
+            Some(Xml::Hook(hook)) => hook,
+            other => panic!("expected XML hook, got {:?}", other),
+        };
+        assert_eq!(hook.hook_name(), "custom");
+        assert_eq!(hook.to_json(), Any::from_json(r#"{"a":1}"#).unwrap());
+
+        // hook survives encoding round trip
+        let payload = doc.encode_state_as_update_v1(&StateVector::default());
+        let doc2 = Doc::new();
+        let xml2 = doc2.transact().get_xml_fragment("xml");
+        doc2.transact()
+            .apply_update(Update::decode_v1(&payload).unwrap());
+        match xml2.get::<Xml>(0) {
+            Some(Xml::Hook(hook)) => {
+                assert_eq!(hook.hook_name(), "custom");
+                assert_eq!(hook.to_json(), Any::from_json(r#"{"a":1}"#).unwrap());
+            }
+            other => panic!("expected XML hook, got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,874 @@
+use crate::types::text::Diff;
+use crate::types::xml::{children, Xml, XmlElement, XmlFragment, XmlText};
+use crate::types::{Attrs, Branch, BranchPtr, Delta, Entries, Value};
+use crate::{OffsetKind, Text, Transaction};
+use lib0::any::Any;
+use std::collections::HashMap;
+use std::fmt::Formatter;
+use std::rc::Rc;
+
+/// A detached deep copy of an XML node, which is not bound to any document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlSnapshot {
+    /// Copy of an [XmlElement] with its tag, attributes and child nodes.
+    Element {
+        tag: Rc<str>,
+        attributes: Attrs,
+        children: Vec<XmlSnapshot>,
+    },
+    /// Copy of an [XmlText] with its attributes and formatted content chunks.
+    Text {
+        attributes: Attrs,
+        chunks: Vec<TextChunk>,
+    },
+    /// Copy of an [XmlHook](crate::XmlHook) with its name and entries.
+    Hook { name: Rc<str>, entries: Attrs },
+}
+
+impl XmlSnapshot {
+    fn is_compatible(&self, other: &XmlSnapshot) -> bool {
+        match (self, other) {
+            (XmlSnapshot::Element { tag: a, .. }, XmlSnapshot::Element { tag: b, .. }) => a == b,
+            (XmlSnapshot::Text { .. }, XmlSnapshot::Text { .. }) => true,
+            // hooks are retained only when they are deeply equal
+            _ => false,
+        }
+    }
+}
+
+/// A piece of [XmlText] content sharing the same formatting attributes. Its `insert` is either
+/// a string or an embedded value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChunk {
+    pub insert: Any,
+    pub attributes: Option<Box<Attrs>>,
+}
+
+/// A structural difference between two XML collections ([XmlFragment]s or [XmlElement]s),
+/// returned by [XmlFragment::diff]. It describes the edits necessary to turn a source collection
+/// into a target one, and can be applied using [XmlFragment::apply_diff].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XmlDiff {
+    /// Attributes which have been changed: `Some` contains a new attribute value while `None`
+    /// means that an attribute has been removed.
+    pub attributes: HashMap<Rc<str>, Option<Any>>,
+    /// Changes made over the child nodes.
+    pub children: Vec<XmlChange>,
+}
+
+impl XmlDiff {
+    /// Returns `true` if compared collections were deeply equal.
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+            && self
+                .children
+                .iter()
+                .all(|c| matches!(c, XmlChange::Retain(_)))
+    }
+}
+
+/// A difference between two [XmlText]s, which were matched as the same node.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XmlTextDiff {
+    /// Attributes which have been changed: `Some` contains a new attribute value while `None`
+    /// means that an attribute has been removed.
+    pub attributes: HashMap<Rc<str>, Option<Any>>,
+    /// Changes made over the text content. Lengths and indexes are expressed using Unicode code
+    /// points, embedded values have a length of 1. Formatting attributes set to [Any::Null] in
+    /// [Delta::Retain] are meant to be removed.
+    pub delta: Vec<Delta>,
+}
+
+/// Error returned by [XmlFragment::apply_diff] when a diff doesn't match the structure of
+/// a collection it's applied onto.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlDiffError {
+    /// Diff refers to a child node at a given index, which doesn't exist.
+    NodeNotFound(u32),
+    /// Diff changes a child node at a given index as an [XmlElement] or an [XmlText], but that
+    /// node is of a different kind.
+    NodeMismatch(u32),
+    /// Text delta of an [XmlText] at a given index refers to content past the end of that text.
+    TextOutOfBounds(u32),
+}
+
+impl std::fmt::Display for XmlDiffError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XmlDiffError::NodeNotFound(index) => write!(f, "no XML node at index {}", index),
+            XmlDiffError::NodeMismatch(index) => {
+                write!(f, "XML node at index {} is of unexpected kind", index)
+            }
+            XmlDiffError::TextOutOfBounds(index) => {
+                write!(
+                    f,
+                    "text delta exceeds the length of XML text at index {}",
+                    index
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for XmlDiffError {}
+
+/// A single change done over a list of XML child nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlChange {
+    /// A number of consecutive child nodes, which are deeply equal in both collections.
+    Retain(u32),
+    /// A number of consecutive child nodes, which have been removed.
+    Removed(u32),
+    /// New child nodes, which have been inserted.
+    Added(Vec<XmlSnapshot>),
+    /// A single [XmlElement] with the same tag in both collections, which attributes or children
+    /// have changed.
+    Element(XmlDiff),
+    /// A single [XmlText] present in both collections, which attributes or content has changed.
+    Text(XmlTextDiff),
+}
+
+/// Creates a detached deep copy of a given XML node.
+pub(crate) fn snapshot(node: BranchPtr) -> XmlSnapshot {
+    match Xml::from(node) {
+        Xml::Element(elem) => XmlSnapshot::Element {
+            tag: elem.tag().into(),
+            attributes: attributes(node),
+            children: children(node).into_iter().map(snapshot).collect(),
+        },
+        Xml::Text(text) => XmlSnapshot::Text {
+            attributes: attributes(node),
+            chunks: text_chunks(&text),
+        },
+        Xml::Hook(hook) => XmlSnapshot::Hook {
+            name: hook.hook_name().into(),
+            entries: attributes(node),
+        },
+    }
+}
+
+/// Creates detached deep copies of all child nodes of a given XML collection.
+pub(crate) fn snapshot_children(parent: BranchPtr) -> Vec<XmlSnapshot> {
+    children(parent).into_iter().map(snapshot).collect()
+}
+
+/// Returns raw attribute values of a given XML node (or entries in case of [XmlHook](crate::XmlHook)).
+pub(crate) fn attributes(branch: BranchPtr) -> Attrs {
+    let branch: &Branch = &branch;
+    let mut attrs = Attrs::new();
+    for (key, item) in Entries::new(&branch.map) {
+        if let Some(value) = item.content.get_last() {
+            attrs.insert(key.into(), value.to_json());
+        }
+    }
+    attrs
+}
+
+fn text_chunks(text: &XmlText) -> Vec<TextChunk> {
+    let branch = BranchPtr::from(text.as_ref());
+    let mut txn = match branch.try_transact() {
+        Some(txn) => txn,
+        None => return Vec::new(),
+    };
+    Text::from(branch)
+        .diff(&mut txn)
+        .into_iter()
+        .map(|Diff::Insert(value, attributes)| TextChunk {
+            insert: match value {
+                Value::Any(any) => any,
+                other => other.to_json(),
+            },
+            attributes,
+        })
+        .collect()
+}
+
+/// Computes a difference between attributes and child nodes of two XML collections.
+pub(crate) fn diff(source: BranchPtr, target: BranchPtr) -> XmlDiff {
+    XmlDiff {
+        attributes: diff_attributes(&attributes(source), &attributes(target)),
+        children: diff_children(&snapshot_children(source), &snapshot_children(target)),
+    }
+}
+
+fn diff_attributes(source: &Attrs, target: &Attrs) -> HashMap<Rc<str>, Option<Any>> {
+    let mut changes = HashMap::new();
+    for (key, value) in target.iter() {
+        if source.get(key) != Some(value) {
+            changes.insert(key.clone(), Some(value.clone()));
+        }
+    }
+    for key in source.keys() {
+        if !target.contains_key(key) {
+            changes.insert(key.clone(), None);
+        }
+    }
+    changes
+}
+
+fn diff_children(source: &[XmlSnapshot], target: &[XmlSnapshot]) -> Vec<XmlChange> {
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    // deeply equal nodes are retained, others are matched by their tag within the gaps between
+    for (si, ti) in lcs(source, target, |a, b| a == b)
+        .into_iter()
+        .chain(std::iter::once((source.len(), target.len())))
+    {
+        let (gap_source, gap_target) = (&source[i..si], &target[j..ti]);
+        let (mut gi, mut gj) = (0, 0);
+        for (gsi, gti) in lcs(gap_source, gap_target, XmlSnapshot::is_compatible)
+            .into_iter()
+            .chain(std::iter::once((gap_source.len(), gap_target.len())))
+        {
+            push_change(&mut changes, XmlChange::Removed((gsi - gi) as u32));
+            push_change(&mut changes, XmlChange::Added(gap_target[gj..gti].to_vec()));
+            if let (Some(a), Some(b)) = (gap_source.get(gsi), gap_target.get(gti)) {
+                match diff_node(a, b) {
+                    Some(change) => push_change(&mut changes, change),
+                    None => {
+                        push_change(&mut changes, XmlChange::Removed(1));
+                        push_change(&mut changes, XmlChange::Added(vec![b.clone()]));
+                    }
+                }
+            }
+            gi = gsi + 1;
+            gj = gti + 1;
+        }
+        if si < source.len() {
+            push_change(&mut changes, XmlChange::Retain(1));
+        }
+        i = si + 1;
+        j = ti + 1;
+    }
+    // trailing retains don't bring any information
+    while let Some(XmlChange::Retain(_)) = changes.last() {
+        changes.pop();
+    }
+    changes
+}
+
+/// Compares two matched XML nodes. Returns `None` if nodes are not of the same kind, as only
+/// [XmlElement]s and [XmlText]s can be changed in place.
+fn diff_node(source: &XmlSnapshot, target: &XmlSnapshot) -> Option<XmlChange> {
+    let change = match (source, target) {
+        (
+            XmlSnapshot::Element {
+                attributes: source_attrs,
+                children: source_children,
+                ..
+            },
+            XmlSnapshot::Element {
+                attributes: target_attrs,
+                children: target_children,
+                ..
+            },
+        ) => XmlChange::Element(XmlDiff {
+            attributes: diff_attributes(source_attrs, target_attrs),
+            children: diff_children(source_children, target_children),
+        }),
+        (
+            XmlSnapshot::Text {
+                attributes: source_attrs,
+                chunks: source_chunks,
+            },
+            XmlSnapshot::Text {
+                attributes: target_attrs,
+                chunks: target_chunks,
+            },
+        ) => XmlChange::Text(XmlTextDiff {
+            attributes: diff_attributes(source_attrs, target_attrs),
+            delta: diff_text(source_chunks, target_chunks),
+        }),
+        _ => return None,
+    };
+    Some(change)
+}
+
+fn push_change(changes: &mut Vec<XmlChange>, change: XmlChange) {
+    match (changes.last_mut(), change) {
+        (_, XmlChange::Retain(0)) | (_, XmlChange::Removed(0)) => {}
+        (_, XmlChange::Added(nodes)) if nodes.is_empty() => {}
+        (Some(XmlChange::Retain(n)), XmlChange::Retain(m)) => *n += m,
+        (Some(XmlChange::Removed(n)), XmlChange::Removed(m)) => *n += m,
+        (Some(XmlChange::Added(nodes)), XmlChange::Added(other)) => nodes.extend(other),
+        (_, change) => changes.push(change),
+    }
+}
+
+/// A single Unicode code point or an embedded value of a text, together with its formatting.
+#[derive(PartialEq)]
+enum TextUnit<'a> {
+    Char(char, Option<&'a Attrs>),
+    Embed(&'a Any, Option<&'a Attrs>),
+}
+
+impl<'a> TextUnit<'a> {
+    fn attributes(&self) -> Option<&'a Attrs> {
+        match self {
+            TextUnit::Char(_, attrs) | TextUnit::Embed(_, attrs) => *attrs,
+        }
+    }
+
+    fn same_content(&self, other: &TextUnit) -> bool {
+        match (self, other) {
+            (TextUnit::Char(a, _), TextUnit::Char(b, _)) => a == b,
+            (TextUnit::Embed(a, _), TextUnit::Embed(b, _)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+fn text_units(chunks: &[TextChunk]) -> Vec<TextUnit<'_>> {
+    let mut units = Vec::new();
+    for chunk in chunks {
+        let attrs = chunk.attributes.as_deref();
+        match &chunk.insert {
+            Any::String(s) => units.extend(s.chars().map(|c| TextUnit::Char(c, attrs))),
+            embed => units.push(TextUnit::Embed(embed, attrs)),
+        }
+    }
+    units
+}
+
+fn diff_text(source: &[TextChunk], target: &[TextChunk]) -> Vec<Delta> {
+    let source = text_units(source);
+    let target = text_units(target);
+    let mut delta = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (si, ti) in lcs(&source, &target, TextUnit::same_content)
+        .into_iter()
+        .chain(std::iter::once((source.len(), target.len())))
+    {
+        push_delta(&mut delta, Delta::Deleted((si - i) as u32));
+        for unit in &target[j..ti] {
+            let attrs = unit.attributes().cloned().map(Box::new);
+            let value = match unit {
+                TextUnit::Char(c, _) => Value::Any(Any::String(c.to_string().into())),
+                TextUnit::Embed(embed, _) => Value::Any((*embed).clone()),
+            };
+            push_delta(&mut delta, Delta::Inserted(value, attrs));
+        }
+        if let (Some(a), Some(b)) = (source.get(si), target.get(ti)) {
+            let format = diff_format(a.attributes(), b.attributes());
+            push_delta(&mut delta, Delta::Retain(1, format));
+        }
+        i = si + 1;
+        j = ti + 1;
+    }
+    while let Some(Delta::Retain(_, None)) = delta.last() {
+        delta.pop();
+    }
+    delta
+}
+
+/// Returns formatting attributes, which need to be applied to turn `source` formatting into
+/// `target` one. Removed attributes are set to [Any::Null].
+fn diff_format(source: Option<&Attrs>, target: Option<&Attrs>) -> Option<Box<Attrs>> {
+    let empty = Attrs::new();
+    let source = source.unwrap_or(&empty);
+    let target = target.unwrap_or(&empty);
+    let format: Attrs = diff_attributes(source, target)
+        .into_iter()
+        .map(|(key, value)| (key, value.unwrap_or(Any::Null)))
+        .collect();
+    if format.is_empty() {
+        None
+    } else {
+        Some(Box::new(format))
+    }
+}
+
+fn push_delta(delta: &mut Vec<Delta>, change: Delta) {
+    match (delta.last_mut(), change) {
+        (_, Delta::Deleted(0)) => {}
+        (Some(Delta::Deleted(n)), Delta::Deleted(m)) => *n += m,
+        (Some(Delta::Retain(n, a)), Delta::Retain(m, b)) if *a == b => *n += m,
+        (
+            Some(Delta::Inserted(Value::Any(Any::String(s)), a)),
+            Delta::Inserted(Value::Any(Any::String(other)), b),
+        ) if *a == b => *s = format!("{}{}", s, other).into(),
+        (_, change) => delta.push(change),
+    }
+}
+
+/// Returns index pairs of a longest common subsequence of `source` and `target` elements, which
+/// are considered equal by a given predicate.
+///
+/// Subsequence is found using Myers' diff algorithm in its linear space variant: sequences are
+/// recursively split around the middle snake of their shortest edit script, which takes
+/// `O((N + M) * D)` time and `O(N + M)` memory, where `D` is the number of differing elements.
+fn lcs<T, F>(source: &[T], target: &[T], eq: F) -> Vec<(usize, usize)>
+where
+    F: Fn(&T, &T) -> bool,
+{
+    let mut pairs = Vec::new();
+    lcs_within(source, target, (0, 0), &eq, &mut pairs);
+    pairs
+}
+
+/// Appends index pairs of a longest common subsequence of `source` and `target` slices, which
+/// start at given `offset`s of compared sequences, to `pairs`.
+fn lcs_within<T, F>(
+    source: &[T],
+    target: &[T],
+    offset: (usize, usize),
+    eq: &F,
+    pairs: &mut Vec<(usize, usize)>,
+) where
+    F: Fn(&T, &T) -> bool,
+{
+    // common prefix and suffix are matched directly
+    let prefix = source
+        .iter()
+        .zip(target.iter())
+        .take_while(|(a, b)| eq(a, b))
+        .count();
+    pairs.extend((0..prefix).map(|i| (offset.0 + i, offset.1 + i)));
+    let (source, target) = (&source[prefix..], &target[prefix..]);
+    let offset = (offset.0 + prefix, offset.1 + prefix);
+    let suffix = source
+        .iter()
+        .rev()
+        .zip(target.iter().rev())
+        .take_while(|(a, b)| eq(a, b))
+        .count();
+    let source = &source[..source.len() - suffix];
+    let target = &target[..target.len() - suffix];
+
+    if let Some((start, end)) = middle_snake(source, target, eq) {
+        lcs_within(&source[..start.0], &target[..start.1], offset, eq, pairs);
+        pairs
+            .extend((0..end.0 - start.0).map(|i| (offset.0 + start.0 + i, offset.1 + start.1 + i)));
+        let rest = (offset.0 + end.0, offset.1 + end.1);
+        lcs_within(&source[end.0..], &target[end.1..], rest, eq, pairs);
+    }
+
+    let end = (offset.0 + source.len(), offset.1 + target.len());
+    pairs.extend((0..suffix).map(|i| (end.0 + i, end.1 + i)));
+}
+
+/// Start and end positions of a snake within compared sequences.
+type Snake = ((usize, usize), (usize, usize));
+
+/// Returns start and end positions of a middle snake - a sequence of matching elements, which
+/// lies in the middle of the shortest edit script turning `source` into `target`. Returns `None`
+/// if any of the slices is empty.
+fn middle_snake<T, F>(source: &[T], target: &[T], eq: &F) -> Option<Snake>
+where
+    F: Fn(&T, &T) -> bool,
+{
+    if source.is_empty() || target.is_empty() {
+        return None;
+    }
+    let (n, m) = (source.len() as isize, target.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    // furthest reaching x positions of forward and backward paths, indexed by their diagonals;
+    // backward paths are computed over reversed sequences
+    let size = 2 * max as usize + 3;
+    let (mut forward, mut backward) = (vec![0isize; size], vec![0isize; size]);
+    let at = |k: isize| (k + max + 1) as usize;
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let start = (x, x - k);
+            while x < n && x - k < m && eq(&source[x as usize], &target[(x - k) as usize]) {
+                x += 1;
+            }
+            forward[at(k)] = x;
+            let reversed = delta - k;
+            if odd && reversed.abs() < d && x + backward[at(reversed)] >= n {
+                let start = (start.0 as usize, start.1 as usize);
+                return Some((start, (x as usize, (x - k) as usize)));
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let end = (n - x, m - (x - k));
+            while x < n
+                && x - k < m
+                && eq(
+                    &source[(n - x - 1) as usize],
+                    &target[(m - (x - k) - 1) as usize],
+                )
+            {
+                x += 1;
+            }
+            backward[at(k)] = x;
+            let forward_k = delta - k;
+            if !odd && forward_k.abs() <= d && x + forward[at(forward_k)] >= n {
+                let start = ((n - x) as usize, (m - (x - k)) as usize);
+                return Some((start, (end.0 as usize, end.1 as usize)));
+            }
+        }
+    }
+    None
+}
+
+/// Applies a given `diff` onto children and attributes of a `parent` XML collection. A `diff` is
+/// validated against the structure of a `parent` first, so that nothing is changed if it doesn't
+/// match.
+pub(crate) fn apply(
+    parent: &XmlFragment,
+    txn: &mut Transaction,
+    diff: &XmlDiff,
+) -> Result<(), XmlDiffError> {
+    validate(BranchPtr::from(parent.as_ref()), diff)?;
+    apply_valid(parent, txn, diff);
+    Ok(())
+}
+
+/// Checks if all child nodes referenced by a given `diff` exist within a `parent` XML collection
+/// and are of the expected kind.
+fn validate(parent: BranchPtr, diff: &XmlDiff) -> Result<(), XmlDiffError> {
+    let nodes = children(parent);
+    let mut index = 0;
+    for change in diff.children.iter() {
+        let len = match change {
+            XmlChange::Retain(len) | XmlChange::Removed(len) => *len,
+            XmlChange::Added(_) => 0,
+            XmlChange::Element(_) | XmlChange::Text(_) => 1,
+        };
+        if index + len > nodes.len() as u32 {
+            return Err(XmlDiffError::NodeNotFound(index + len - 1));
+        }
+        match change {
+            XmlChange::Element(diff) => match Xml::from(nodes[index as usize]) {
+                Xml::Element(child) => validate(BranchPtr::from(child.as_ref()), diff)?,
+                _ => return Err(XmlDiffError::NodeMismatch(index)),
+            },
+            XmlChange::Text(diff) => match Xml::from(nodes[index as usize]) {
+                Xml::Text(child) => validate_text(&child, diff, index)?,
+                _ => return Err(XmlDiffError::NodeMismatch(index)),
+            },
+            _ => {}
+        }
+        index += len;
+    }
+    Ok(())
+}
+
+/// Checks if a text `delta` doesn't exceed the length of a given `text`, which is a child node
+/// placed at a given `index`.
+fn validate_text(text: &XmlText, diff: &XmlTextDiff, index: u32) -> Result<(), XmlDiffError> {
+    let len = text_units(&text_chunks(text)).len() as u32;
+    let mut offset = 0;
+    for delta in diff.delta.iter() {
+        if let Delta::Retain(n, _) | Delta::Deleted(n) = delta {
+            offset += n;
+        }
+    }
+    if offset > len {
+        Err(XmlDiffError::TextOutOfBounds(index))
+    } else {
+        Ok(())
+    }
+}
+
+fn apply_valid(parent: &XmlFragment, txn: &mut Transaction, diff: &XmlDiff) {
+    let elem: XmlElement = parent.clone().into();
+    for (key, value) in diff.attributes.iter() {
+        match value {
+            Some(value) => elem.insert_attribute_value(txn, key.clone(), value.clone()),
+            None => elem.remove_attribute(txn, key),
+        }
+    }
+    let mut index = 0;
+    for change in diff.children.iter() {
+        match change {
+            XmlChange::Retain(len) => index += len,
+            XmlChange::Removed(len) => parent.remove(txn, index, *len),
+            XmlChange::Added(nodes) => {
+                for node in nodes {
+                    insert(parent, txn, index, node);
+                    index += 1;
+                }
+            }
+            XmlChange::Element(diff) => {
+                if let Some(child) = parent.get::<XmlElement>(index) {
+                    apply_valid(child.as_fragment(), txn, diff);
+                }
+                index += 1;
+            }
+            XmlChange::Text(diff) => {
+                if let Some(child) = parent.get::<XmlText>(index) {
+                    apply_text(&child, txn, diff);
+                }
+                index += 1;
+            }
+        }
+    }
+}
+
+fn apply_text(text: &XmlText, txn: &mut Transaction, diff: &XmlTextDiff) {
+    for (key, value) in diff.attributes.iter() {
+        match value {
+            Some(value) => text.insert_attribute_value(txn, key.clone(), value.clone()),
+            None => text.remove_attribute(txn, key),
+        }
+    }
+    let kind = OffsetKind::Utf32;
+    let mut index = 0;
+    for delta in diff.delta.iter() {
+        match delta {
+            Delta::Retain(len, attrs) => {
+                if let Some(attrs) = attrs {
+                    text.format_with_offset_kind(txn, index, *len, *attrs.clone(), kind);
+                }
+                index += len;
+            }
+            Delta::Deleted(len) => text.remove_range_with_offset_kind(txn, index, *len, kind),
+            Delta::Inserted(value, attrs) => {
+                let attrs = attrs.as_deref().cloned().unwrap_or_default();
+                index += insert_chunk(text, txn, index, value.clone().to_json(), attrs);
+            }
+        }
+    }
+}
+
+/// Inserts a text or embed `content` at a given `index` (in Unicode code points) and returns its
+/// length. Attributes are always passed explicitly, so that inserted content doesn't inherit
+/// formatting from its neighbours.
+fn insert_chunk(
+    text: &XmlText,
+    txn: &mut Transaction,
+    index: u32,
+    content: Any,
+    attrs: Attrs,
+) -> u32 {
+    let kind = OffsetKind::Utf32;
+    match content {
+        Any::String(s) => {
+            text.insert_with_attributes_with_offset_kind(txn, index, &s, attrs, kind);
+            s.chars().count() as u32
+        }
+        embed => {
+            text.insert_embed_with_attributes_with_offset_kind(txn, index, embed, attrs, kind);
+            1
+        }
+    }
+}
+
+/// Inserts a deep copy of a given detached `node` into a `parent` XML collection.
+fn insert(parent: &XmlFragment, txn: &mut Transaction, index: u32, node: &XmlSnapshot) {
+    match node {
+        XmlSnapshot::Element {
+            tag,
+            attributes,
+            children,
+        } => {
+            let elem = parent.insert_elem(txn, index, tag.clone());
+            for (key, value) in attributes.iter() {
+                elem.insert_attribute_value(txn, key.clone(), value.clone());
+            }
+            for (i, child) in children.iter().enumerate() {
+                insert(elem.as_fragment(), txn, i as u32, child);
+            }
+        }
+        XmlSnapshot::Text { attributes, chunks } => {
+            let text = parent.insert_text(txn, index);
+            for (key, value) in attributes.iter() {
+                text.insert_attribute_value(txn, key.clone(), value.clone());
+            }
+            let mut len = 0;
+            for chunk in chunks {
+                let attrs = chunk.attributes.as_deref().cloned().unwrap_or_default();
+                len += insert_chunk(&text, txn, len, chunk.insert.clone(), attrs);
+            }
+        }
+        XmlSnapshot::Hook { name, entries } => {
+            let hook = parent.insert_hook(txn, index, name.clone());
+            for (key, value) in entries.iter() {
+                hook.insert(txn, key.to_string(), value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::exchange_updates;
+    use crate::types::xml_diff::{lcs, XmlChange, XmlDiffError};
+    use crate::types::Attrs;
+    use crate::{Doc, XmlElement, XmlText};
+    use lib0::any::Any;
+    use std::convert::TryInto;
+
+    #[test]
+    fn diff_and_apply() {
+        let d1 = Doc::with_client_id(1);
+        let live = d1.transact().get_xml_fragment("article");
+        {
+            let mut txn = d1.transact();
+            live.insert_xml_str(
+                &mut txn,
+                0,
+                r#"<h1>Title</h1><p id="a">hello world</p><p id="b">bye</p><hr/>"#,
+            )
+            .unwrap();
+        }
+
+        // externally edited export, loaded into a separate document
+        let external = Doc::with_client_id(100);
+        let edited = external.transact().get_xml_fragment("article");
+        {
+            let mut txn = external.transact();
+            edited
+                .insert_xml_str(
+                    &mut txn,
+                    0,
+                    r#"<h1>Title</h1><p id="a" class="x">hello brave world</p><ul><li>new</li></ul><hr/>"#,
+                )
+                .unwrap();
+            let h1: XmlElement = edited.get(0).unwrap();
+            let text: XmlText = h1.get(0).unwrap().try_into().unwrap();
+            let mut bold = Attrs::new();
+            bold.insert("bold".into(), Any::Bool(true));
+            text.format(&mut txn, 0, 2, bold);
+        }
+
+        assert!(live.deep_eq(&live));
+        assert!(!live.deep_eq(&edited));
+        let diff = live.diff(&edited);
+        assert!(!diff.is_empty());
+        assert!(matches!(diff.children[0], XmlChange::Element(_)));
+        assert!(matches!(diff.children[1], XmlChange::Element(_)));
+        assert_eq!(diff.children[2], XmlChange::Removed(1));
+        assert!(matches!(diff.children[3], XmlChange::Added(_)));
+        assert_eq!(diff.children.len(), 4);
+
+        // concurrent change made by another peer is preserved
+        let d2 = Doc::with_client_id(2);
+        let remote = d2.transact().get_xml_fragment("article");
+        exchange_updates(&[&d1, &d2]);
+        {
+            let mut txn = d2.transact();
+            let hr: XmlElement = remote.get(3).unwrap();
+            hr.insert_attribute(&mut txn, "style", "dashed");
+        }
+
+        {
+            let mut txn = d1.transact();
+            live.apply_diff(&mut txn, &diff).unwrap();
+        }
+        assert!(live.deep_eq(&edited));
+        assert!(live.diff(&edited).is_empty());
+
+        exchange_updates(&[&d1, &d2]);
+        let hr: XmlElement = live.get(3).unwrap();
+        assert_eq!(hr.get_attribute("style"), Some("dashed".to_string()));
+        assert!(remote.deep_eq(&live));
+    }
+
+    #[test]
+    fn text_diff() {
+        let doc = Doc::new();
+        let (src, dst) = {
+            let mut txn = doc.transact();
+            let src = txn.get_xml_element("src");
+            let dst = txn.get_xml_element("dst");
+            let text = src.push_text_back(&mut txn);
+            text.push(&mut txn, "kitten sitting");
+            text.insert_embed(&mut txn, 7, Any::Bool(true));
+            let text = dst.push_text_back(&mut txn);
+            text.push(&mut txn, "smitten knitting");
+            let mut italic = Attrs::new();
+            italic.insert("italic".into(), Any::Bool(true));
+            text.format(&mut txn, 0, 7, italic);
+            (src, dst)
+        };
+        assert!(src.diff(&src).is_empty());
+
+        let diff = src.diff(&dst);
+        match &diff.children[..] {
+            [XmlChange::Text(text)] => assert!(text.attributes.is_empty()),
+            other => panic!("unexpected diff: {:?}", other),
+        }
+        {
+            let mut txn = doc.transact();
+            src.apply_diff(&mut txn, &diff).unwrap();
+        }
+        assert!(src.deep_eq(&dst));
+        assert_eq!(src.to_string(), dst.to_string());
+    }
+
+    #[test]
+    fn apply_mismatched_diff() {
+        let doc = Doc::new();
+        let (a, b, c) = {
+            let mut txn = doc.transact();
+            let a = txn.get_xml_fragment("a");
+            let b = txn.get_xml_fragment("b");
+            let c = txn.get_xml_fragment("c");
+            a.insert_xml_str(&mut txn, 0, "<p>hello</p>").unwrap();
+            b.insert_xml_str(&mut txn, 0, "<p>hello world</p><hr/>")
+                .unwrap();
+            c.insert_xml_str(&mut txn, 0, "plain text").unwrap();
+            (a, b, c)
+        };
+        let diff = a.diff(&b);
+
+        // diff computed for another structure is rejected without applying any of its changes
+        let mut txn = doc.transact();
+        assert_eq!(
+            c.apply_diff(&mut txn, &diff),
+            Err(XmlDiffError::NodeMismatch(0))
+        );
+        assert_eq!(c.to_string(), "plain text");
+        let empty = txn.get_xml_fragment("empty");
+        assert_eq!(
+            empty.apply_diff(&mut txn, &diff),
+            Err(XmlDiffError::NodeNotFound(0))
+        );
+        assert_eq!(empty.len(), 0);
+    }
+
+    #[test]
+    fn lcs_of_long_sequences() {
+        fn lcs_len(a: &[u32], b: &[u32]) -> usize {
+            let mut lengths = vec![vec![0; b.len() + 1]; a.len() + 1];
+            for i in (0..a.len()).rev() {
+                for j in (0..b.len()).rev() {
+                    lengths[i][j] = if a[i] == b[j] {
+                        lengths[i + 1][j + 1] + 1
+                    } else {
+                        lengths[i + 1][j].max(lengths[i][j + 1])
+                    };
+                }
+            }
+            lengths[0][0]
+        }
+
+        let mut seed = 7u32;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % 4
+        };
+        for len in [1, 2, 5, 17, 40, 100] {
+            let a: Vec<u32> = (0..len).map(|_| next()).collect();
+            let b: Vec<u32> = (0..len + len / 3).map(|_| next()).collect();
+            let pairs = lcs(&a, &b, |x, y| x == y);
+            assert_eq!(pairs.len(), lcs_len(&a, &b));
+            assert!(pairs.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+            assert!(pairs.iter().all(|&(i, j)| a[i] == b[j]));
+        }
+
+        // sequences too long for a dense table still produce a minimal diff
+        let a: Vec<u32> = (0..5000).collect();
+        let mut b = a.clone();
+        b.remove(1000);
+        b.insert(2500, 10_000);
+        b.remove(4000);
+        let pairs = lcs(&a, &b, |x, y| x == y);
+        assert_eq!(pairs.len(), 4998);
+    }
+}
@@ -0,0 +1,765 @@
+use std::fmt::Formatter;
+
+/// Error returned when a string passed to [XmlFragment::insert_xml_str] or
+/// [XmlFragment::insert_html_str] could not be parsed. Positions are byte offsets within an input
+/// string.
+///
+/// [XmlFragment::insert_xml_str]: crate::types::xml::XmlFragment::insert_xml_str
+/// [XmlFragment::insert_html_str]: crate::types::xml::XmlFragment::insert_html_str
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XmlParseError {
+    /// Input ended in the middle of a tag, comment or CDATA section.
+    UnexpectedEof,
+    /// Character at a given position is not allowed in the current context.
+    UnexpectedChar(usize),
+    /// Closing tag found at a given position doesn't match the innermost open element.
+    MismatchedTag {
+        position: usize,
+        expected: String,
+        found: String,
+    },
+    /// Entity reference found at a given position is neither predefined nor a valid character
+    /// reference.
+    UnknownEntity(usize),
+    /// Element with a given tag name was never closed.
+    UnclosedElement(String),
+}
+
+impl std::fmt::Display for XmlParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XmlParseError::UnexpectedEof => write!(f, "unexpected end of input"),
+            XmlParseError::UnexpectedChar(pos) => write!(f, "unexpected character at {}", pos),
+            XmlParseError::MismatchedTag {
+                position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected </{}> but found </{}> at {}",
+                expected, found, position
+            ),
+            XmlParseError::UnknownEntity(pos) => write!(f, "unknown entity at {}", pos),
+            XmlParseError::UnclosedElement(tag) => write!(f, "element <{}> was not closed", tag),
+        }
+    }
+}
+
+impl std::error::Error for XmlParseError {}
+
+/// A node of a parsed XML tree, which is about to be inserted into a Yrs XML collection.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum XmlNode {
+    Element {
+        tag: String,
+        attributes: Vec<(String, String)>,
+        children: Vec<XmlNode>,
+    },
+    Text(String),
+}
+
+/// HTML elements which never have any content nor a closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// HTML elements, which are implicitly closed when another element with the same tag is opened
+/// right inside of them, eg. `<li>a<li>b` is equivalent of `<li>a</li><li>b</li>`.
+const SELF_CLOSING_SIBLINGS: &[&str] = &["li", "p", "tr", "td", "th", "dt", "dd", "option"];
+
+/// HTML elements, which contents are not parsed as markup but treated as a text up to their
+/// closing tag. Entity references are decoded only within escapable ones.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+const ESCAPABLE_RAW_TEXT_ELEMENTS: &[&str] = &["textarea", "title"];
+
+/// Elements, inside of which whitespace is always significant.
+const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "textarea"];
+
+/// HTML elements rendered as blocks. Whitespace used to format the markup around them doesn't
+/// affect how a document is displayed.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// Parses a given `input` into a list of top-level XML nodes.
+///
+/// In strict mode an input must be a well-formed XML. In `html` mode parser tolerates common HTML
+/// quirks: void elements (like `<br>`), unquoted and valueless attributes, case-insensitive tag
+/// names, HTML named entities, stray closing tags, elements left unclosed and raw text contents
+/// of elements like `<script>` or `<style>`.
+///
+/// Comments, processing instructions and doctype declarations are skipped. Text nodes consisting
+/// only of whitespace and a line break are treated as formatting and skipped as well, but only
+/// at the beginning or the end of an element or next to block elements (like `<p>` or `<div>`).
+/// Whitespace is always preserved inside of `<pre>` and `<textarea>` elements and elements with
+/// `xml:space="preserve"` attribute.
+pub(crate) fn parse(input: &str, html: bool) -> Result<Vec<XmlNode>, XmlParseError> {
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        html,
+    };
+    parser.parse()
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    html: bool,
+}
+
+/// An element, which has been opened but not yet closed.
+struct OpenElement {
+    tag: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<XmlNode>,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(&mut self) -> Result<Vec<XmlNode>, XmlParseError> {
+        let mut root = Vec::new();
+        let mut stack: Vec<OpenElement> = Vec::new();
+        while self.pos < self.input.len() {
+            let rest = &self.input[self.pos..];
+            if rest.starts_with("<!--") {
+                self.skip_past(4, "-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                let start = self.pos + 9;
+                self.skip_past(9, "]]>")?;
+                let text = &self.input[start..self.pos - 3];
+                push_text(&mut root, &mut stack, text.to_string());
+            } else if rest.starts_with("<?") {
+                self.skip_past(2, "?>")?;
+            } else if rest.starts_with("<!") {
+                self.skip_past(2, ">")?;
+            } else if rest.starts_with("</") {
+                let position = self.pos;
+                self.pos += 2;
+                let tag = self.read_name()?;
+                self.skip_whitespace();
+                self.expect('>')?;
+                self.close(&mut root, &mut stack, position, tag)?;
+            } else if rest.starts_with('<') && self.is_tag_start(1) {
+                self.pos += 1;
+                let tag = self.read_name()?;
+                let (attributes, self_closed) = self.read_attributes()?;
+                let is_void = self.html && VOID_ELEMENTS.contains(&tag.as_str());
+                let closes_sibling = self.html && SELF_CLOSING_SIBLINGS.contains(&tag.as_str());
+                let is_raw_text = RAW_TEXT_ELEMENTS.contains(&tag.as_str());
+                let is_escapable_raw_text = ESCAPABLE_RAW_TEXT_ELEMENTS.contains(&tag.as_str());
+                if closes_sibling && stack.last().map(|e| e.tag == tag).unwrap_or(false) {
+                    let elem = stack.pop().unwrap();
+                    push_node(&mut root, &mut stack, elem.into());
+                }
+                let elem = OpenElement {
+                    tag,
+                    attributes,
+                    children: Vec::new(),
+                };
+                if self_closed || is_void {
+                    push_node(&mut root, &mut stack, elem.into());
+                } else if self.html && (is_raw_text || is_escapable_raw_text) {
+                    let text = self.read_raw_text(&elem.tag, is_escapable_raw_text)?;
+                    stack.push(elem);
+                    push_text(&mut root, &mut stack, text);
+                } else {
+                    stack.push(elem);
+                }
+            } else if rest.starts_with('<') && !self.html {
+                return Err(XmlParseError::UnexpectedChar(self.pos));
+            } else {
+                // in HTML mode a '<' which doesn't start a tag is a part of the text
+                let first = rest.chars().next().map(char::len_utf8).unwrap_or(1);
+                let len = rest[first..]
+                    .find('<')
+                    .map(|i| i + first)
+                    .unwrap_or(rest.len());
+                let text = self.decode(self.pos, &rest[..len])?;
+                self.pos += len;
+                push_text(&mut root, &mut stack, text);
+            }
+        }
+
+        if let Some(elem) = stack.pop() {
+            if !self.html {
+                return Err(XmlParseError::UnclosedElement(elem.tag));
+            }
+            // close all remaining elements
+            push_node(&mut root, &mut stack, elem.into());
+            while let Some(elem) = stack.pop() {
+                push_node(&mut root, &mut stack, elem.into());
+            }
+        }
+        finish(&mut root, false);
+
+        Ok(root)
+    }
+
+    /// Closes an innermost open element, making sure that its tag matches a closing `tag`.
+    fn close(
+        &self,
+        root: &mut Vec<XmlNode>,
+        stack: &mut Vec<OpenElement>,
+        position: usize,
+        tag: String,
+    ) -> Result<(), XmlParseError> {
+        if self.html {
+            // close all elements up to the matching one, ignore stray closing tags
+            if let Some(i) = stack.iter().rposition(|e| e.tag == tag) {
+                while stack.len() > i {
+                    let elem = stack.pop().unwrap();
+                    push_node(root, stack, elem.into());
+                }
+            }
+            Ok(())
+        } else {
+            match stack.pop() {
+                Some(elem) if elem.tag == tag => {
+                    push_node(root, stack, elem.into());
+                    Ok(())
+                }
+                Some(elem) => Err(XmlParseError::MismatchedTag {
+                    position,
+                    expected: elem.tag,
+                    found: tag,
+                }),
+                None => Err(XmlParseError::UnexpectedChar(position)),
+            }
+        }
+    }
+
+    /// Reads contents of a raw text element with a given `tag` up to its closing tag (or the end
+    /// of an input), without parsing any markup inside. Closing tag itself is not consumed.
+    fn read_raw_text(&mut self, tag: &str, escapable: bool) -> Result<String, XmlParseError> {
+        let rest = &self.input[self.pos..];
+        // ASCII lowercasing preserves byte offsets
+        let lowercase = rest.to_ascii_lowercase();
+        let mut from = 0;
+        let end = loop {
+            match lowercase[from..].find("</") {
+                None => break rest.len(),
+                Some(i) => {
+                    let name = &lowercase[from + i + 2..];
+                    let closes = name.starts_with(tag)
+                        && name[tag.len()..]
+                            .chars()
+                            .next()
+                            .map_or(true, |c| c.is_whitespace() || c == '>' || c == '/');
+                    if closes {
+                        break from + i;
+                    }
+                    from += i + 2;
+                }
+            }
+        };
+        let text = if escapable {
+            self.decode(self.pos, &rest[..end])?
+        } else {
+            rest[..end].to_string()
+        };
+        self.pos += end;
+        Ok(text)
+    }
+
+    fn is_tag_start(&self, offset: usize) -> bool {
+        self.input[self.pos + offset..]
+            .chars()
+            .next()
+            .map(is_name_start)
+            .unwrap_or(false)
+    }
+
+    /// Moves current position past the first occurrence of a given `end` delimiter, searching
+    /// from the `offset` relative to current position.
+    fn skip_past(&mut self, offset: usize, end: &str) -> Result<(), XmlParseError> {
+        match self.input[self.pos + offset..].find(end) {
+            Some(i) => {
+                self.pos += offset + i + end.len();
+                Ok(())
+            }
+            None => Err(XmlParseError::UnexpectedEof),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), XmlParseError> {
+        match self.peek() {
+            Some(next) if next == c => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            Some(_) => Err(XmlParseError::UnexpectedChar(self.pos)),
+            None => Err(XmlParseError::UnexpectedEof),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn read_name(&mut self) -> Result<String, XmlParseError> {
+        let rest = &self.input[self.pos..];
+        match rest.chars().next() {
+            Some(c) if is_name_start(c) => {}
+            Some(_) => return Err(XmlParseError::UnexpectedChar(self.pos)),
+            None => return Err(XmlParseError::UnexpectedEof),
+        }
+        let len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        self.pos += len;
+        let name = &rest[..len];
+        if self.html {
+            Ok(name.to_ascii_lowercase())
+        } else {
+            Ok(name.to_string())
+        }
+    }
+
+    /// Reads attributes of an opening tag up to its end. Returns parsed attributes and a flag
+    /// informing if a tag was self-closed, eg. `<br/>`.
+    fn read_attributes(&mut self) -> Result<(Vec<(String, String)>, bool), XmlParseError> {
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return Err(XmlParseError::UnexpectedEof),
+                Some('>') => {
+                    self.pos += 1;
+                    return Ok((attributes, false));
+                }
+                Some('/') => {
+                    self.pos += 1;
+                    self.expect('>')?;
+                    return Ok((attributes, true));
+                }
+                Some(_) => {
+                    let name = self.read_name()?;
+                    self.skip_whitespace();
+                    let value = if self.peek() == Some('=') {
+                        self.pos += 1;
+                        self.skip_whitespace();
+                        self.read_attribute_value()?
+                    } else if self.html {
+                        String::new()
+                    } else {
+                        return Err(XmlParseError::UnexpectedChar(self.pos));
+                    };
+                    attributes.push((name, value));
+                }
+            }
+        }
+    }
+
+    fn read_attribute_value(&mut self) -> Result<String, XmlParseError> {
+        let rest = &self.input[self.pos..];
+        match rest.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => {
+                let len = rest[1..].find(quote).ok_or(XmlParseError::UnexpectedEof)?;
+                let value = self.decode(self.pos + 1, &rest[1..len + 1])?;
+                self.pos += len + 2;
+                Ok(value)
+            }
+            Some(_) if self.html => {
+                let len = rest
+                    .find(|c: char| c.is_whitespace() || c == '>')
+                    .unwrap_or(rest.len());
+                let value = self.decode(self.pos, &rest[..len])?;
+                self.pos += len;
+                Ok(value)
+            }
+            Some(_) => Err(XmlParseError::UnexpectedChar(self.pos)),
+            None => Err(XmlParseError::UnexpectedEof),
+        }
+    }
+
+    /// Replaces entity references found in a given `text`, which starts at `offset` of an input.
+    fn decode(&self, offset: usize, text: &str) -> Result<String, XmlParseError> {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(i) = rest.find('&') {
+            result.push_str(&rest[..i]);
+            rest = &rest[i..];
+            let position = offset + text.len() - rest.len();
+            let decoded = rest
+                .find(';')
+                .and_then(|end| Some((decode_entity(&rest[1..end], self.html)?, end)));
+            match decoded {
+                Some((c, end)) => {
+                    result.push(c);
+                    rest = &rest[end + 1..];
+                }
+                None if self.html => {
+                    // unknown entities are left untouched
+                    result.push('&');
+                    rest = &rest[1..];
+                }
+                None => return Err(XmlParseError::UnknownEntity(position)),
+            }
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+}
+
+impl From<OpenElement> for XmlNode {
+    fn from(elem: OpenElement) -> Self {
+        XmlNode::Element {
+            tag: elem.tag,
+            attributes: elem.attributes,
+            children: elem.children,
+        }
+    }
+}
+
+fn push_node(root: &mut Vec<XmlNode>, stack: &mut [OpenElement], mut node: XmlNode) {
+    let preformatted = is_preformatted(stack);
+    if let XmlNode::Element {
+        tag,
+        attributes,
+        children,
+    } = &mut node
+    {
+        let preserve = preformatted || preserves_whitespace(tag, attributes);
+        finish(children, preserve);
+    }
+    let siblings = match stack.last_mut() {
+        Some(parent) => &mut parent.children,
+        None => root,
+    };
+    if !preformatted && is_block(&node) && siblings.last().map_or(false, is_formatting) {
+        siblings.pop();
+    }
+    siblings.push(node);
+}
+
+fn push_text(root: &mut Vec<XmlNode>, stack: &mut [OpenElement], text: String) {
+    if text.is_empty() {
+        return;
+    }
+    let preformatted = is_preformatted(stack);
+    let siblings = match stack.last_mut() {
+        Some(parent) => &mut parent.children,
+        None => root,
+    };
+    if !preformatted && is_formatting_text(&text) && siblings.last().map_or(true, is_block) {
+        // skip formatting whitespace at the beginning of an element or after a block
+        return;
+    }
+    // adjacent text nodes are merged once their parent is complete, until then formatting
+    // whitespace is kept apart, so that it can be removed if a block or closing tag follows
+    siblings.push(XmlNode::Text(text));
+}
+
+/// Removes formatting whitespace from the end of a complete list of `children` (unless it should
+/// be preserved) and merges adjacent text nodes.
+fn finish(children: &mut Vec<XmlNode>, preserve: bool) {
+    if !preserve && children.last().map_or(false, is_formatting) {
+        children.pop();
+    }
+    let mut merged: Vec<XmlNode> = Vec::with_capacity(children.len());
+    for node in children.drain(..) {
+        match (merged.last_mut(), node) {
+            (Some(XmlNode::Text(prev)), XmlNode::Text(text)) => prev.push_str(&text),
+            (_, node) => merged.push(node),
+        }
+    }
+    *children = merged;
+}
+
+/// Checks if any of the currently open elements requires its whitespace to be preserved.
+fn is_preformatted(stack: &[OpenElement]) -> bool {
+    stack
+        .iter()
+        .any(|e| preserves_whitespace(&e.tag, &e.attributes))
+}
+
+fn preserves_whitespace(tag: &str, attributes: &[(String, String)]) -> bool {
+    PREFORMATTED_ELEMENTS.contains(&tag)
+        || attributes
+            .iter()
+            .any(|(name, value)| name == "xml:space" && value == "preserve")
+}
+
+fn is_block(node: &XmlNode) -> bool {
+    match node {
+        XmlNode::Element { tag, .. } => BLOCK_ELEMENTS.contains(&tag.as_str()),
+        XmlNode::Text(_) => false,
+    }
+}
+
+fn is_formatting(node: &XmlNode) -> bool {
+    match node {
+        XmlNode::Text(text) => is_formatting_text(text),
+        XmlNode::Element { .. } => false,
+    }
+}
+
+fn is_formatting_text(text: &str) -> bool {
+    text.trim().is_empty() && text.contains('\n')
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == ':'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == ':' || c == '-' || c == '.'
+}
+
+/// Decodes a single entity `name` (without a leading `&` and trailing `;`).
+fn decode_entity(name: &str, html: bool) -> Option<char> {
+    if let Some(code) = name.strip_prefix('#') {
+        let code = match code.strip_prefix('x').or_else(|| code.strip_prefix('X')) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => code.parse().ok()?,
+        };
+        return std::char::from_u32(code);
+    }
+    let c = match name {
+        "lt" => '<',
+        "gt" => '>',
+        "amp" => '&',
+        "quot" => '"',
+        "apos" => '\'',
+        _ if !html => return None,
+        "nbsp" => '\u{a0}',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "hellip" => '…',
+        "mdash" => '—',
+        "ndash" => '–',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "bull" => '•',
+        "middot" => '·',
+        "deg" => '°',
+        "euro" => '€',
+        "times" => '×',
+        _ => return None,
+    };
+    Some(c)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::xml_parser::{parse, XmlNode, XmlParseError};
+
+    fn elem(tag: &str, attributes: &[(&str, &str)], children: Vec<XmlNode>) -> XmlNode {
+        XmlNode::Element {
+            tag: tag.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            children,
+        }
+    }
+
+    fn text(s: &str) -> XmlNode {
+        XmlNode::Text(s.to_string())
+    }
+
+    #[test]
+    fn parse_xml() {
+        let nodes = parse(
+            "<?xml version=\"1.0\"?>\n<doc lang='en'>\n  <p class=\"a &amp; b\">1 &lt; 2<br/></p>\n  <!-- comment --><![CDATA[<raw>]]>\n</doc>",
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            nodes,
+            vec![elem(
+                "doc",
+                &[("lang", "en")],
+                vec![
+                    elem(
+                        "p",
+                        &[("class", "a & b")],
+                        vec![text("1 < 2"), elem("br", &[], vec![])]
+                    ),
+                    text("<raw>"),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_xml_errors() {
+        assert_eq!(
+            parse("<p><b>text</p>", false),
+            Err(XmlParseError::MismatchedTag {
+                position: 10,
+                expected: "b".to_string(),
+                found: "p".to_string()
+            })
+        );
+        assert_eq!(
+            parse("<p>text", false),
+            Err(XmlParseError::UnclosedElement("p".to_string()))
+        );
+        assert_eq!(
+            parse("<p>&nbsp;</p>", false),
+            Err(XmlParseError::UnknownEntity(3))
+        );
+        assert_eq!(
+            parse("<p attr>", false),
+            Err(XmlParseError::UnexpectedChar(7))
+        );
+        assert_eq!(parse("<p", false), Err(XmlParseError::UnexpectedEof));
+    }
+
+    #[test]
+    fn parse_html() {
+        let nodes = parse(
+            "<UL><li>one<li>two &amp; a&nbsp;half</UL><p>a < b<br><input disabled value=x></div>",
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            nodes,
+            vec![
+                elem(
+                    "ul",
+                    &[],
+                    vec![
+                        elem("li", &[], vec![text("one")]),
+                        elem("li", &[], vec![text("two & a\u{a0}half")]),
+                    ]
+                ),
+                elem(
+                    "p",
+                    &[],
+                    vec![
+                        text("a < b"),
+                        elem("br", &[], vec![]),
+                        elem("input", &[("disabled", ""), ("value", "x")], vec![]),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_html_raw_text() {
+        let nodes = parse(
+            "<div><SCRIPT>if (a < b && c) { x = '</div>'; }</Script><style>p > a {}</style><title>a &amp; <b></title><script>",
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            nodes,
+            vec![elem(
+                "div",
+                &[],
+                vec![
+                    elem(
+                        "script",
+                        &[],
+                        vec![text("if (a < b && c) { x = '</div>'; }")]
+                    ),
+                    elem("style", &[], vec![text("p > a {}")]),
+                    elem("title", &[], vec![text("a & <b>")]),
+                    elem("script", &[], vec![]),
+                ]
+            )]
+        );
+
+        // raw text elements have no special meaning in XML
+        let nodes = parse("<script>a<b/></script>", false).unwrap();
+        assert_eq!(
+            nodes,
+            vec![elem("script", &[], vec![text("a"), elem("b", &[], vec![])])]
+        );
+    }
+
+    #[test]
+    fn parse_whitespace() {
+        // whitespace between inline elements is significant
+        let input = "<div>\n  <p><b>a</b>\n<i>b</i>\n</p>\n  <p>c\n  </p>\n</div>\n";
+        let expected = vec![elem(
+            "div",
+            &[],
+            vec![
+                elem(
+                    "p",
+                    &[],
+                    vec![
+                        elem("b", &[], vec![text("a")]),
+                        text("\n"),
+                        elem("i", &[], vec![text("b")]),
+                    ],
+                ),
+                elem("p", &[], vec![text("c\n  ")]),
+            ],
+        )];
+        assert_eq!(parse(input, true).unwrap(), expected);
+        assert_eq!(parse(input, false).unwrap(), expected);
+
+        // whitespace inside of preformatted elements is always preserved
+        let input = "<pre>\n<b>a</b>\n  \n</pre><textarea>\n</textarea>";
+        let expected = vec![
+            elem(
+                "pre",
+                &[],
+                vec![text("\n"), elem("b", &[], vec![text("a")]), text("\n  \n")],
+            ),
+            elem("textarea", &[], vec![text("\n")]),
+        ];
+        assert_eq!(parse(input, true).unwrap(), expected);
+        assert_eq!(parse(input, false).unwrap(), expected);
+
+        let nodes = parse("<code xml:space=\"preserve\">\n</code>", false).unwrap();
+        assert_eq!(
+            nodes,
+            vec![elem("code", &[("xml:space", "preserve")], vec![text("\n")])]
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use crate::types::xml::{TreeWalker, Xml, XmlElement};
+use crate::types::Branch;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+/// Error returned when a CSS-like selector string could not be parsed. Positions are byte offsets
+/// within a selector string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectorError {
+    /// Selector (or one of the comma-separated selectors) is empty.
+    Empty,
+    /// Selector ended in the middle of an attribute filter or after a combinator.
+    UnexpectedEof,
+    /// Character at a given position is not allowed in the current context.
+    UnexpectedChar(usize),
+}
+
+impl std::fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectorError::Empty => write!(f, "empty selector"),
+            SelectorError::UnexpectedEof => write!(f, "unexpected end of selector"),
+            SelectorError::UnexpectedChar(pos) => write!(f, "unexpected character at {}", pos),
+        }
+    }
+}
+
+impl std::error::Error for SelectorError {}
+
+/// A parsed selector, which can be used to match [XmlElement]s. It supports a subset of CSS
+/// selector syntax:
+///
+/// - `p` matches elements with a `p` tag (tag names are case-sensitive), `*` matches any element.
+/// - `[attr]` matches elements having an `attr` attribute, while `[attr=value]` matches elements
+///   which `attr` is equal to a `value` (which can be quoted). `[attr~=value]`, `[attr^=value]`,
+///   `[attr$=value]` and `[attr*=value]` match attribute values containing `value` as a
+///   whitespace-separated word, prefix, suffix or substring respectively.
+/// - `div p` matches `p` elements being successors of a `div` element, while `div > p` matches
+///   only `p` elements being direct children of a `div` element.
+/// - `h1, h2` matches elements matching any of the comma-separated selectors.
+///
+/// Example:
+/// ```
+/// use yrs::types::xml_selector::XmlSelector;
+///
+/// let selector = XmlSelector::parse("ul > li[data-id], p b").unwrap();
+/// assert!(XmlSelector::parse("p >").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlSelector(Vec<ComplexSelector>);
+
+impl XmlSelector {
+    /// Parses a given selector string.
+    pub fn parse(selector: &str) -> Result<Self, SelectorError> {
+        let mut parser = Parser {
+            input: selector,
+            pos: 0,
+        };
+        parser.parse()
+    }
+
+    /// Checks if a given XML element is matched by a current selector. Ancestors of that element
+    /// are taken into account when checking child and descendant combinators.
+    pub fn matches(&self, elem: &XmlElement) -> bool {
+        self.0.iter().any(|complex| complex.matches(elem))
+    }
+}
+
+impl FromStr for XmlSelector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        XmlSelector::parse(s)
+    }
+}
+
+/// A sequence of compound selectors joined by combinators, eg. `div > p b`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ComplexSelector {
+    /// Right-most compound selector, which must match a checked element itself.
+    last: CompoundSelector,
+    /// Preceding compound selectors, starting from the closest one, each paired with a combinator
+    /// which joins it with a compound selector on its right.
+    ancestors: Vec<(Combinator, CompoundSelector)>,
+}
+
+impl ComplexSelector {
+    fn matches(&self, elem: &XmlElement) -> bool {
+        self.last.matches(elem) && self.matches_ancestors(elem, 0)
+    }
+
+    /// Checks if compound selectors starting from `index` are matched by ancestors of a given
+    /// element. Descendant combinator may require backtracking, eg. `a > b c` must try every
+    /// `b` ancestor of `c` until one of them has an `a` parent.
+    fn matches_ancestors(&self, elem: &XmlElement, index: usize) -> bool {
+        let (combinator, compound) = match self.ancestors.get(index) {
+            None => return true,
+            Some(entry) => entry,
+        };
+        let mut current = elem.parent();
+        while let Some(ancestor) = current {
+            if compound.matches(&ancestor) && self.matches_ancestors(&ancestor, index + 1) {
+                return true;
+            }
+            if *combinator == Combinator::Child {
+                break;
+            }
+            current = ancestor.parent();
+        }
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    /// `a b`: right element is any successor of a left one.
+    Descendant,
+    /// `a > b`: right element is a direct child of a left one.
+    Child,
+}
+
+/// A tag name (`None` for `*`) optionally followed by attribute filters, eg. `p[data-id]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CompoundSelector {
+    tag: Option<String>,
+    attributes: Vec<AttributeSelector>,
+}
+
+impl CompoundSelector {
+    fn matches(&self, elem: &XmlElement) -> bool {
+        let branch: &Branch = elem.as_ref();
+        let tag = match branch.name.as_deref() {
+            Some(tag) => tag,
+            None => return false, // root types are not matched by any selector
+        };
+        if let Some(expected) = &self.tag {
+            if expected != tag {
+                return false;
+            }
+        }
+        self.attributes.iter().all(|attr| attr.matches(elem))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AttributeSelector {
+    name: String,
+    op: Option<(AttributeOp, String)>,
+}
+
+impl AttributeSelector {
+    fn matches(&self, elem: &XmlElement) -> bool {
+        let value = match elem.get_attribute(&self.name) {
+            Some(value) => value,
+            None => return false,
+        };
+        match &self.op {
+            None => true,
+            Some((AttributeOp::Equal, expected)) => &value == expected,
+            Some((AttributeOp::Word, expected)) => value.split_whitespace().any(|w| w == expected),
+            Some((AttributeOp::Prefix, expected)) => value.starts_with(expected.as_str()),
+            Some((AttributeOp::Suffix, expected)) => value.ends_with(expected.as_str()),
+            Some((AttributeOp::Contains, expected)) => value.contains(expected.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttributeOp {
+    /// `[attr=value]`
+    Equal,
+    /// `[attr~=value]`
+    Word,
+    /// `[attr^=value]`
+    Prefix,
+    /// `[attr$=value]`
+    Suffix,
+    /// `[attr*=value]`
+    Contains,
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(&mut self) -> Result<XmlSelector, SelectorError> {
+        let mut selectors = vec![self.parse_complex()?];
+        while self.peek().is_some() {
+            // parse_complex stops only at the end of input or at a comma
+            self.pos += 1;
+            selectors.push(self.parse_complex()?);
+        }
+        Ok(XmlSelector(selectors))
+    }
+
+    fn parse_complex(&mut self) -> Result<ComplexSelector, SelectorError> {
+        self.skip_whitespace();
+        let mut compounds = Vec::new();
+        let mut combinators = Vec::new();
+        match self.peek() {
+            None | Some(',') => return Err(SelectorError::Empty),
+            _ => compounds.push(self.parse_compound()?),
+        }
+        loop {
+            let had_whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                None | Some(',') => break,
+                Some('>') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    Combinator::Child
+                }
+                Some(_) if had_whitespace => Combinator::Descendant,
+                Some(_) => return Err(SelectorError::UnexpectedChar(self.pos)),
+            };
+            match self.peek() {
+                None => return Err(SelectorError::UnexpectedEof),
+                Some(',') => return Err(SelectorError::UnexpectedChar(self.pos)),
+                Some(_) => {}
+            }
+            combinators.push(combinator);
+            compounds.push(self.parse_compound()?);
+        }
+        let last = compounds.pop().unwrap();
+        let ancestors = combinators
+            .into_iter()
+            .rev()
+            .zip(compounds.into_iter().rev())
+            .collect();
+        Ok(ComplexSelector { last, ancestors })
+    }
+
+    fn parse_compound(&mut self) -> Result<CompoundSelector, SelectorError> {
+        let tag = match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                None
+            }
+            Some('[') => None,
+            _ => Some(self.read_name()?),
+        };
+        let mut attributes = Vec::new();
+        while self.peek() == Some('[') {
+            self.pos += 1;
+            attributes.push(self.parse_attribute()?);
+        }
+        Ok(CompoundSelector { tag, attributes })
+    }
+
+    fn parse_attribute(&mut self) -> Result<AttributeSelector, SelectorError> {
+        self.skip_whitespace();
+        let name = self.read_name()?;
+        self.skip_whitespace();
+        let op = match self.peek() {
+            Some(']') => None,
+            Some('=') => Some(AttributeOp::Equal),
+            Some('~') => Some(AttributeOp::Word),
+            Some('^') => Some(AttributeOp::Prefix),
+            Some('$') => Some(AttributeOp::Suffix),
+            Some('*') => Some(AttributeOp::Contains),
+            Some(_) => return Err(SelectorError::UnexpectedChar(self.pos)),
+            None => return Err(SelectorError::UnexpectedEof),
+        };
+        let op = match op {
+            None => None,
+            Some(op) => {
+                if op != AttributeOp::Equal {
+                    self.pos += 1;
+                }
+                self.expect('=')?;
+                self.skip_whitespace();
+                let value = self.read_value()?;
+                self.skip_whitespace();
+                Some((op, value))
+            }
+        };
+        self.expect(']')?;
+        Ok(AttributeSelector { name, op })
+    }
+
+    fn read_name(&mut self) -> Result<String, SelectorError> {
+        let rest = &self.input[self.pos..];
+        let len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return match rest.chars().next() {
+                Some(_) => Err(SelectorError::UnexpectedChar(self.pos)),
+                None => Err(SelectorError::UnexpectedEof),
+            };
+        }
+        self.pos += len;
+        Ok(rest[..len].to_string())
+    }
+
+    /// Reads an attribute value, which is either a quoted string or a name.
+    fn read_value(&mut self) -> Result<String, SelectorError> {
+        match self.peek() {
+            Some(quote) if quote == '"' || quote == '\'' => {
+                let start = self.pos + 1;
+                match self.input[start..].find(quote) {
+                    Some(len) => {
+                        self.pos = start + len + 1;
+                        Ok(self.input[start..start + len].to_string())
+                    }
+                    None => Err(SelectorError::UnexpectedEof),
+                }
+            }
+            _ => self.read_name(),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SelectorError> {
+        match self.peek() {
+            Some(found) if found == c => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            Some(_) => Err(SelectorError::UnexpectedChar(self.pos)),
+            None => Err(SelectorError::UnexpectedEof),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    /// Skips whitespace characters, returning `true` if any have been skipped.
+    fn skip_whitespace(&mut self) -> bool {
+        let rest = &self.input[self.pos..];
+        let len = rest.len() - rest.trim_start().len();
+        self.pos += len;
+        len > 0
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == ':'
+}
+
+/// Iterator over XML elements matching a given [XmlSelector], returned by
+/// [XmlFragment::query_selector_all]. Elements are visited in a depth-first order.
+///
+/// [XmlFragment::query_selector_all]: crate::types::xml::XmlFragment::query_selector_all
+pub struct QuerySelectorAll<'a> {
+    walker: TreeWalker<'a>,
+    selector: XmlSelector,
+}
+
+impl<'a> QuerySelectorAll<'a> {
+    pub(crate) fn new(walker: TreeWalker<'a>, selector: XmlSelector) -> Self {
+        QuerySelectorAll { walker, selector }
+    }
+}
+
+impl<'a> Iterator for QuerySelectorAll<'a> {
+    type Item = Xml;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for node in self.walker.by_ref() {
+            if let Xml::Element(elem) = &node {
+                if self.selector.matches(elem) {
+                    return Some(node);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::xml_selector::{SelectorError, XmlSelector};
+    use crate::{Doc, Xml};
+
+    #[test]
+    fn parse_selector_errors() {
+        assert!(XmlSelector::parse("p > b[data-id], ul li[class~='a b']").is_ok());
+        assert_eq!(XmlSelector::parse(""), Err(SelectorError::Empty));
+        assert_eq!(XmlSelector::parse("p,,b"), Err(SelectorError::Empty));
+        assert_eq!(XmlSelector::parse("p >"), Err(SelectorError::UnexpectedEof));
+        assert_eq!(
+            XmlSelector::parse("p[id"),
+            Err(SelectorError::UnexpectedEof)
+        );
+        assert_eq!(
+            XmlSelector::parse("p[id!=a]"),
+            Err(SelectorError::UnexpectedChar(4))
+        );
+        assert_eq!(
+            XmlSelector::parse("p > > b"),
+            Err(SelectorError::UnexpectedChar(4))
+        );
+    }
+
+    #[test]
+    fn query_selector_all() {
+        let doc = Doc::new();
+        let f = doc.transact().get_xml_fragment("fragment");
+        {
+            let mut txn = doc.transact();
+            f.insert_xml_str(
+                &mut txn,
+                0,
+                concat!(
+                    r#"<div><p>a <b data-id="1">b</b> <i><b>c</b></i></p></div>"#,
+                    r#"<p class="x y"><b data-id="2">d</b></p><b data-id="3">e</b>"#,
+                ),
+            )
+            .unwrap();
+        }
+
+        let query = |selector: &str| -> Vec<String> {
+            f.query_selector_all(selector)
+                .unwrap()
+                .map(|node| match node {
+                    Xml::Element(elem) => elem.to_string(),
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            query("p > b[data-id]"),
+            vec![
+                r#"<b "data-id"="1">b</b>"#.to_string(),
+                r#"<b "data-id"="2">d</b>"#.to_string()
+            ]
+        );
+        assert_eq!(query("p b").len(), 3);
+        assert_eq!(query("div b").len(), 2);
+        assert_eq!(query("div > p > i > b"), vec!["<b>c</b>".to_string()]);
+        assert_eq!(query("b").len(), 4);
+        assert_eq!(query("[data-id='3']"), vec![r#"<b "data-id"="3">e</b>"#]);
+        assert_eq!(query("p[class~=y] *").len(), 1);
+        assert_eq!(query("p[class^=x], i").len(), 2);
+        assert!(query("p > p").is_empty());
+
+        let div = f.query_selector("div").unwrap().unwrap();
+        let div = match div {
+            Xml::Element(elem) => elem,
+            _ => unreachable!(),
+        };
+        // ancestors above the queried element are still taken into account
+        assert_eq!(div.query_selector_all("div b").unwrap().count(), 2);
+        assert_eq!(div.query_selector_all("p").unwrap().count(), 1);
+        assert!(f.query_selector_all("p >").is_err());
+    }
+}
@@ -329,6 +329,16 @@ impl Update {
                     _ => {}
                 }
 
+                if let ItemContent::Type(branch) = &item.content {
+                    if let Some(source) = branch.link_source.as_ref() {
+                        for id in [&source.quote_start.id, &source.quote_end.id] {
+                            if id.clock >= local_sv.get(&id.client) {
+                                return Some(id.client);
+                            }
+                        }
+                    }
+                }
+
                 if let ItemContent::Move(m) = &item.content {
                     let start = m.start.id;
                     if start.clock >= local_sv.get(&start.client) {
@@ -2820,7 +2820,12 @@ fn value_into_js(v: Value) -> JsValue {
         Value::YArray(v) => YArray::from(v).into(),
         Value::YMap(v) => YMap::from(v).into(),
         Value::YXmlElement(v) => YXmlElement(v).into(),
+        Value::YXmlFragment(v) => JsValue::from(v.to_string()),
         Value::YXmlText(v) => YXmlText(v).into(),
+        Value::YXmlHook(v) => YMap::from(Map::from(v)).into(),
+        Value::YWeakLink(v) => any_into_js(&v.to_json()),
+        Value::YCounter(v) => any_into_js(&v.to_json()),
+        Value::YTree(v) => any_into_js(&v.to_json()),
     }
 }
 
@@ -2828,6 +2833,7 @@ fn xml_into_js(v: Xml) -> JsValue {
     match v {
         Xml::Element(v) => YXmlElement(v).into(),
         Xml::Text(v) => YXmlText(v).into(),
+        Xml::Hook(v) => YMap::from(Map::from(v)).into(),
     }
 }
 
@@ -2840,6 +2846,13 @@ fn events_into_js(txn: &Transaction, e: &Events) -> JsValue {
             Event::Map(e) => YMapEvent::new(e, txn).into(),
             Event::XmlElement(e) => YXmlEvent::new(e, txn).into(),
             Event::XmlText(e) => YXmlTextEvent::new(e, txn).into(),
+            // weak links, counters, trees, XML fragments and XML hooks are not exposed as shared
+            // types to JavaScript yet
+            Event::Weak(_)
+            | Event::Counter(_)
+            | Event::Tree(_)
+            | Event::XmlFragment(_)
+            | Event::XmlHook(_) => JsValue::UNDEFINED,
         };
         js
     });